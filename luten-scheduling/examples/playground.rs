@@ -1,9 +1,9 @@
-extern crate timeslot_algo;
+extern crate luten_scheduling;
 
 
 fn main() {
 
 
-    let solution = timeslot_algo::solve(unimplemented!());
+    let solution = luten_scheduling::solve(unimplemented!());
     println!("{:?}", solution);
 }
@@ -19,6 +19,13 @@ macro_rules! instance {
                 ];
             )*
         }
+        rooms: {
+            $(
+                $rname:expr, $capacity:expr => [
+                    $( $room_day:ident $room_slot:expr, )*
+                ];
+            )*
+        }
     ) => {{
         let mut tutors = Vec::new();
 
@@ -44,9 +51,30 @@ macro_rules! instance {
             }
         )*
 
+        let mut rooms = Vec::new();
+
+        $(
+            {
+                let mut available_slots = ::std::collections::HashSet::new();
+                $(
+                    available_slots.insert(Timeslot {
+                        day: WorkDay::$room_day,
+                        slot_of_day: $room_slot,
+                    });
+                )*
+
+                rooms.push(Room {
+                    name: $rname.into(),
+                    capacity: $capacity,
+                    available_slots: available_slots,
+                });
+            }
+        )*
+
         Instance {
             students: students,
             tutors: tutors,
+            rooms: rooms,
         }
 
     }};
@@ -101,6 +129,14 @@ pub fn small_instance0() -> Instance {
                 Good: Monday 1,
             ];
         }
+        rooms: {
+            "R1", 6 => [
+                Monday 0,
+                Monday 1,
+                Monday 2,
+                Monday 3,
+            ];
+        }
     }
     // expected solution:
     // Monday 0: Tobias - Lisa
@@ -138,6 +174,15 @@ pub fn small_instance1() -> Instance {
                 Good: Wednesday 0,
             ];
         }
+        rooms: {
+            "R1", 3 => [
+                Tuesday 0,
+            ];
+            "R2", 3 => [
+                Wednesday 0,
+                Thursday 0,
+            ];
+        }
     }
     // expected solution:
     // Tuesday:     T1 - S3, S4
@@ -132,10 +132,12 @@ impl<T, U, V> IndependentSample<SlotAssignment> for RatingDistribution<T, U, V>
 /// The `student_rating_distribution` contains probability distributions that are used to sample
 /// a `SlotRating` for each generated student. The same for `tutor_rating_distribution` for the
 /// tutors. With 0 <=`team_percentage` <= 1 one can adust how many students have preferred partners
-/// that they want to be in a team with vs. how many students are 'single'.
+/// that they want to be in a team with vs. how many students are 'single'. The given `rooms` are
+/// used as they are.
 pub fn random_instance<T, U, V, W, X, Y>(
     no_of_students: u64,
     no_of_tutors: u64,
+    rooms: Vec<Room>,
     student_rating_distribution: &mut RatingDistribution<T, U, V>,
     tutor_rating_distribution: &mut RatingDistribution<W, X, Y>,
     team_percentage: f64,
@@ -191,5 +193,6 @@ pub fn random_instance<T, U, V, W, X, Y>(
     Instance {
         students: students,
         tutors: tutors,
+        rooms: rooms,
     }
 }
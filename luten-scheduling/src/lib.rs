@@ -4,7 +4,7 @@ pub mod instances;
 pub mod types;
 pub mod util;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use types::*;
 
@@ -74,9 +74,135 @@ pub fn is_valid_solution(instance: &Instance, solution: &Solution) -> Result<(),
     }
 
 
+    let rooms_available = solution.testats.iter()
+        .all(|testat| {
+            instance.rooms.iter()
+                .any(|r| r.name == testat.room && r.is_available(testat.slot))
+        });
+    if !rooms_available {
+        errs.push("Some Testats take place in rooms that don't exist or aren't available at \
+            that time.".into())
+    }
+
+
+    let rooms_not_overfull = {
+        let mut seats = HashMap::new();
+        for testat in &solution.testats {
+            *seats.entry((testat.slot, &testat.room)).or_insert(0) += testat.seats();
+        }
+
+        seats.iter().all(|(&(_, name), &needed)| {
+            instance.rooms.iter()
+                .filter(|r| r.name == *name)
+                .all(|r| needed <= r.capacity)
+        })
+    };
+    if !rooms_not_overfull {
+        errs.push("Some rooms have more people in them than seats.".into())
+    }
+
+
     if errs.is_empty() {
         Ok(())
     } else {
         Err(errs)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use instances::{small_instance0, small_instance1};
+
+    fn student(instance: &Instance, name: &str) -> Student {
+        instance.students.iter().find(|s| s.name == name).unwrap().clone()
+    }
+
+    fn tutor(instance: &Instance, name: &str) -> Tutor {
+        instance.tutors.iter().find(|t| t.name == name).unwrap().clone()
+    }
+
+    fn testat(instance: &Instance, slot: (WorkDay, u16), tutor_name: &str, team: Team, room: &str) -> Testat {
+        Testat {
+            slot: Timeslot { day: slot.0, slot_of_day: slot.1 },
+            tutor: tutor(instance, tutor_name),
+            team,
+            room: room.into(),
+        }
+    }
+
+    /// The expected solution of `small_instance0()`.
+    fn solution0(instance: &Instance) -> Solution {
+        let lisa = Team::Single(student(instance, "Lisa"));
+        let willi_susi = Team::Full(student(instance, "Willi"), student(instance, "Susi"));
+
+        Solution {
+            testats: vec![
+                testat(instance, (WorkDay::Monday, 0), "Tobias", lisa, "R1"),
+                testat(instance, (WorkDay::Monday, 1), "Tobias", willi_susi, "R1"),
+            ],
+        }
+    }
+
+    /// The expected solution of `small_instance1()`.
+    fn solution1(instance: &Instance) -> Solution {
+        let s3_s4 = Team::Full(student(instance, "S3"), student(instance, "S4"));
+        let s1_s2 = Team::Full(student(instance, "S1"), student(instance, "S2"));
+
+        Solution {
+            testats: vec![
+                testat(instance, (WorkDay::Tuesday, 0), "T1", s3_s4, "R1"),
+                testat(instance, (WorkDay::Wednesday, 0), "T2", s1_s2, "R2"),
+            ],
+        }
+    }
+
+    #[test]
+    fn expected_solutions_are_valid() {
+        let instance = small_instance0();
+        assert_eq!(is_valid_solution(&instance, &solution0(&instance)), Ok(()));
+
+        let instance = small_instance1();
+        assert_eq!(is_valid_solution(&instance, &solution1(&instance)), Ok(()));
+    }
+
+    #[test]
+    fn overfull_room() {
+        // The team of two and the tutor need three seats.
+        let mut instance = small_instance0();
+        instance.rooms[0].capacity = 2;
+        let errs = is_valid_solution(&instance, &solution0(&instance)).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(errs[0].contains("more people in them than seats"));
+
+        // Two testats in the same room at the same time
+        let instance = small_instance1();
+        let mut solution = solution1(&instance);
+        solution.testats[1].slot = solution.testats[0].slot;
+        solution.testats[1].room = "R1".into();
+        let errs = is_valid_solution(&instance, &solution).unwrap_err();
+        assert!(errs.iter().any(|e| e.contains("more people in them than seats")));
+    }
+
+    #[test]
+    fn unavailable_room() {
+        // R1 can only be used on Tuesday.
+        let instance = small_instance1();
+        let mut solution = solution1(&instance);
+        solution.testats[1].room = "R1".into();
+        let errs = is_valid_solution(&instance, &solution).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(errs[0].contains("aren't available"));
+    }
+
+    #[test]
+    fn unknown_room() {
+        let instance = small_instance0();
+        let mut solution = solution0(&instance);
+        solution.testats[0].room = "R42".into();
+        let errs = is_valid_solution(&instance, &solution).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert!(errs[0].contains("don't exist"));
+    }
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
@@ -72,10 +72,29 @@ pub struct Student {
 }
 
 
+/// A room in which testats take place.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Room {
+    pub name: String,
+    /// Number of seats. Each testat in the room needs one seat per student
+    /// plus one for the tutor.
+    pub capacity: u16,
+    /// The timeslots in which the room can be used.
+    pub available_slots: HashSet<Timeslot>,
+}
+
+impl Room {
+    pub fn is_available(&self, slot: Timeslot) -> bool {
+        self.available_slots.contains(&slot)
+    }
+}
+
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Instance {
     pub students: Vec<Student>,
     pub tutors: Vec<Tutor>,
+    pub rooms: Vec<Room>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -94,6 +113,14 @@ impl Team {
         }
     }
 
+    /// Number of students in the team.
+    pub fn size(&self) -> u16 {
+        match *self {
+            Team::Single(_) => 1,
+            Team::Full(_, _) => 2,
+        }
+    }
+
     pub fn contains(&self, s: &Student) -> bool {
         match *self {
             Team::Single(ref s1) => s1 == s,
@@ -107,6 +134,16 @@ pub struct Testat {
     pub slot: Timeslot,
     pub tutor: Tutor,
     pub team: Team,
+    /// The name of the room.
+    pub room: String,
+}
+
+impl Testat {
+    /// Number of seats needed in the room: one per student and one for the
+    /// tutor.
+    pub fn seats(&self) -> u16 {
+        self.team.size() + 1
+    }
 }
 
 #[derive(Clone, PartialEq, Debug)]
@@ -0,0 +1,4 @@
+alter table if exists sessions
+    drop column if exists last_use,
+    drop column if exists user_agent,
+    drop column if exists ip_address;
@@ -0,0 +1,14 @@
+alter table sessions
+    -- When the session was last used to authenticate a request. To avoid a
+    -- write on every request, this is only updated every few minutes (see
+    -- `config::SESSION_LAST_USE_UPDATE_INTERVAL`).
+    add column last_use timestamptz
+        not null
+        default now(),
+
+    -- The `User-Agent` header of the login request. Shown to the user to help
+    -- them recognize their sessions.
+    add column user_agent text,
+
+    -- The IP address from which the login request was sent.
+    add column ip_address text;
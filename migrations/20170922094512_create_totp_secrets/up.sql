@@ -0,0 +1,44 @@
+create table totp_secrets (
+    user_id bigint
+        primary key
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    -- The shared secret (RFC 6238) the user stored in their authenticator
+    -- app.
+    secret bytea
+        not null,
+
+    -- The last time step for which a code was accepted. Codes of this or an
+    -- earlier time step are rejected, so that an observed code cannot be
+    -- used again.
+    last_used_step bigint
+        not null
+        default 0,
+
+    -- Number of wrong codes since the last correct one. If this gets too
+    -- large (see `config::TOTP_MAX_FAILED_ATTEMPTS`), TOTP codes are
+    -- rejected for a while.
+    failed_attempts integer
+        not null
+        default 0,
+
+    -- TOTP codes are rejected until then. Recovery codes are still accepted.
+    locked_until timestamptz
+);
+
+create table recovery_codes (
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    -- SHA-256 hash of the code. The codes are random, so a salt is not
+    -- necessary.
+    hash bytea
+        not null,
+
+    primary key (user_id, hash)
+);
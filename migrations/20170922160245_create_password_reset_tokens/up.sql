@@ -0,0 +1,16 @@
+create table password_reset_tokens (
+    -- SHA-256 hash of the token. The token itself is only part of the reset
+    -- link an admin passes on to the user.
+    hash bytea
+        primary key,
+
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    -- The token cannot be used after this point in time.
+    expires timestamptz
+        not null
+);
@@ -0,0 +1,3 @@
+alter table if exists sessions
+    drop column if exists impersonation_id;
+drop table if exists impersonations;
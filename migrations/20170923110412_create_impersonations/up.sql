@@ -0,0 +1,36 @@
+-- Admins can view the app as another user ("impersonation"). Every
+-- impersonation is recorded here. The records outlive the users involved:
+-- deleting a user only clears the reference to them.
+create table impersonations (
+    id bigserial
+        primary key,
+
+    -- The admin who impersonates another user. `null` if the admin was
+    -- deleted.
+    admin_id bigint
+        references users(id)
+            on delete set null
+            on update cascade,
+
+    -- The user who is impersonated. `null` if the user was deleted.
+    user_id bigint
+        references users(id)
+            on delete set null
+            on update cascade,
+
+    started timestamptz
+        not null
+        default now(),
+
+    -- When the admin stopped the impersonation. `null` while it's still
+    -- running (or if the session was removed otherwise).
+    ended timestamptz
+);
+
+alter table sessions
+    -- Sessions of impersonations belong to the impersonated user, but
+    -- reference the impersonation they were created for.
+    add column impersonation_id bigint
+        references impersonations(id)
+            on delete cascade
+            on update cascade;
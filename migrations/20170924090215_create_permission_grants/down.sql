@@ -0,0 +1,2 @@
+drop table if exists permission_grants;
+drop type if exists permission;
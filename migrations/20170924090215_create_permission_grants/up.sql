@@ -0,0 +1,26 @@
+-- See `user::permission::Permission`.
+create type permission as enum (
+    'view_admin_panel',
+    'manage_users',
+    'impersonate_users',
+    'manage_permissions',
+    'change_app_state',
+    'manage_timeslots',
+    'view_all_ratings',
+    'grade_students'
+);
+
+-- Permissions granted to single users in addition to the permissions of
+-- their role.
+create table permission_grants (
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    permission permission
+        not null,
+
+    primary key (user_id, permission)
+);
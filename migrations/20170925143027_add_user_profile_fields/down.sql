@@ -0,0 +1,5 @@
+alter table users
+    drop column semester,
+    drop column study_program,
+    drop column matriculation_number,
+    drop column email;
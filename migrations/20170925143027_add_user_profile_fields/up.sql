@@ -0,0 +1,8 @@
+-- Optional profile fields of users, see `user::profile`.
+alter table users
+    add column email text,
+    add column matriculation_number text
+        unique,
+    add column study_program text,
+    add column semester smallint
+        check (semester > 0);
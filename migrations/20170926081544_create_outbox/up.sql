@@ -0,0 +1,42 @@
+-- Emails which are waiting to be sent (or were sent), see `notify`.
+create table outbox (
+    id bigserial
+        primary key,
+
+    -- The user the email is sent to. The address is stored separately, so
+    -- that changing the address doesn't affect queued emails.
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    recipient text
+        not null,
+
+    subject text
+        not null,
+
+    body text
+        not null,
+
+    created timestamptz
+        not null
+        default now(),
+
+    -- Failed attempts are retried later, see `config::OUTBOX_MAX_ATTEMPTS`.
+    attempts integer
+        not null
+        default 0,
+
+    next_attempt timestamptz
+        not null
+        default now(),
+
+    last_error text,
+
+    -- `null` as long as the email wasn't sent.
+    sent timestamptz
+);
+
+create index outbox_pending on outbox (next_attempt) where sent is null;
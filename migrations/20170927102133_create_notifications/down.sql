@@ -0,0 +1,2 @@
+drop table if exists notifications;
+drop type if exists notification_kind;
@@ -0,0 +1,37 @@
+-- See `notify::Notification`.
+create type notification_kind as enum (
+    'preparation_started',
+    'schedule_ready',
+    'partner_chosen',
+    'partner_accepted'
+);
+
+-- Notifications shown in the inbox of a user, see `notify::inbox`.
+create table notifications (
+    id bigserial
+        primary key,
+
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    kind notification_kind
+        not null,
+
+    -- Additional data depending on the kind, e.g. the username of the partner.
+    argument text,
+
+    created timestamptz
+        not null
+        default now(),
+
+    -- Notifications are read when the inbox is opened. Dismissed
+    -- notifications are deleted.
+    read boolean
+        not null
+        default false
+);
+
+create index notifications_user_id on notifications (user_id);
@@ -0,0 +1,18 @@
+delete from permission_grants where permission = 'manage_announcements';
+alter type permission rename to permission_old;
+create type permission as enum (
+    'view_admin_panel',
+    'manage_users',
+    'impersonate_users',
+    'manage_permissions',
+    'change_app_state',
+    'manage_timeslots',
+    'view_all_ratings',
+    'grade_students'
+);
+alter table permission_grants
+    alter column permission type permission using permission::text::permission;
+drop type permission_old;
+
+drop table if exists dismissed_announcements;
+drop table if exists announcements;
@@ -0,0 +1,63 @@
+-- Announcements by admins, shown on every page. See `announcement`.
+create table announcements (
+    id bigserial
+        primary key,
+
+    -- Markdown, see `announcement::Announcement::render()`.
+    body text
+        not null,
+
+    -- Which roles the announcement is shown to.
+    for_students boolean
+        not null,
+    for_tutors boolean
+        not null,
+    for_admins boolean
+        not null,
+
+    -- The announcement is only shown between these points in time. `null`
+    -- means "no limit".
+    starts timestamptz,
+    ends timestamptz,
+
+    created timestamptz
+        not null
+        default now(),
+
+    check (starts is null or ends is null or starts < ends)
+);
+
+-- Announcements a user doesn't want to see anymore.
+create table dismissed_announcements (
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    announcement_id bigint
+        not null
+        references announcements(id)
+            on delete cascade
+            on update cascade,
+
+    primary key (user_id, announcement_id)
+);
+
+-- Values cannot be added to an enum type inside a transaction, so the type is
+-- recreated with the new permission.
+alter type permission rename to permission_old;
+create type permission as enum (
+    'view_admin_panel',
+    'manage_users',
+    'impersonate_users',
+    'manage_permissions',
+    'change_app_state',
+    'manage_timeslots',
+    'view_all_ratings',
+    'grade_students',
+    'manage_announcements'
+);
+alter table permission_grants
+    alter column permission type permission using permission::text::permission;
+drop type permission_old;
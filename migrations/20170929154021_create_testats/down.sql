@@ -0,0 +1,2 @@
+drop table if exists calendar_tokens;
+drop table if exists testats;
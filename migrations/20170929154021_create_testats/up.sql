@@ -0,0 +1,37 @@
+-- The schedule: in which timeslot and with which tutor a student has their
+-- testat every week. Partners are two rows with the same timeslot and tutor.
+create table testats (
+    student_id bigint
+        primary key
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    tutor_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    timeslot_id smallint
+        not null
+        references timeslots(id)
+            on delete cascade
+            on update cascade
+);
+
+create index testats_tutor_id on testats (tutor_id);
+
+-- Secret tokens of the calendar feeds, see `testat::ical`. The token is part
+-- of the feed URL, so it's stored as it is (unlike passwords).
+create table calendar_tokens (
+    user_id bigint
+        primary key
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    token text
+        not null
+        unique
+);
@@ -0,0 +1,2 @@
+drop table semester_days_off;
+drop table semester;
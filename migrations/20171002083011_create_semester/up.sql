@@ -0,0 +1,41 @@
+-- The current semester. See `semester`.
+create table semester (
+    -- Artificial PK to ensure this table only stores one single row
+    id bool
+        primary key
+        default TRUE,
+
+    -- A name shown to users, e.g. 'WS 2017/18'.
+    name text
+        not null,
+
+    -- The first and last day on which testats take place.
+    lecture_start date
+        not null,
+    lecture_end date
+        not null,
+
+    check (lecture_start <= lecture_end),
+
+    -- Make sure the id is true (combined with the unique-contraints, this
+    -- means that there is only one row).
+    constraint semester_one_row CHECK (id)
+);
+
+-- Days in the lecture period without testats, e.g. holidays or the christmas
+-- break. A single day has the same first and last day.
+create table semester_days_off (
+    id serial
+        primary key,
+
+    first_day date
+        not null,
+    last_day date
+        not null,
+
+    -- Shown to users, e.g. 'Christmas break'.
+    description text
+        not null,
+
+    check (first_day <= last_day)
+);
@@ -0,0 +1,44 @@
+-- Only the data of the first course is kept.
+delete from permission_grants where permission = 'manage_courses';
+alter type permission rename to permission_old;
+create type permission as enum (
+    'view_admin_panel',
+    'manage_users',
+    'impersonate_users',
+    'manage_permissions',
+    'change_app_state',
+    'manage_timeslots',
+    'view_all_ratings',
+    'grade_students',
+    'manage_announcements'
+);
+alter table permission_grants
+    alter column permission type permission using permission::text::permission;
+drop type permission_old;
+
+delete from testats where course_id <> (select min(id) from courses);
+alter table testats drop constraint testats_pkey;
+alter table testats drop column course_id;
+alter table testats add primary key (student_id);
+
+delete from prep_student_preferences where course_id <> (select min(id) from courses);
+alter table prep_student_preferences drop constraint prep_student_preferences_pkey;
+alter table prep_student_preferences drop column course_id;
+alter table prep_student_preferences add primary key (user_id);
+
+delete from current_app_state where course_id <> (select min(id) from courses);
+alter table current_app_state drop constraint current_app_state_pkey;
+alter table current_app_state drop column course_id;
+alter table current_app_state add column id bool primary key default true;
+alter table current_app_state add constraint current_app_state_one_row check (id);
+
+delete from timeslot_ratings where timeslot_id in (
+    select id from timeslots where course_id <> (select min(id) from courses)
+);
+delete from timeslots where course_id <> (select min(id) from courses);
+drop index timeslots_unique_daytime_idx;
+alter table timeslots drop column course_id;
+create unique index timeslots_unique_daytime_idx on timeslots (day, time);
+
+drop table course_members;
+drop table courses;
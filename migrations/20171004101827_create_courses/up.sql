@@ -0,0 +1,127 @@
+-- Courses. Timeslots, preferences, ratings, testats, the app state and the
+-- roles of students and tutors belong to one course. See `course`.
+create table courses (
+    id smallserial
+        primary key,
+
+    -- Used in URLs, e.g. 'info-a-ws17'.
+    slug text
+        not null
+        unique,
+
+    name text
+        not null,
+
+    -- Users logging in for the first time become members of these courses.
+    auto_enroll bool
+        not null
+        default false,
+
+    created timestamptz
+        not null
+        default now()
+);
+
+-- All existing data belongs to the first course.
+insert into courses (slug, name, auto_enroll)
+    values ('default', 'Default course', true);
+
+-- The role of users in a course. Admins are admins in every course, so they
+-- aren't members.
+create table course_members (
+    course_id smallint
+        not null
+        references courses(id)
+            on delete cascade
+            on update cascade,
+
+    user_id bigint
+        not null
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    role user_role
+        not null,
+
+    primary key (course_id, user_id),
+    check (role <> 'admin')
+);
+
+create index course_members_user_id on course_members (user_id);
+
+insert into course_members (course_id, user_id, role)
+    select (select id from courses where slug = 'default'), id, role
+    from users
+    where role <> 'admin';
+
+
+-- Timeslots
+alter table timeslots
+    add column course_id smallint
+        references courses(id)
+            on delete cascade
+            on update cascade;
+update timeslots set course_id = (select id from courses where slug = 'default');
+alter table timeslots alter column course_id set not null;
+
+drop index timeslots_unique_daytime_idx;
+create unique index timeslots_unique_daytime_idx on timeslots (course_id, day, time);
+
+
+-- The app state: one row per course instead of one single row.
+alter table current_app_state drop constraint current_app_state_one_row;
+alter table current_app_state drop constraint current_app_state_pkey;
+alter table current_app_state drop column id;
+alter table current_app_state
+    add column course_id smallint
+        references courses(id)
+            on delete cascade
+            on update cascade;
+update current_app_state set course_id = (select id from courses where slug = 'default');
+alter table current_app_state alter column course_id set not null;
+alter table current_app_state add primary key (course_id);
+
+
+-- Preferences of students
+alter table prep_student_preferences
+    add column course_id smallint
+        references courses(id)
+            on delete cascade
+            on update cascade;
+update prep_student_preferences set course_id = (select id from courses where slug = 'default');
+alter table prep_student_preferences alter column course_id set not null;
+alter table prep_student_preferences drop constraint prep_student_preferences_pkey;
+alter table prep_student_preferences add primary key (user_id, course_id);
+
+
+-- Testats: a student has one testat per course.
+alter table testats
+    add column course_id smallint
+        references courses(id)
+            on delete cascade
+            on update cascade;
+update testats set course_id = (select id from courses where slug = 'default');
+alter table testats alter column course_id set not null;
+alter table testats drop constraint testats_pkey;
+alter table testats add primary key (student_id, course_id);
+
+
+-- Values cannot be added to an enum type inside a transaction, so the type is
+-- recreated with the new permission.
+alter type permission rename to permission_old;
+create type permission as enum (
+    'view_admin_panel',
+    'manage_users',
+    'impersonate_users',
+    'manage_permissions',
+    'change_app_state',
+    'manage_timeslots',
+    'view_all_ratings',
+    'grade_students',
+    'manage_announcements',
+    'manage_courses'
+);
+alter table permission_grants
+    alter column permission type permission using permission::text::permission;
+drop type permission_old;
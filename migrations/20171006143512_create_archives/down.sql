@@ -0,0 +1,2 @@
+-- The schemas containing the archived data are kept.
+drop table archives;
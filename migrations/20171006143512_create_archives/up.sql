@@ -0,0 +1,25 @@
+-- Archives of finished semesters. The data itself is stored in a separate
+-- schema per archive, see `archive`.
+create table archives (
+    id serial
+        primary key,
+
+    -- `null` if the course was deleted later.
+    course_id smallint
+        references courses(id)
+            on delete set null
+            on update cascade,
+
+    course_name text
+        not null,
+
+    -- The schema containing the archived data, e.g.
+    -- 'archive_default_20180216120000'.
+    schema_name text
+        not null
+        unique,
+
+    created timestamptz
+        not null
+        default now()
+);
@@ -0,0 +1,3 @@
+alter table testats drop column room_id;
+drop table room_availability;
+drop table rooms;
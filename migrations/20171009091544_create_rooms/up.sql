@@ -0,0 +1,44 @@
+-- Rooms in which testats take place. See `room`.
+create table rooms (
+    id smallserial
+        primary key,
+
+    course_id smallint
+        not null
+        references courses(id)
+            on delete cascade
+            on update cascade,
+
+    name text
+        not null,
+
+    -- Number of seats
+    capacity smallint
+        not null
+        check (capacity > 0),
+
+    unique (course_id, name)
+);
+
+-- The timeslots in which a room can be used.
+create table room_availability (
+    room_id smallint
+        not null
+        references rooms(id)
+            on delete cascade
+            on update cascade,
+
+    timeslot_id smallint
+        not null
+        references timeslots(id)
+            on delete cascade
+            on update cascade,
+
+    primary key (room_id, timeslot_id)
+);
+
+alter table testats
+    add column room_id smallint
+        references rooms(id)
+            on delete set null
+            on update cascade;
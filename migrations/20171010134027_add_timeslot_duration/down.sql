@@ -0,0 +1 @@
+alter table timeslots drop column duration;
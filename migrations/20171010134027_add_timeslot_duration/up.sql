@@ -0,0 +1,7 @@
+-- The length of a timeslot in minutes. Until now, all timeslots were 30
+-- minutes long.
+alter table timeslots
+    add column duration smallint
+        not null
+        default 30
+        check (duration > 0);
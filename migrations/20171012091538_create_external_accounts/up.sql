@@ -0,0 +1,21 @@
+-- Accounts at external login providers (LDAP, OpenID Connect, SAML) which
+-- belong to a user. A user is identified by the provider and the ID the
+-- provider uses for them (e.g. the `sub` claim of OpenID Connect), not by
+-- the username. Each user can only be linked to one external account.
+create table external_accounts (
+    -- The ID of the login provider, e.g. `oidc`.
+    provider text
+        not null,
+
+    external_id text
+        not null,
+
+    user_id bigint
+        not null
+        unique
+        references users(id)
+            on delete cascade
+            on update cascade,
+
+    primary key (provider, external_id)
+);
@@ -1,10 +1,24 @@
+use chrono::{DateTime, Local, Utc};
 use maud::{html, Markup};
 use rocket::config::Config;
+use url::form_urlencoded;
 
+use announcement::Announcement;
+use archive::Archive;
 use config;
+use course::Course;
 use dict::{self, Locale};
+use login::Session;
+use login::impersonation::LogEntry;
+use prep::StudentPreferences;
+use room::Room;
+use semester::Calendar;
 use state::CurrentAppState;
-use timeslot::{DayOfWeek, TimeSlot};
+use timeslot::{DayOfWeek, Rating, TimeSlot};
+use user::{Role, User};
+use user::import::{ConflictKind, ImportPlan};
+use user::permission::Permission;
+use user::profile::ProfileField;
 
 
 pub fn index(locale: Locale, stats: &Stats, config: &Config) -> Markup {
@@ -13,8 +27,15 @@ pub fn index(locale: Locale, stats: &Stats, config: &Config) -> Markup {
     html! {
         h1 (dict.title())
         ul {
+            li a href="/admin_panel/users" (dict.users_title())
+            li a href="/admin_panel/courses" (dict.courses_title())
             li a href="/admin_panel/state" (dict.state_title())
             li a href="/admin_panel/timeslots" (dict.timeslots_title())
+            li a href="/admin_panel/rooms" (dict.rooms_title())
+            li a href="/admin_panel/semester" (dict.semester_title())
+            li a href="/admin_panel/impersonations" (dict.impersonations_title())
+            li a href="/admin_panel/permissions" (dict.permissions_title())
+            li a href="/admin_panel/announcements" (dict.announcements_title())
         }
 
         h2 (dict.statistics_headline())
@@ -24,12 +45,39 @@ pub fn index(locale: Locale, stats: &Stats, config: &Config) -> Markup {
             li { (dict.num_students()) ": " (stats.num_students) }
         }
 
+        h2 (dict.kill_sessions_headline())
+        p (dict.kill_sessions_explanation())
+        form action="/admin_panel/kill_sessions" method="post" {
+            div class="o-form-element" {
+                label class="c-label" for="kill-sessions-username" (dict.username())
+                input id="kill-sessions-username" name="username" class="c-field";
+            }
+            input
+                class="c-button c-button--error"
+                type="submit"
+                value=(dict.kill_sessions_button()) {}
+        }
+
+        h2 (dict.password_reset_headline())
+        p (dict.password_reset_explanation())
+        form action="/admin_panel/password_reset" method="post" {
+            div class="o-form-element" {
+                label class="c-label" for="password-reset-username" (dict.username())
+                input id="password-reset-username" name="username" class="c-field";
+            }
+            input
+                class="c-button c-button--warning"
+                type="submit"
+                value=(dict.password_reset_button()) {}
+        }
+
         h2 (dict.config_headline())
         ul {
             li tt { "WEBSITE_TITLE: " (config::WEBSITE_TITLE) }
             li tt { "SESSION_COOKIE_NAME: " (config::SESSION_COOKIE_NAME) }
             li tt { "SESSION_ID_LEN: " (config::SESSION_ID_LEN) }
             li tt { "INITIAL_REQ_COOKIE_NAME: " (config::INITIAL_REQ_COOKIE_NAME) }
+            li tt { "developers: " (config::FILE.developers.usernames.join(", ")) }
         }
         h3 "Rocket:"
         code (format!("{:#?}", config))
@@ -44,6 +92,553 @@ pub struct Stats {
     pub num_students: u64,
 }
 
+/// One page of the user list together with the search which led to it.
+pub struct UserList<'a> {
+    pub users: &'a [User],
+    pub query: &'a str,
+    pub role: Option<Role>,
+    pub page: usize,
+    pub num_pages: usize,
+}
+
+impl<'a> UserList<'a> {
+    /// The URL of another page of the same search.
+    fn page_url(&self, page: usize) -> String {
+        let mut query = form_urlencoded::Serializer::new(String::new());
+        query.append_pair("q", self.query);
+        query.append_pair("role", self.role.map_or("", |r| r.as_str()));
+        query.append_pair("page", &page.to_string());
+        format!("/admin_panel/users?{}", query.finish())
+    }
+}
+
+pub fn users(locale: Locale, list: &UserList) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    html! {
+        h1 (dict.users_title())
+
+        form action="/admin_panel/users" method="get" class="o-form-element" {
+            div class="c-input-group" {
+                div class="o-field" {
+                    input
+                        class="c-field"
+                        name="q"
+                        value=(list.query)
+                        placeholder=(dict.user_search_placeholder());
+                }
+                select class="c-field" name="role" {
+                    option value="" (dict.all_roles())
+                    @for role in Role::all() {
+                        option value=(role.as_str()) selected?[list.role == Some(*role)] (role.as_str())
+                    }
+                }
+                button class="c-button c-button--brand" type="submit" (dict.search())
+            }
+        }
+
+        table class="c-table" {
+            thead class="c-table__head" {
+                tr class="c-table__row c-table__row--heading" {
+                    th class="c-table__cell" (dict.username())
+                    th class="c-table__cell" (dict.name())
+                    th class="c-table__cell" (dict.role())
+                }
+            }
+            tbody class="c-table__body" {
+                @for user in list.users {
+                    tr class="c-table__row" {
+                        td class="c-table__cell" {
+                            a href=(format!("/admin_panel/users/{}", user.id())) (user.username())
+                        }
+                        td class="c-table__cell" (user.name().unwrap_or("–"))
+                        td class="c-table__cell" (user.role().as_str())
+                    }
+                }
+            }
+        }
+
+        @if list.num_pages > 1 {
+            p class="u-centered" {
+                @if list.page > 0 {
+                    a href=(list.page_url(list.page - 1)) "«"
+                    " "
+                }
+                (dict.page_of(list.page as u64 + 1, list.num_pages as u64))
+                @if list.page + 1 < list.num_pages {
+                    " "
+                    a href=(list.page_url(list.page + 1)) "»"
+                }
+            }
+        }
+
+        h2 (dict.create_user_headline())
+        p (dict.create_user_explanation())
+        form action="/admin_panel/users/create" method="post" style="max-width: 25em" {
+            div class="o-form-element" {
+                label class="c-label" for="new-username" (dict.username())
+                input id="new-username" name="username" class="c-field";
+            }
+            div class="o-form-element" {
+                label class="c-label" for="new-name" (dict.name())
+                input id="new-name" name="name" class="c-field";
+            }
+            div class="o-form-element" {
+                label class="c-label" for="new-role" (dict.role())
+                select id="new-role" class="c-field" name="role" {
+                    @for role in Role::all() {
+                        option value=(role.as_str()) selected?[*role == Role::Student] (role.as_str())
+                    }
+                }
+            }
+            div class="o-form-element" {
+                label class="c-label" for="new-password" (dict.password())
+                input id="new-password" type="password" name="password" class="c-field";
+            }
+            div class="o-form-element" {
+                label class="c-label" for="new-password-confirm" (dict.password_confirm())
+                input id="new-password-confirm" type="password" name="password_confirm" class="c-field";
+            }
+            input
+                class="c-button c-button--success"
+                type="submit"
+                value=(dict.create_user_button()) {}
+        }
+
+        h2 (dict.import_headline())
+        p (dict.import_explanation())
+        a class="c-button c-button--info" href="/admin_panel/users/import" (dict.import_link())
+    }
+}
+
+pub fn users_import(locale: Locale, csv: &str, role: Role) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    html! {
+        h1 (dict.import_headline())
+        p (dict.import_format_explanation())
+        form action="/admin_panel/users/import" method="post" {
+            div class="o-form-element" {
+                label class="c-label" for="import-csv" (dict.import_csv())
+                textarea id="import-csv" name="csv" class="c-field" rows="15" (csv)
+            }
+            div class="o-form-element" style="max-width: 25em" {
+                label class="c-label" for="import-role" (dict.import_role())
+                select id="import-role" class="c-field" name="role" {
+                    @for r in Role::all() {
+                        option value=(r.as_str()) selected?[*r == role] (r.as_str())
+                    }
+                }
+            }
+            input
+                class="c-button c-button--brand"
+                type="submit"
+                value=(dict.import_preview_button()) {}
+        }
+    }
+}
+
+fn conflict_reason(locale: Locale, kind: &ConflictKind) -> String {
+    let dict = dict::new(locale).admin_panel;
+
+    match *kind {
+        ConflictKind::DuplicateInFile => dict.import_conflict_duplicate(),
+        ConflictKind::RoleMismatch(role) => dict.import_conflict_role(role.as_str()),
+        ConflictKind::InvalidProfile(ref e) => e.msg(locale),
+        ConflictKind::MatriculationNumberTaken => dict.import_conflict_matriculation_number(),
+    }
+}
+
+pub fn users_import_preview(locale: Locale, plan: &ImportPlan, csv: &str) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    html! {
+        h1 (dict.import_preview_title())
+        p (dict.import_preview_summary(
+            plan.new_users.len() as u64,
+            plan.updates.len() as u64,
+            plan.unchanged.len() as u64,
+            plan.conflicts.len() as u64,
+        ))
+
+        @if !plan.conflicts.is_empty() {
+            h2 (dict.import_conflicts_headline())
+            table class="c-table" {
+                tbody class="c-table__body" {
+                    @for conflict in &plan.conflicts {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (conflict.row.line)
+                            td class="c-table__cell" (conflict.row.username)
+                            td class="c-table__cell" (conflict_reason(locale, &conflict.kind))
+                        }
+                    }
+                }
+            }
+        }
+
+        @if !plan.new_users.is_empty() {
+            h2 (dict.import_new_headline())
+            table class="c-table" {
+                tbody class="c-table__body" {
+                    @for row in &plan.new_users {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (row.username)
+                            td class="c-table__cell" (row.name.as_ref().map_or("–", |n| n.as_str()))
+                            td class="c-table__cell" (row.email.as_ref().map_or("–", |e| e.as_str()))
+                            td class="c-table__cell" {
+                                (row.matriculation_number.as_ref().map_or("–", |n| n.as_str()))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        @if !plan.updates.is_empty() {
+            h2 (dict.import_updates_headline())
+            table class="c-table" {
+                tbody class="c-table__body" {
+                    @for &(ref user, ref row) in &plan.updates {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (user.username())
+                            td class="c-table__cell" (user.name().unwrap_or("–"))
+                            td class="c-table__cell" "→"
+                            td class="c-table__cell" (row.name.as_ref().map_or("–", |n| n.as_str()))
+                            td class="c-table__cell" (row.email.as_ref().map_or("–", |e| e.as_str()))
+                            td class="c-table__cell" {
+                                (row.matriculation_number.as_ref().map_or("–", |n| n.as_str()))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        @if plan.is_noop() {
+            div class="c-alert c-alert--info" (dict.import_nothing_to_do())
+        } @else {
+            form action="/admin_panel/users/import/apply" method="post" class="u-letter-box--small" {
+                textarea name="csv" style="display: none" (csv)
+                input type="hidden" name="role" value=(plan.role.as_str());
+                input
+                    class="c-button c-button--success"
+                    type="submit"
+                    value=(dict.import_apply_button()) {}
+            }
+        }
+        a href="/admin_panel/users/import" (dict.import_back())
+    }
+}
+
+/// Everything shown on the detail page of a user.
+pub struct UserDetails {
+    pub user: User,
+    pub granted_permissions: Vec<Permission>,
+    pub has_password: bool,
+    pub sessions: Vec<Session>,
+
+    /// All courses with the role of the user in them.
+    pub memberships: Vec<(Course, Option<Role>)>,
+
+    /// The current course, which `preferences` and `ratings` belong to.
+    pub course: Option<Course>,
+    pub preferences: Option<StudentPreferences>,
+    pub ratings: Vec<(TimeSlot, Rating)>,
+}
+
+pub fn user_details(locale: Locale, details: &UserDetails) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+    let user = &details.user;
+    let base_url = format!("/admin_panel/users/{}", user.id());
+
+    html! {
+        h1 (user.username())
+        ul {
+            li { b (dict.role()) ": " (user.role().as_str()) }
+            li {
+                b (dict.permission())
+                ": "
+                (user.role().permissions().iter()
+                    .chain(&details.granted_permissions)
+                    .map(|p| p.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "))
+            }
+            li {
+                b (dict.internal_password())
+                ": "
+                @if details.has_password { (dict.yes()) } @else { (dict.no()) }
+            }
+        }
+
+        h2 (dict.edit_user_headline())
+        form action=(format!("{}/edit", base_url)) method="post" style="max-width: 25em" {
+            div class="o-form-element" {
+                label class="c-label" for="edit-name" (dict.name())
+                input id="edit-name" name="name" class="c-field" value=(user.name().unwrap_or(""));
+            }
+            div class="o-form-element" {
+                label class="c-label" for="edit-role" (dict.role())
+                select id="edit-role" class="c-field" name="role" {
+                    @for role in Role::all() {
+                        option value=(role.as_str()) selected?[*role == user.role()] (role.as_str())
+                    }
+                }
+            }
+            @for field in ProfileField::all() {
+                div class="o-form-element" {
+                    label class="c-label" for=(format!("edit-{}", field.as_str())) (field.label(locale))
+                    input
+                        id=(format!("edit-{}", field.as_str()))
+                        name=(field.as_str())
+                        class="c-field"
+                        value=(user.profile().field(*field).unwrap_or_default());
+                }
+            }
+            input
+                class="c-button c-button--success"
+                type="submit"
+                value=(dict::new(locale).save_form()) {}
+        }
+
+        h2 (dict.courses_title())
+        @if user.is_admin() {
+            p (dict.admin_in_all_courses())
+        } @else {
+            table class="c-table" {
+                tbody class="c-table__body" {
+                    @for &(ref course, role) in &details.memberships {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (course.name)
+                            td class="c-table__cell" {
+                                form
+                                    action=(format!("{}/course_role", base_url))
+                                    method="post"
+                                    class="c-input-group"
+                                {
+                                    input type="hidden" name="course" value=(course.slug);
+                                    select class="c-field" name="role" {
+                                        option value="" selected?[role.is_none()] (dict.no_member())
+                                        @for r in &[Role::Student, Role::Tutor] {
+                                            option value=(r.as_str()) selected?[role == Some(*r)] (r.as_str())
+                                        }
+                                    }
+                                    div class="o-field" {
+                                        input
+                                            class="c-button c-button--success"
+                                            type="submit"
+                                            value=(dict::new(locale).save_form()) {}
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        @if let Some(ref course) = details.course {
+            p { i (dict.data_of_course(&course.name)) }
+        }
+
+        @if let Some(ref pref) = details.preferences {
+            h2 (dict.preferences_headline())
+            ul {
+                li {
+                    b (dict.partner())
+                    ": "
+                    (pref.partner.as_ref().map_or("–", |p| p.as_str()))
+                }
+                li {
+                    b (dict.prefers_english())
+                    ": "
+                    @if pref.prefers_english { (dict.yes()) } @else { (dict.no()) }
+                }
+            }
+        }
+
+        h2 (dict.ratings_headline())
+        table class="c-table timeslot-table" {
+            tbody class="c-table__body" {
+                @for &(ref slot, ref rating) in &details.ratings {
+                    tr class="c-table__row" {
+                        td class="c-table__cell" (slot.day())
+                        td class="c-table__cell" (slot.time())
+                        td class="c-table__cell" (format!("{:?}", rating))
+                    }
+                }
+            }
+        }
+
+        h2 (dict.sessions_headline())
+        table class="c-table" {
+            tbody class="c-table__body" {
+                @for session in &details.sessions {
+                    tr class="c-table__row" {
+                        td class="c-table__cell" (session.birth.format("%d.%m.%Y %H:%M"))
+                        td class="c-table__cell" (session.last_use.format("%d.%m.%Y %H:%M"))
+                        td class="c-table__cell" (session.user_agent.as_ref().map_or("–", |s| s.as_str()))
+                        td class="c-table__cell" (session.ip_address.as_ref().map_or("–", |s| s.as_str()))
+                    }
+                }
+            }
+        }
+        form action="/admin_panel/kill_sessions" method="post" class="u-letter-box--small" {
+            input type="hidden" name="username" value=(user.username());
+            input
+                class="c-button c-button--error"
+                type="submit"
+                value=(dict.kill_sessions_button()) {}
+        }
+
+        @if details.has_password {
+            form action="/admin_panel/password_reset" method="post" class="u-letter-box--small" {
+                input type="hidden" name="username" value=(user.username());
+                input
+                    class="c-button c-button--warning"
+                    type="submit"
+                    value=(dict.password_reset_button()) {}
+            }
+        }
+
+        h2 (dict.delete_user_headline())
+        p (dict.delete_user_explanation())
+        form
+            action=(format!("{}/delete", base_url))
+            method="post"
+            onsubmit=(format!("return confirm('{}')", dict.delete_user_confirm()))
+        {
+            input
+                class="c-button c-button--error"
+                type="submit"
+                value=(dict.delete_user_button()) {}
+        }
+    }
+}
+
+pub fn password_reset_link(
+    locale: Locale,
+    user: &User,
+    token: &str,
+    expires: &DateTime<Utc>,
+) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+    let link = format!("/password_reset/{}", token);
+
+    html! {
+        h1 (dict.password_reset_title())
+        p (dict.password_reset_link_explanation(user.username(), &expires.to_string()))
+        pre class="c-code c-code--multiline" (link)
+        a href="/admin_panel" (dict.back_to_admin_panel())
+    }
+}
+
+pub fn impersonations(locale: Locale, log: &[LogEntry]) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    // The records are kept when users are deleted.
+    let username = |user: &Option<User>| {
+        user.as_ref()
+            .map(|u| u.username().to_owned())
+            .unwrap_or_else(|| dict.impersonation_deleted_user().into())
+    };
+
+    html! {
+        h1 (dict.impersonations_title())
+        p (dict.impersonate_explanation())
+        form action="/admin_panel/impersonate" method="post" {
+            div class="o-form-element" {
+                label class="c-label" for="impersonate-username" (dict.username())
+                input id="impersonate-username" name="username" class="c-field";
+            }
+            input
+                class="c-button c-button--warning"
+                type="submit"
+                value=(dict.impersonate_button()) {}
+        }
+
+        h2 (dict.impersonation_log_headline())
+        table class="c-table" {
+            thead class="c-table__head" {
+                tr class="c-table__row c-table__row--heading" {
+                    th class="c-table__cell" (dict.impersonation_admin())
+                    th class="c-table__cell" (dict.username())
+                    th class="c-table__cell" (dict.impersonation_started())
+                    th class="c-table__cell" (dict.impersonation_ended())
+                }
+            }
+            tbody class="c-table__body" {
+                @for entry in log {
+                    tr class="c-table__row" {
+                        td class="c-table__cell" (username(&entry.admin))
+                        td class="c-table__cell" (username(&entry.user))
+                        td class="c-table__cell" (entry.impersonation.started)
+                        td class="c-table__cell" {
+                            @if let Some(ended) = entry.impersonation.ended {
+                                (ended)
+                            } @else {
+                                "–"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn permissions(locale: Locale, grants: &[(User, Vec<Permission>)]) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    html! {
+        h1 (dict.permissions_title())
+        p (dict.permissions_explanation())
+
+        h2 (dict.role_permissions_headline())
+        ul {
+            @for role in Role::all() {
+                li {
+                    b (format!("{:?}", role))
+                    ": "
+                    (role.permissions().iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", "))
+                }
+            }
+        }
+
+        h2 (dict.granted_permissions_headline())
+        ul {
+            @for &(ref user, ref perms) in grants {
+                li {
+                    b (user.username())
+                    ": "
+                    (perms.iter().map(|p| p.as_str()).collect::<Vec<_>>().join(", "))
+                }
+            }
+        }
+
+        h2 (dict.change_permission_headline())
+        form action="/admin_panel/permissions" method="post" {
+            div class="o-form-element" {
+                label class="c-label" for="permission-username" (dict.username())
+                input id="permission-username" name="username" class="c-field";
+            }
+            div class="o-form-element" {
+                label class="c-label" for="permission" (dict.permission())
+                select id="permission" class="c-field" name="permission" {
+                    @for perm in Permission::all() {
+                        option value=(perm.as_str()) (perm.as_str())
+                    }
+                }
+            }
+            button class="c-button c-button--success" type="submit" name="action" value="grant"
+                (dict.grant_permission())
+            " "
+            button class="c-button c-button--error" type="submit" name="action" value="revoke"
+                (dict.revoke_permission())
+        }
+    }
+}
+
 pub fn state(locale: Locale, app_state: &CurrentAppState) -> Markup {
     let root_dict = dict::new(locale);
     let dict = &root_dict.admin_panel;
@@ -94,6 +689,14 @@ pub fn state(locale: Locale, app_state: &CurrentAppState) -> Markup {
               input id="reason" name="reason" class="c-field";
             }
 
+            div class="o-form-element" {
+                label class="c-field c-field--choice" {
+                    input type="checkbox" name="notify" value="yes" checked?;
+                    " "
+                    (dict.state_notify_users())
+                }
+            }
+
             input
                 class="c-button c-button--success"
                 type="submit"
@@ -119,6 +722,7 @@ pub fn timeslots(timeslots: &[TimeSlot], locale: Locale) -> Markup {
                 tr class="c-table__row c-table__row--heading" {
                     th class="c-table__cell" "Day"
                     th class="c-table__cell" "Time"
+                    th class="c-table__cell" "Length (minutes)"
                     th class="c-table__cell" {}
                 }
             }
@@ -127,7 +731,8 @@ pub fn timeslots(timeslots: &[TimeSlot], locale: Locale) -> Markup {
                 @for timeslot in timeslots {
                     tr class="c-table__row" {
                         td class="c-table__cell" (timeslot.day())
-                        td class="c-table__cell" (timeslot.time())
+                        td class="c-table__cell" { (timeslot.time()) "–" (timeslot.end()) }
+                        td class="c-table__cell" (timeslot.duration())
                         td class="c-table__cell" {
                             form action="/admin_panel/delete_timeslot" method="post" {
                                 input type="hidden" name="id" value=(timeslot.id());
@@ -156,6 +761,15 @@ pub fn timeslots(timeslots: &[TimeSlot], locale: Locale) -> Markup {
                                 placeholder="HH:MM or HH:MM-HH:MM"
                                 name="time";
                         }
+                        td class="c-table__cell" {
+                            input
+                                class="c-field"
+                                type="number"
+                                min="1"
+                                max=(config::MAX_TIMESLOT_LEN)
+                                value=(config::DEFAULT_TIMESLOT_LEN)
+                                name="duration";
+                        }
                         td class="c-table__cell" {
                             input
                                 type="submit"
@@ -168,3 +782,427 @@ pub fn timeslots(timeslots: &[TimeSlot], locale: Locale) -> Markup {
         }
     }
 }
+
+/// All rooms of the current course, each with the ids of the timeslots in
+/// which it is available.
+pub fn rooms(locale: Locale, rooms: &[(Room, Vec<i16>)], timeslots: &[TimeSlot]) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    html! {
+        h1 (dict.rooms_title())
+        p (dict.rooms_explanation())
+
+        @if rooms.is_empty() {
+            p { i (dict.no_rooms()) }
+        }
+
+        @for &(ref room, ref available) in rooms {
+            h2 (room.name)
+            form action={"/admin_panel/rooms/" (room.id())} method="post" {
+                div class="o-grid o-grid--small-full" {
+                    div class="o-grid__cell" {
+                        div class="o-form-element" {
+                            label class="c-label" for={"room-name-" (room.id())} (dict.name())
+                            input id={"room-name-" (room.id())} name="name" class="c-field" value=(room.name);
+                        }
+                    }
+                    div class="o-grid__cell" {
+                        div class="o-form-element" {
+                            label class="c-label" for={"room-capacity-" (room.id())} (dict.room_capacity())
+                            input
+                                id={"room-capacity-" (room.id())}
+                                name="capacity"
+                                type="number"
+                                min="1"
+                                class="c-field"
+                                value=(room.capacity);
+                        }
+                    }
+                }
+                fieldset class="o-fieldset" {
+                    legend class="o-fieldset__legend" (dict.room_availability())
+                    @if timeslots.is_empty() {
+                        p { i (dict.no_timeslots()) }
+                    }
+                    @for timeslot in timeslots {
+                        label class="c-field c-field--choice" style="display: inline-block; width: 12em" {
+                            input
+                                type="checkbox"
+                                name={"slot-" (timeslot.id())}
+                                value="yes"
+                                checked?[available.contains(&timeslot.id())];
+                            " " (timeslot.day().short_name(locale)) " " (timeslot.time())
+                        }
+                    }
+                }
+                input
+                    class="c-button c-button--success"
+                    type="submit"
+                    value=(dict::new(locale).save_form());
+            }
+            form
+                action={"/admin_panel/rooms/" (room.id()) "/delete"}
+                method="post"
+                class="u-letter-box--small"
+                onsubmit=(format!("return confirm('{}')", dict.delete_room_confirm(&room.name)))
+            {
+                input
+                    type="submit"
+                    class="c-button c-button--error u-xsmall"
+                    value=(dict.delete_room());
+            }
+        }
+
+        h2 (dict.new_room())
+        form action="/admin_panel/rooms" method="post" style="max-width: 25em" {
+            div class="o-form-element" {
+                label class="c-label" for="room-name" (dict.name())
+                input id="room-name" name="name" class="c-field" required?;
+            }
+            div class="o-form-element" {
+                label class="c-label" for="room-capacity" (dict.room_capacity())
+                input id="room-capacity" name="capacity" type="number" min="1" class="c-field" required?;
+                div class="c-hint" (dict.room_capacity_hint())
+            }
+            input
+                class="c-button c-button--success"
+                type="submit"
+                value=(dict.create_room());
+        }
+    }
+}
+
+/// The course list and all archives. Each course comes with its number of
+/// students and tutors.
+pub fn courses(locale: Locale, courses: &[(Course, u64, u64)], archives: &[Archive]) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+
+    html! {
+        h1 (dict.courses_title())
+        p (dict.courses_explanation())
+
+        table class="c-table" {
+            thead class="c-table__head" {
+                tr class="c-table__row c-table__row--heading" {
+                    th class="c-table__cell" (dict.course_slug())
+                    th class="c-table__cell" (dict.name())
+                    th class="c-table__cell" (dict.num_students())
+                    th class="c-table__cell" (dict.num_tutors())
+                    th class="c-table__cell" (dict.course_auto_enroll())
+                    th class="c-table__cell" {}
+                }
+            }
+            tbody class="c-table__body" {
+                @for &(ref course, num_students, num_tutors) in courses {
+                    tr class="c-table__row" {
+                        td class="c-table__cell" code (course.slug)
+                        td class="c-table__cell" (course.name)
+                        td class="c-table__cell" (num_students)
+                        td class="c-table__cell" (num_tutors)
+                        td class="c-table__cell" {
+                            form
+                                action={"/admin_panel/courses/" (course.slug) "/auto_enroll"}
+                                method="post"
+                            {
+                                input type="checkbox" name="auto_enroll" value="yes" checked?[course.auto_enroll];
+                                " "
+                                input
+                                    type="submit"
+                                    class="c-button c-button--brand u-xsmall"
+                                    value=(dict::new(locale).save_form());
+                            }
+                        }
+                        td class="c-table__cell" {
+                            a class="c-button c-button--info u-xsmall" href={"/courses/" (course.slug)} {
+                                (dict.select_course())
+                            }
+                            " "
+                            form
+                                action={"/admin_panel/courses/" (course.slug) "/archive"}
+                                method="post"
+                                style="display: inline"
+                                onsubmit=(format!("return confirm('{}')", dict.archive_course_confirm(&course.name)))
+                            {
+                                input
+                                    type="submit"
+                                    class="c-button c-button--warning u-xsmall"
+                                    value=(dict.archive_course());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        h2 (dict.new_course())
+        form action="/admin_panel/courses" method="post" style="max-width: 25em" {
+            div class="o-form-element" {
+                label class="c-label" for="course-slug" (dict.course_slug())
+                input
+                    id="course-slug"
+                    name="slug"
+                    class="c-field"
+                    maxlength=(config::MAX_COURSE_SLUG_LEN)
+                    pattern="[a-z0-9-]+"
+                    required?;
+                div class="c-hint" (dict.course_slug_hint())
+            }
+            div class="o-form-element" {
+                label class="c-label" for="course-name" (dict.name())
+                input id="course-name" name="name" class="c-field" required?;
+            }
+            label class="c-field c-field--choice" {
+                input type="checkbox" name="auto_enroll" value="yes";
+                " "
+                (dict.course_auto_enroll())
+            }
+            input
+                class="c-button c-button--success"
+                type="submit"
+                value=(dict.create_course());
+        }
+
+        h2 (dict.archives_headline())
+        p (dict.archives_explanation())
+        @if archives.is_empty() {
+            p { i (dict.no_archives()) }
+        } @else {
+            table class="c-table" {
+                tbody class="c-table__body" {
+                    @for archive in archives {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (archive.course_name)
+                            td class="c-table__cell" code (archive.schema_name)
+                            td class="c-table__cell" {
+                                (archive.created.with_timezone(&Local).format("%d.%m.%Y %H:%M"))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub fn announcements(locale: Locale, announcements: &[Announcement]) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+    let format_time = |time: Option<DateTime<Utc>>| {
+        time.map(|t| t.with_timezone(&Local).format("%d.%m.%Y %H:%M").to_string())
+            .unwrap_or_else(|| "–".into())
+    };
+
+    html! {
+        h1 (dict.announcements_title())
+        p (dict.announcements_explanation())
+
+        @if announcements.is_empty() {
+            p { i (dict.no_announcements()) }
+        } @else {
+            table class="c-table" {
+                thead class="c-table__head" {
+                    tr class="c-table__row c-table__row--heading" {
+                        th class="c-table__cell" (dict.announcement_body())
+                        th class="c-table__cell" (dict.announcement_roles())
+                        th class="c-table__cell" (dict.announcement_starts())
+                        th class="c-table__cell" (dict.announcement_ends())
+                        th class="c-table__cell" {}
+                    }
+                }
+                tbody class="c-table__body" {
+                    @for announcement in announcements {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (announcement.render())
+                            td class="c-table__cell" {
+                                (Role::all().iter()
+                                    .filter(|r| announcement.is_for(**r))
+                                    .map(|r| r.as_str())
+                                    .collect::<Vec<_>>()
+                                    .join(", "))
+                            }
+                            td class="c-table__cell" (format_time(announcement.starts))
+                            td class="c-table__cell" (format_time(announcement.ends))
+                            td class="c-table__cell" {
+                                form
+                                    action={"/admin_panel/announcements/" (announcement.id()) "/delete"}
+                                    method="post"
+                                {
+                                    input
+                                        type="submit"
+                                        class="c-button c-button--error u-xsmall"
+                                        value=(dict.delete_announcement());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        h2 (dict.new_announcement())
+        form action="/admin_panel/announcements" method="post" {
+            div class="o-form-element" {
+                label class="c-label" for="announcement-body" (dict.announcement_body())
+                textarea id="announcement-body" name="body" class="c-field" rows="5" {}
+                div class="c-hint" (dict.announcement_body_hint())
+            }
+            fieldset class="o-fieldset" {
+                legend class="o-fieldset__legend" (dict.announcement_roles())
+                label class="c-field c-field--choice" {
+                    input type="checkbox" name="for_students" value="yes" checked?;
+                    " student"
+                }
+                label class="c-field c-field--choice" {
+                    input type="checkbox" name="for_tutors" value="yes" checked?;
+                    " tutor"
+                }
+                label class="c-field c-field--choice" {
+                    input type="checkbox" name="for_admins" value="yes" checked?;
+                    " admin"
+                }
+            }
+            div class="o-form-element" style="max-width: 25em" {
+                label class="c-label" for="announcement-starts" (dict.announcement_starts())
+                input id="announcement-starts" type="datetime-local" name="starts" class="c-field";
+            }
+            div class="o-form-element" style="max-width: 25em" {
+                label class="c-label" for="announcement-ends" (dict.announcement_ends())
+                input id="announcement-ends" type="datetime-local" name="ends" class="c-field";
+                div class="c-hint" (dict.announcement_window_hint())
+            }
+            input
+                class="c-button c-button--brand"
+                type="submit"
+                value=(dict.create_announcement()) {}
+        }
+
+        a href="/admin_panel" (dict.back_to_admin_panel())
+    }
+}
+
+pub fn semester(locale: Locale, calendar: Option<&Calendar>, timeslots: &[TimeSlot]) -> Markup {
+    let dict = dict::new(locale).admin_panel;
+    let semester = calendar.map(|c| &c.semester);
+
+    // The days of the week on which testats take place, each only once.
+    let mut days = timeslots.iter().map(|t| t.day()).collect::<Vec<_>>();
+    days.sort();
+    days.dedup();
+
+    html! {
+        h1 (dict.semester_title())
+        p (dict.semester_explanation())
+
+        @if semester.is_none() {
+            p { i (dict.no_semester()) }
+        }
+
+        form action="/admin_panel/semester" method="post" style="max-width: 25em" {
+            div class="o-form-element" {
+                label class="c-label" for="semester-name" (dict.semester_name())
+                input
+                    id="semester-name"
+                    class="c-field"
+                    name="name"
+                    value=(semester.map(|s| s.name.as_str()).unwrap_or(""));
+                div class="c-hint" (dict.semester_name_hint())
+            }
+            div class="o-form-element" {
+                label class="c-label" for="semester-start" (dict.lecture_start())
+                input
+                    id="semester-start"
+                    type="date"
+                    class="c-field"
+                    name="lecture_start"
+                    value=(semester.map(|s| s.lecture_start.to_string()).unwrap_or_default());
+            }
+            div class="o-form-element" {
+                label class="c-label" for="semester-end" (dict.lecture_end())
+                input
+                    id="semester-end"
+                    type="date"
+                    class="c-field"
+                    name="lecture_end"
+                    value=(semester.map(|s| s.lecture_end.to_string()).unwrap_or_default());
+            }
+            input
+                class="c-button c-button--brand"
+                type="submit"
+                value=(dict.save_semester());
+        }
+
+        @if let Some(calendar) = calendar {
+            @if !days.is_empty() {
+                h2 (dict.testat_dates_headline())
+                ul {
+                    @for day in &days {
+                        li {
+                            (day.full_name(locale)) ": "
+                            (dict.testat_dates(calendar.all_on(*day)
+                                .into_iter()
+                                .filter(|date| calendar.day_off(*date).is_none())
+                                .count() as u64))
+                        }
+                    }
+                }
+            }
+
+            h2 (dict.days_off_headline())
+            @if calendar.days_off.is_empty() {
+                p { i (dict.no_days_off()) }
+            } @else {
+                table class="c-table" {
+                    thead class="c-table__head" {
+                        tr class="c-table__row c-table__row--heading" {
+                            th class="c-table__cell" (dict.day_off_first_day())
+                            th class="c-table__cell" (dict.day_off_last_day())
+                            th class="c-table__cell" (dict.day_off_description())
+                            th class="c-table__cell" {}
+                        }
+                    }
+                    tbody class="c-table__body" {
+                        @for day_off in &calendar.days_off {
+                            tr class="c-table__row" {
+                                td class="c-table__cell" (day_off.first_day.format("%d.%m.%Y"))
+                                td class="c-table__cell" (day_off.last_day.format("%d.%m.%Y"))
+                                td class="c-table__cell" (day_off.description)
+                                td class="c-table__cell" {
+                                    form
+                                        action={"/admin_panel/semester/days_off/" (day_off.id()) "/delete"}
+                                        method="post"
+                                    {
+                                        input
+                                            type="submit"
+                                            class="c-button c-button--error u-xsmall"
+                                            value=(dict.delete_day_off());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form action="/admin_panel/semester/days_off" method="post" style="max-width: 25em" {
+                div class="o-form-element" {
+                    label class="c-label" for="day-off-first" (dict.day_off_first_day())
+                    input id="day-off-first" type="date" class="c-field" name="first_day";
+                }
+                div class="o-form-element" {
+                    label class="c-label" for="day-off-last" (dict.day_off_last_day())
+                    input id="day-off-last" type="date" class="c-field" name="last_day";
+                    div class="c-hint" (dict.day_off_last_day_hint())
+                }
+                div class="o-form-element" {
+                    label class="c-label" for="day-off-description" (dict.day_off_description())
+                    input id="day-off-description" class="c-field" name="description";
+                }
+                input
+                    class="c-button c-button--brand"
+                    type="submit"
+                    value=(dict.add_day_off());
+            }
+        }
+
+        a href="/admin_panel" (dict.back_to_admin_panel())
+    }
+}
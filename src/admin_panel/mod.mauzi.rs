@@ -21,11 +21,333 @@ unit num_students {
 }
 
 
+unit kill_sessions_headline {
+    De => "Sitzungen beenden",
+}
+unit kill_sessions_explanation {
+    De => "Meldet einen Nutzer auf allen Geräten ab, z.B. wenn sein Account \
+           kompromittiert sein könnte.",
+}
+unit username {
+    De => "Nutzername",
+}
+unit kill_sessions_button {
+    De => "Alle Sitzungen beenden",
+}
+unit flash_err_user_not_found {
+    De => "Der angegebene Nutzer existiert nicht.",
+}
+
+unit flash_err_target_is_admin(username: &str) {
+    De => "'{username}' ist Admin und kann nur von Admins verwaltet werden.",
+}
+
+unit flash_success_sessions_killed(username: &str, count: u64) {
+    De => "{count} Sitzung(en) von '{username}' wurden beendet.",
+}
+
+
+unit password_reset_headline {
+    De => "Passwort zurücksetzen",
+}
+unit password_reset_explanation {
+    De => "Erzeugt einen einmaligen Link, mit dem ein Nutzer mit internem \
+           Passwort ein neues Passwort setzen kann. Der Link muss dem Nutzer \
+           selbst übermittelt werden.",
+}
+unit password_reset_button {
+    De => "Link erzeugen",
+}
+unit flash_err_no_internal_password(username: &str) {
+    De => "'{username}' hat kein internes Passwort.",
+}
+unit password_reset_title {
+    De => "Link zum Zurücksetzen des Passworts",
+}
+unit password_reset_link_explanation(username: &str, expires: &str) {
+    De => "Mit folgendem Link (relativ zu dieser Seite) kann '{username}' bis \
+           {expires} ein neues Passwort setzen. Bestehende Links für diesen \
+           Nutzer bleiben gültig.",
+}
+unit back_to_admin_panel {
+    De => "Zurück zur Administrationsoberfläche",
+}
+
+
 unit config_headline {
     De => "Server-Konfiguration",
 }
 
 
+// ===========================================================================
+// User management pages
+// ===========================================================================
+unit users_title {
+    De => "Nutzer verwalten",
+}
+unit user_search_placeholder {
+    De => "Nutzername, Name, E-Mail oder Matrikelnummer",
+}
+unit all_roles {
+    De => "Alle Rollen",
+}
+unit search {
+    De => "Suchen",
+}
+unit name {
+    De => "Name",
+}
+unit role {
+    De => "Rolle",
+}
+unit page_of(page: u64, num_pages: u64) {
+    De => "Seite {page} von {num_pages}",
+}
+unit create_user_headline {
+    De => "Nutzer anlegen",
+}
+unit create_user_explanation {
+    De => "Nutzer werden normalerweise bei ihrer ersten Anmeldung angelegt. Wird \
+           ein Passwort angegeben, kann sich der Nutzer mit einem internen \
+           Passwort anmelden.",
+}
+unit password {
+    De => "Passwort (optional)",
+}
+unit password_confirm {
+    De => "Passwort wiederholen",
+}
+unit create_user_button {
+    De => "Anlegen",
+}
+unit internal_password {
+    De => "Internes Passwort",
+}
+unit yes {
+    De => "Ja",
+}
+unit no {
+    De => "Nein",
+}
+unit edit_user_headline {
+    De => "Bearbeiten",
+}
+unit preferences_headline {
+    De => "Einstellungen",
+}
+unit partner {
+    De => "Partner",
+}
+unit prefers_english {
+    De => "Bevorzugt Englisch",
+}
+unit ratings_headline {
+    De => "Timeslot-Bewertungen",
+}
+unit sessions_headline {
+    De => "Sitzungen",
+}
+unit delete_user_headline {
+    De => "Nutzer löschen",
+}
+unit delete_user_explanation {
+    De => "Löscht den Nutzer mit allen zugehörigen Daten (Sitzungen, Passwort, \
+           Einstellungen, Bewertungen, ...). Das kann nicht rückgängig gemacht \
+           werden.",
+}
+unit delete_user_confirm {
+    De => "Nutzer wirklich löschen?",
+}
+unit delete_user_button {
+    De => "Endgültig löschen",
+}
+unit flash_err_username_empty {
+    De => "Der Nutzername darf nicht leer sein.",
+}
+unit flash_err_user_exists(username: &str) {
+    De => "Es gibt bereits einen Nutzer '{username}'.",
+}
+unit flash_success_user_created(username: &str) {
+    De => "Der Nutzer '{username}' wurde angelegt.",
+}
+unit flash_err_change_own_role {
+    De => "Du kannst deine eigene Rolle nicht ändern.",
+}
+unit flash_err_grant_admin {
+    De => "Nur Admins können andere Nutzer zu Admins machen.",
+}
+unit flash_success_user_updated(username: &str) {
+    De => "Der Nutzer '{username}' wurde gespeichert.",
+}
+unit flash_err_delete_self {
+    De => "Du kannst dich nicht selbst löschen.",
+}
+unit flash_err_user_has_impersonations(username: &str) {
+    De => "'{username}' kann nicht gelöscht werden, da der Nutzer im \
+           Protokoll der Nutzeransichten vorkommt.",
+}
+unit flash_success_user_deleted(username: &str) {
+    De => "Der Nutzer '{username}' wurde gelöscht.",
+}
+
+
+// ===========================================================================
+// User import
+// ===========================================================================
+unit import_headline {
+    De => "Nutzer importieren",
+}
+unit import_explanation {
+    De => "Nutzer können aus einer CSV-Datei (z.B. dem Teilnehmerexport aus \
+           Stud.IP) importiert werden. Vor dem Import wird eine Vorschau angezeigt.",
+}
+unit import_link {
+    De => "Zum Import",
+}
+unit import_format_explanation {
+    De => "Füge hier den Inhalt der CSV-Datei ein. Die erste Zeile muss die \
+           Spaltennamen enthalten; erkannt werden Nutzername (bzw. Kennung), \
+           Name oder Vorname und Nachname, E-Mail und Matrikelnummer. Nur der \
+           Nutzername ist notwendig. Trennzeichen können ';', ',' oder Tabs sein.",
+}
+unit import_csv {
+    De => "CSV-Daten",
+}
+unit import_role {
+    De => "Rolle neuer Nutzer",
+}
+unit import_preview_button {
+    De => "Vorschau",
+}
+unit import_preview_title {
+    De => "Vorschau des Imports",
+}
+unit import_preview_summary(new: u64, updated: u64, unchanged: u64, conflicts: u64) {
+    De => "{new} Nutzer werden angelegt, {updated} aktualisiert, {unchanged} sind \
+           unverändert und {conflicts} Zeilen werden wegen Konflikten übersprungen.",
+}
+unit import_conflicts_headline {
+    De => "Konflikte",
+}
+unit import_conflict_duplicate {
+    De => "Nutzername kommt mehrfach in der Datei vor",
+}
+unit import_conflict_role(role: &str) {
+    De => "Nutzer existiert bereits mit der Rolle '{role}'",
+}
+unit import_conflict_matriculation_number {
+    De => "Matrikelnummer gehört bereits zu einem anderen Nutzer",
+}
+unit import_new_headline {
+    De => "Neue Nutzer",
+}
+unit import_updates_headline {
+    De => "Geänderte Nutzer",
+}
+unit import_nothing_to_do {
+    De => "Der Import würde nichts ändern.",
+}
+unit import_apply_button {
+    De => "Import ausführen",
+}
+unit import_back {
+    De => "Zurück",
+}
+unit csv_err_empty {
+    De => "Die CSV-Daten sind leer.",
+}
+unit csv_err_missing_username_column {
+    De => "Die CSV-Daten haben keine Spalte mit Nutzernamen.",
+}
+unit csv_err_unterminated_quote(line: u64) {
+    De => "Ein Feld in Zeile {line} hat kein schließendes Anführungszeichen.",
+}
+unit csv_err_empty_username(line: u64) {
+    De => "Zeile {line} enthält keinen Nutzernamen.",
+}
+unit flash_success_import(created: u64, updated: u64, skipped: u64) {
+    De => "Import abgeschlossen: {created} Nutzer angelegt, {updated} aktualisiert, \
+           {skipped} Zeilen übersprungen.",
+}
+
+
+// ===========================================================================
+// Impersonation page
+// ===========================================================================
+unit impersonations_title {
+    De => "Als Nutzer ansehen",
+}
+unit impersonate_explanation {
+    De => "Zeigt die Seite so, wie der angegebene Nutzer sie sieht. Dabei kann \
+           nichts verändert werden. Jede Nutzung wird protokolliert.",
+}
+unit impersonate_button {
+    De => "Als Nutzer ansehen",
+}
+unit flash_err_impersonate_self {
+    De => "Du kannst dich nicht selbst ansehen.",
+}
+unit flash_info_impersonation_started(username: &str) {
+    De => "Du siehst die Seite jetzt als '{username}'.",
+}
+unit impersonation_log_headline {
+    De => "Protokoll",
+}
+unit impersonation_admin {
+    De => "Administrator",
+}
+unit impersonation_started {
+    De => "Beginn",
+}
+unit impersonation_ended {
+    De => "Ende",
+}
+unit impersonation_deleted_user {
+    De => "(gelöscht)",
+}
+
+
+// ===========================================================================
+// Permission managing page
+// ===========================================================================
+unit permissions_title {
+    De => "Berechtigungen verwalten",
+}
+unit permissions_explanation {
+    De => "Jede Rolle hat feste Berechtigungen. Einzelnen Nutzern können \
+           zusätzliche Berechtigungen erteilt werden, ohne ihre Rolle zu ändern. \
+           Wer kein Admin ist, kann nur eigene Berechtigungen weitergeben und \
+           weder die eigenen noch die von Admins ändern.",
+}
+unit role_permissions_headline {
+    De => "Berechtigungen der Rollen",
+}
+unit granted_permissions_headline {
+    De => "Zusätzlich erteilte Berechtigungen",
+}
+unit change_permission_headline {
+    De => "Berechtigung erteilen oder entziehen",
+}
+unit permission {
+    De => "Berechtigung",
+}
+unit grant_permission {
+    De => "Erteilen",
+}
+unit revoke_permission {
+    De => "Entziehen",
+}
+unit flash_success_permission_granted(username: &str, permission: &str) {
+    De => "'{username}' hat jetzt die Berechtigung '{permission}'.",
+}
+unit flash_success_permission_revoked(username: &str, permission: &str) {
+    De => "Die zusätzliche Berechtigung '{permission}' von '{username}' wurde entzogen.",
+}
+unit flash_err_change_permission_denied(username: &str, permission: &str) {
+    De => "Du darfst die Berechtigung '{permission}' von '{username}' nicht ändern.",
+}
+
+
 // ===========================================================================
 // State managing page
 // ===========================================================================
@@ -51,6 +373,10 @@ unit next_state_switch {
 unit no_state_switch_estimate {
     De => "Nicht angegeben",
 }
+unit state_notify_users {
+    De => "Studenten und Tutoren benachrichtigen (nur beim Wechsel zu 'Preparation' \
+           oder 'Running')",
+}
 unit flash_success_app_state_updated {
     De => "App Status wurde erfolgreich aktualisiert.",
 }
@@ -62,3 +388,284 @@ unit flash_success_app_state_updated {
 unit timeslots_title {
     De => "Timeslots verwalten",
 }
+
+
+// ===========================================================================
+// Announcements
+// ===========================================================================
+unit announcements_title {
+    De => "Ankündigungen verwalten",
+}
+unit announcements_explanation {
+    De => "Ankündigungen werden den ausgewählten Rollen auf jeder Seite angezeigt, bis \
+           sie von den Nutzern ausgeblendet werden.",
+}
+unit no_announcements {
+    De => "Es gibt keine Ankündigungen.",
+}
+unit new_announcement {
+    De => "Neue Ankündigung",
+}
+unit announcement_body {
+    De => "Text",
+}
+unit announcement_body_hint {
+    De => "Markdown kann verwendet werden, z.B. **fett**, _kursiv_ und [Links](https://…).",
+}
+unit announcement_roles {
+    De => "Anzeigen für",
+}
+unit announcement_starts {
+    De => "Anzeigen ab",
+}
+unit announcement_ends {
+    De => "Anzeigen bis",
+}
+unit announcement_window_hint {
+    De => "Leere Felder bedeuten „sofort“ bzw. „unbegrenzt“.",
+}
+unit create_announcement {
+    De => "Ankündigung erstellen",
+}
+unit delete_announcement {
+    De => "Löschen",
+}
+unit flash_err_announcement_empty {
+    De => "Die Ankündigung darf nicht leer sein.",
+}
+unit flash_err_announcement_no_roles {
+    De => "Es muss mindestens eine Rolle ausgewählt werden.",
+}
+unit flash_err_announcement_window {
+    De => "Das Ende muss nach dem Anfang liegen.",
+}
+unit flash_err_invalid_datetime(input: &str) {
+    De => "'{input}' ist kein gültiger Zeitpunkt (erwartet: TT.MM.JJJJ HH:MM).",
+}
+unit flash_success_announcement_created {
+    De => "Die Ankündigung wurde erstellt.",
+}
+unit flash_success_announcement_deleted {
+    De => "Die Ankündigung wurde gelöscht.",
+}
+
+
+// ===========================================================================
+// Semester
+// ===========================================================================
+unit semester_title {
+    De => "Semester verwalten",
+}
+unit semester_explanation {
+    De => "Testate finden in der Vorlesungszeit jede Woche statt, außer an den \
+           freien Tagen. Daraus werden die Termine für die Übersicht der Studenten \
+           und Tutoren und für die Kalender-Links berechnet.",
+}
+unit no_semester {
+    De => "Es wurde noch kein Semester eingerichtet.",
+}
+unit semester_name {
+    De => "Name",
+}
+unit semester_name_hint {
+    De => "Z.B. „WS 2017/18“.",
+}
+unit lecture_start {
+    De => "Erster Testat-Tag",
+}
+unit lecture_end {
+    De => "Letzter Testat-Tag",
+}
+unit save_semester {
+    De => "Speichern",
+}
+unit days_off_headline {
+    De => "Freie Tage",
+}
+unit no_days_off {
+    De => "Es gibt keine freien Tage.",
+}
+unit day_off_first_day {
+    De => "Von",
+}
+unit day_off_last_day {
+    De => "Bis",
+}
+unit day_off_last_day_hint {
+    De => "Leer lassen für einen einzelnen Tag.",
+}
+unit day_off_description {
+    De => "Beschreibung",
+}
+unit add_day_off {
+    De => "Hinzufügen",
+}
+unit delete_day_off {
+    De => "Löschen",
+}
+unit testat_dates_headline {
+    De => "Anzahl der Testat-Termine",
+}
+unit testat_dates(count: u64) {
+    De => "{count} Termine",
+}
+unit flash_err_invalid_date(input: &str) {
+    De => "'{input}' ist kein gültiges Datum (erwartet: TT.MM.JJJJ).",
+}
+unit flash_err_semester_name_empty {
+    De => "Der Name darf nicht leer sein.",
+}
+unit flash_err_day_off_description_empty {
+    De => "Die Beschreibung darf nicht leer sein.",
+}
+unit flash_err_date_order {
+    De => "Das Ende darf nicht vor dem Anfang liegen.",
+}
+unit flash_success_semester_saved {
+    De => "Das Semester wurde gespeichert.",
+}
+unit flash_success_day_off_added {
+    De => "Der freie Tag wurde hinzugefügt.",
+}
+unit flash_success_day_off_deleted {
+    De => "Der freie Tag wurde gelöscht.",
+}
+unit courses_title {
+    De => "Kurse",
+}
+unit courses_explanation {
+    De => "Zeitslots, Einstellungen, Testate und der Zustand der Anwendung gehören \
+           jeweils zu einem Kurs. Die Seiten des Admin-Panels zeigen den aktuellen Kurs, \
+           der oben in der Navigation gewechselt werden kann. Neue Nutzer werden \
+           automatisch in alle Kurse mit automatischer Aufnahme eingetragen.",
+}
+unit course_slug {
+    De => "Kürzel",
+}
+unit course_slug_hint {
+    De => "Wird in Links verwendet. Nur Kleinbuchstaben, Ziffern und '-'.",
+}
+unit course_auto_enroll {
+    De => "Automatische Aufnahme",
+}
+unit select_course {
+    De => "Auswählen",
+}
+unit new_course {
+    De => "Neuer Kurs",
+}
+unit create_course {
+    De => "Kurs anlegen",
+}
+unit no_current_course {
+    De => "Es gibt keinen aktuellen Kurs. Bitte lege zuerst einen Kurs an.",
+}
+unit no_member {
+    De => "kein Mitglied",
+}
+unit admin_in_all_courses {
+    De => "Admins haben in allen Kursen Zugriff.",
+}
+unit data_of_course(name: &str) {
+    De => "Die folgenden Daten gehören zum aktuellen Kurs „{name}“.",
+}
+unit flash_err_invalid_course_slug(max: usize) {
+    De => "Das Kürzel darf nur aus Kleinbuchstaben, Ziffern und '-' bestehen und \
+           höchstens {max} Zeichen lang sein.",
+}
+unit flash_err_course_name_empty {
+    De => "Der Name des Kurses darf nicht leer sein.",
+}
+unit flash_err_course_exists(slug: &str) {
+    De => "Es gibt bereits einen Kurs mit dem Kürzel '{slug}'.",
+}
+unit flash_err_admin_course_role(username: &str) {
+    De => "'{username}' ist Admin und hat damit in allen Kursen Zugriff.",
+}
+unit flash_success_course_created(name: &str) {
+    De => "Der Kurs „{name}“ wurde angelegt und ist jetzt der aktuelle Kurs.",
+}
+unit flash_success_course_updated(name: &str) {
+    De => "Der Kurs „{name}“ wurde gespeichert.",
+}
+unit flash_success_course_role_changed(username: &str, course: &str) {
+    De => "Die Rolle von '{username}' im Kurs „{course}“ wurde gespeichert.",
+}
+unit archive_course {
+    De => "Archivieren",
+}
+unit archive_course_confirm(name: &str) {
+    De => "Alle Daten des Kurses „{name}“ archivieren und den Kurs für das nächste \
+           Semester zurücksetzen? Bewertungen, Einstellungen und Testate werden gelöscht!",
+}
+unit archives_headline {
+    De => "Archive",
+}
+unit archives_explanation {
+    De => "Am Ende des Semesters werden alle Daten eines Kurses (Mitglieder, Zeitslots, \
+           Räume, Bewertungen, Einstellungen, Testate und das Semester) in ein eigenes \
+           Schema der Datenbank kopiert. Danach werden Bewertungen, Einstellungen und \
+           Testate zurückgesetzt und der Kurs geht in die Vorbereitungsphase. Accounts, \
+           Rollen, Zeitslots und Räume bleiben erhalten.",
+}
+unit no_archives {
+    De => "Es gibt noch keine Archive.",
+}
+unit flash_success_course_archived(name: &str, schema: &str) {
+    De => "Der Kurs „{name}“ wurde im Schema '{schema}' archiviert und ist jetzt in der \
+           Vorbereitungsphase.",
+}
+unit rooms_title {
+    De => "Räume",
+}
+unit rooms_explanation {
+    De => "Testate finden in Räumen mit begrenzter Anzahl an Plätzen statt. Jedes Testat \
+           braucht einen Platz pro Student und einen für den Tutor. Ein Raum kann nur in \
+           den ausgewählten Zeitslots verwendet werden.",
+}
+unit no_rooms {
+    De => "Dieser Kurs hat noch keine Räume.",
+}
+unit no_timeslots {
+    De => "Dieser Kurs hat noch keine Zeitslots.",
+}
+unit room_capacity {
+    De => "Plätze",
+}
+unit room_capacity_hint {
+    De => "Der neue Raum ist zunächst in allen Zeitslots verfügbar.",
+}
+unit room_availability {
+    De => "Verfügbar in",
+}
+unit new_room {
+    De => "Neuer Raum",
+}
+unit create_room {
+    De => "Raum anlegen",
+}
+unit delete_room {
+    De => "Raum löschen",
+}
+unit delete_room_confirm(name: &str) {
+    De => "Den Raum „{name}“ wirklich löschen? Testate in diesem Raum haben danach \
+           keinen Raum mehr.",
+}
+unit flash_err_room_name_empty {
+    De => "Der Name des Raums darf nicht leer sein.",
+}
+unit flash_err_invalid_capacity(input: &str) {
+    De => "'{input}' ist keine gültige Anzahl an Plätzen.",
+}
+unit flash_err_room_exists(name: &str) {
+    De => "Es gibt bereits einen Raum „{name}“ in diesem Kurs.",
+}
+unit flash_success_room_created(name: &str) {
+    De => "Der Raum „{name}“ wurde angelegt.",
+}
+unit flash_success_room_updated(name: &str) {
+    De => "Der Raum „{name}“ wurde gespeichert.",
+}
+unit flash_success_room_deleted(name: &str) {
+    De => "Der Raum „{name}“ wurde gelöscht.",
+}
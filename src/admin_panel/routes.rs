@@ -1,26 +1,42 @@
 use std::collections::HashMap;
 
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use diesel::prelude::*;
 use diesel::result::{Error as DieselError, DatabaseErrorKind};
 use option_filter::OptionFilterExt;
 use rocket::State;
+use rocket::http::Cookies;
 use rocket::config::Config;
 use rocket::response::{Flash, Redirect};
-use rocket::request::Form;
+use rocket::request::{Form, FormItems, FromForm};
 
+use announcement::{Announcement, NewAnnouncement};
+use archive::Archive;
+use config;
+use course::{self, Course};
 use db::Db;
 use dict::{self, Locale};
 use errors::*;
+use login::{ClientInfo, Session};
+use login::impersonation::Impersonation;
+use login::password::{self, Password, PasswordResetToken};
+use notify::{self, Notification};
+use prep::{StudentPreferences, TimeSlotRating};
+use room::Room;
+use semester::{Calendar, DayOff, Semester};
 use state::{AppState, CurrentAppState};
 use super::html;
 use template::Page;
 use timeslot::{self, DayOfWeek, NewTimeSlot, TimeSlot};
-use user::{AuthAdmin, Role};
+use user::{Role, User};
+use user::import::{self, CsvError, ImportPlan};
+use user::profile::{Profile, ProfileField};
+use user::permission::{self, perm, Permission, Require};
 
 
 #[get("/admin_panel")]
 pub fn index(
-    _admin: AuthAdmin,
+    _user: Require<perm::ViewAdminPanel>,
     locale: Locale,
     db: State<Db>,
     config: State<Config>,
@@ -52,16 +68,832 @@ pub fn index(
 }
 
 
+/// Search parameters of the user list. All parameters are optional.
+#[derive(Debug, Default, FromForm)]
+pub struct UserSearch {
+    /// Part of the username, real name or email address, or a matriculation
+    /// number.
+    q: Option<String>,
+    role: Option<String>,
+    page: Option<usize>,
+}
+
+#[get("/admin_panel/users?<search>")]
+pub fn users_search(
+    _user: Require<perm::ManageUsers>,
+    search: UserSearch,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    users_page(search, locale, &db)
+}
+
+#[get("/admin_panel/users", rank = 2)]
+pub fn users(
+    _user: Require<perm::ManageUsers>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    users_page(UserSearch::default(), locale, &db)
+}
+
+/// Shows one page of all users matching the given search.
+fn users_page(search: UserSearch, locale: Locale, db: &Db) -> Result<Page> {
+    let role = search.role.as_ref().and_then(|r| Role::from_str(r));
+    let query = search.q.as_ref()
+        .map(|q| q.trim().to_lowercase())
+        .filter(|q| !q.is_empty());
+
+    // There are only a few hundred users, so we simply filter in Rust.
+    let matching: Vec<_> = User::load_all(role, db)?
+        .into_iter()
+        .filter(|u| {
+            query.as_ref().map_or(true, |q| {
+                u.username().to_lowercase().contains(q)
+                    || u.name().map_or(false, |n| n.to_lowercase().contains(q))
+                    || u.email().map_or(false, |e| e.to_lowercase().contains(q))
+                    || u.matriculation_number() == Some(q.as_str())
+            })
+        })
+        .collect();
+
+    let per_page = config::ADMIN_USERS_PER_PAGE;
+    let num_pages = (matching.len() + per_page - 1) / per_page;
+    let page = search.page.unwrap_or(0);
+    let users: Vec<_> = matching.into_iter()
+        .skip(page * per_page)
+        .take(per_page)
+        .collect();
+
+    let list = html::UserList {
+        users: &users,
+        query: query.as_ref().map(|q| q.as_str()).unwrap_or(""),
+        role,
+        page,
+        num_pages,
+    };
+
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.users_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::users(locale, &list))
+        .make_ok()
+}
+
+#[get("/admin_panel/users/<id>", rank = 2)]
+pub fn user_details(
+    auth_user: Require<perm::ManageUsers>,
+    id: i64,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Option<Page>> {
+    let user = match User::load_by_id(id, &db)? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+
+    let mut memberships = Vec::new();
+    for course in Course::load_all(&db)? {
+        let role = user.role_in(&course, &db)?;
+        memberships.push((course, role));
+    }
+
+    // Preferences and ratings are shown for the current course.
+    let (preferences, ratings) = match auth_user.course() {
+        Some(course) => {
+            let preferences = match user.clone().into_student_of(course, &db)? {
+                Ok(student) => Some(StudentPreferences::load_for(&student, course, &db)?),
+                Err(_) => None,
+            };
+            (preferences, TimeSlotRating::load_all_of_user(&user, course, &db)?)
+        }
+        None => (None, vec![]),
+    };
+
+    let details = html::UserDetails {
+        granted_permissions: user.granted_permissions(&db)?,
+        has_password: Password::load(&user, &db)?.is_some(),
+        sessions: Session::load_all_of_user(&user, &db)?,
+        memberships,
+        course: auth_user.course().cloned(),
+        preferences,
+        ratings,
+        user,
+    };
+
+    Page::empty()
+        .with_title(details.user.username().to_owned())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::user_details(locale, &details))
+        .make_ok()
+        .map(Some)
+}
+
+#[derive(FromForm)]
+pub struct NewUserForm {
+    username: String,
+    name: String,
+    role: String,
+    password: String,
+    password_confirm: String,
+}
+
+/// Creates a new user. If a password is given, the user can log in with the
+/// internal password provider.
+#[post("/admin_panel/users/create", data = "<form>")]
+pub fn create_user(
+    auth_user: Require<perm::ManageUsers>,
+    form: Form<NewUserForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let err = |msg| Ok(Flash::error(Redirect::to("/admin_panel/users"), msg));
+
+    let username = form.username.trim().to_owned();
+    let role = match Role::from_str(&form.role) {
+        Some(role) => role,
+        None => return err(bad_request(locale)),
+    };
+    if role == Role::Admin && !auth_user.is_admin() {
+        return err(dict.flash_err_grant_admin());
+    }
+    if username.is_empty() {
+        return err(dict.flash_err_username_empty());
+    }
+    if User::load_by_username(&username, &db)?.is_some() {
+        return err(dict.flash_err_user_exists(&username));
+    }
+
+    let with_password = !form.password.is_empty();
+    if with_password {
+        let check = password::check_new_password(
+            &username,
+            &form.password,
+            &form.password_confirm,
+        );
+        if let Err(rejection) = check {
+            return err(rejection.msg(locale));
+        }
+    }
+
+    let name = Some(form.name.trim().to_owned()).filter(|n| !n.is_empty());
+    let user = User::create(username, name, role, &db)?;
+    if with_password {
+        Password::create_for(&user, &form.password, &db)?;
+    }
+
+    Ok(Flash::success(
+        Redirect::to(&format!("/admin_panel/users/{}", user.id())),
+        dict.flash_success_user_created(user.username()),
+    ))
+}
+
+#[derive(FromForm)]
+pub struct EditUserForm {
+    name: String,
+    role: String,
+    email: String,
+    matriculation_number: String,
+    study_program: String,
+    semester: String,
+}
+
+/// Changes the name, role and profile of a user.
+#[post("/admin_panel/users/<id>/edit", data = "<form>")]
+pub fn edit_user(
+    auth_user: Require<perm::ManageUsers>,
+    id: i64,
+    form: Form<EditUserForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Option<Flash<Redirect>>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let target = format!("/admin_panel/users/{}", id);
+
+    let mut user = match User::load_by_id(id, &db)? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+    let role = match Role::from_str(&form.role) {
+        Some(role) => role,
+        None => return Ok(Some(Flash::error(Redirect::to(&target), bad_request(locale)))),
+    };
+
+    // Admins could lock themselves out by accident.
+    if user.id() == auth_user.id() && role != user.role() {
+        return Ok(Some(Flash::error(
+            Redirect::to(&target),
+            dict.flash_err_change_own_role(),
+        )));
+    }
+    if !may_manage_account(&auth_user, &user) {
+        return Ok(Some(Flash::error(
+            Redirect::to(&target),
+            dict.flash_err_target_is_admin(user.username()),
+        )));
+    }
+    if role == Role::Admin && role != user.role() && !auth_user.is_admin() {
+        return Ok(Some(Flash::error(Redirect::to(&target), dict.flash_err_grant_admin())));
+    }
+
+    let mut profile = Profile::default();
+    let inputs = [
+        (ProfileField::Email, &form.email),
+        (ProfileField::MatriculationNumber, &form.matriculation_number),
+        (ProfileField::StudyProgram, &form.study_program),
+        (ProfileField::Semester, &form.semester),
+    ];
+    for &(field, input) in &inputs {
+        if let Err(e) = profile.set_field(field, input) {
+            return Ok(Some(Flash::error(Redirect::to(&target), e.msg(locale))));
+        }
+    }
+    let name = Some(form.name.trim().to_owned()).filter(|n| !n.is_empty());
+    match user.update(profile, name, role, &db) {
+        Ok(()) => {}
+        Err(Error(ErrorKind::ProfileError(e), _)) => {
+            return Ok(Some(Flash::error(Redirect::to(&target), e.msg(locale))));
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(Some(Flash::success(
+        Redirect::to(&target),
+        dict.flash_success_user_updated(user.username()),
+    )))
+}
+
+#[derive(FromForm)]
+pub struct CourseRoleForm {
+    course: String,
+    /// Empty to remove the user from the course.
+    role: String,
+}
+
+#[get("/admin_panel/courses")]
+pub fn courses(
+    _user: Require<perm::ManageCourses>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let mut courses = Vec::new();
+    for course in Course::load_all(&db)? {
+        let num_students = course.count_members(Role::Student, &db)?;
+        let num_tutors = course.count_members(Role::Tutor, &db)?;
+        courses.push((course, num_students, num_tutors));
+    }
+    let archives = Archive::load_all(&db)?;
+
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.courses_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::courses(locale, &courses, &archives))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct NewCourseForm {
+    slug: String,
+    name: String,
+    auto_enroll: Option<String>,
+}
+
+#[post("/admin_panel/courses", data = "<form>")]
+pub fn create_course(
+    _user: Require<perm::ManageCourses>,
+    form: Form<NewCourseForm>,
+    mut cookies: Cookies,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let err = |msg| Ok(Flash::error(Redirect::to("/admin_panel/courses"), msg));
+
+    let slug = form.slug.trim();
+    let name = form.name.trim();
+    if !course::is_valid_slug(slug) {
+        return err(dict.flash_err_invalid_course_slug(config::MAX_COURSE_SLUG_LEN));
+    }
+    if name.is_empty() {
+        return err(dict.flash_err_course_name_empty());
+    }
+
+    let course = match Course::create(slug, name, form.auto_enroll.is_some(), &db) {
+        Ok(course) => course,
+        Err(Error(ErrorKind::Db(
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)
+        ), _)) => {
+            return err(dict.flash_err_course_exists(slug));
+        }
+        Err(e) => bail!(e),
+    };
+
+    // The new course has to be set up (timeslots, app state) next.
+    course::select(&course, &mut cookies);
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/courses"),
+        dict.flash_success_course_created(&course.name),
+    ))
+}
+
+#[derive(FromForm)]
+pub struct AutoEnrollForm {
+    auto_enroll: Option<String>,
+}
+
+#[post("/admin_panel/courses/<slug>/auto_enroll", data = "<form>")]
+pub fn change_auto_enroll(
+    slug: String,
+    _user: Require<perm::ManageCourses>,
+    form: Form<AutoEnrollForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let mut course = match Course::load_by_slug(&slug, &db)? {
+        Some(course) => course,
+        None => return Ok(Flash::error(Redirect::to("/admin_panel/courses"), bad_request(locale))),
+    };
+
+    course.set_auto_enroll(form.into_inner().auto_enroll.is_some(), &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/courses"),
+        dict::new(locale).admin_panel.flash_success_course_updated(&course.name),
+    ))
+}
+
+/// Archives the data of a course and rolls it over to the next semester, see
+/// `archive`.
+#[post("/admin_panel/courses/<slug>/archive")]
+pub fn archive_course(
+    slug: String,
+    _user: Require<perm::ManageCourses>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let course = match Course::load_by_slug(&slug, &db)? {
+        Some(course) => course,
+        None => return Ok(Flash::error(Redirect::to("/admin_panel/courses"), bad_request(locale))),
+    };
+
+    let archive = Archive::create(&course, &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/courses"),
+        dict::new(locale).admin_panel.flash_success_course_archived(&course.name, &archive.schema_name),
+    ))
+}
+
+/// Changes the role of a user in a course.
+#[post("/admin_panel/users/<id>/course_role", data = "<form>")]
+pub fn change_course_role(
+    _user: Require<perm::ManageCourses>,
+    id: i64,
+    form: Form<CourseRoleForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Option<Flash<Redirect>>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let target = format!("/admin_panel/users/{}", id);
+
+    let user = match User::load_by_id(id, &db)? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+    let course = match Course::load_by_slug(&form.course, &db)? {
+        Some(course) => course,
+        None => return Ok(Some(Flash::error(Redirect::to(&target), bad_request(locale)))),
+    };
+    let role = match form.role.as_str() {
+        "" => None,
+        s => match Role::from_str(s) {
+            Some(role) if role != Role::Admin => Some(role),
+            _ => return Ok(Some(Flash::error(Redirect::to(&target), bad_request(locale)))),
+        },
+    };
+    if user.is_admin() {
+        return Ok(Some(Flash::error(
+            Redirect::to(&target),
+            dict.flash_err_admin_course_role(user.username()),
+        )));
+    }
+
+    user.set_role_in(&course, role, &db)?;
+
+    Ok(Some(Flash::success(
+        Redirect::to(&target),
+        dict.flash_success_course_role_changed(user.username(), &course.name),
+    )))
+}
+
+/// Deletes a user with all their data.
+#[post("/admin_panel/users/<id>/delete")]
+pub fn delete_user(
+    auth_user: Require<perm::ManageUsers>,
+    id: i64,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Option<Flash<Redirect>>> {
+    let dict = dict::new(locale).admin_panel;
+    let target = format!("/admin_panel/users/{}", id);
+
+    let user = match User::load_by_id(id, &db)? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+    if user.id() == auth_user.id() {
+        return Ok(Some(Flash::error(Redirect::to(&target), dict.flash_err_delete_self())));
+    }
+    if !may_manage_account(&auth_user, &user) {
+        return Ok(Some(Flash::error(
+            Redirect::to(&target),
+            dict.flash_err_target_is_admin(user.username()),
+        )));
+    }
+
+    let username = user.username().to_owned();
+    match user.delete(&db) {
+        Ok(_) => {}
+        Err(Error(ErrorKind::Db(
+            DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, _)
+        ), _)) => {
+            return Ok(Some(Flash::error(
+                Redirect::to(&target),
+                dict.flash_err_user_has_impersonations(&username),
+            )));
+        }
+        Err(e) => bail!(e),
+    }
+
+    Ok(Some(Flash::success(
+        Redirect::to("/admin_panel/users"),
+        dict.flash_success_user_deleted(&username),
+    )))
+}
+
+
+#[get("/admin_panel/users/import")]
+pub fn users_import(_user: Require<perm::ManageUsers>, locale: Locale) -> Page {
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.import_headline())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::users_import(locale, "", Role::Student))
+}
+
+#[derive(FromForm)]
+pub struct ImportForm {
+    csv: String,
+    role: String,
+}
+
+/// Shows what importing the given CSV file would do.
+#[post("/admin_panel/users/import", data = "<form>")]
+pub fn users_import_preview(
+    _user: Require<perm::ManageUsers>,
+    form: Form<ImportForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let role = match Role::from_str(&form.role) {
+        Some(role) => role,
+        None => return Ok(Page::error(bad_request(locale))),
+    };
+
+    let rows = match import::parse_csv(&form.csv) {
+        Ok(rows) => rows,
+        Err(e) => {
+            return Page::error(csv_error_msg(&e, locale))
+                .with_title(dict.import_headline())
+                .with_active_nav_route("/admin_panel")
+                .with_content(html::users_import(locale, &form.csv, role))
+                .make_ok();
+        }
+    };
+    let plan = ImportPlan::prepare(rows, role, &db)?;
+
+    Page::empty()
+        .with_title(dict.import_preview_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::users_import_preview(locale, &plan, &form.csv))
+        .make_ok()
+}
+
+/// Imports the given CSV file. The plan is prepared again, so that changes
+/// since the preview are taken into account.
+#[post("/admin_panel/users/import/apply", data = "<form>")]
+pub fn users_import_apply(
+    _user: Require<perm::ManageUsers>,
+    form: Form<ImportForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let err = |msg| Ok(Flash::error(Redirect::to("/admin_panel/users/import"), msg));
+
+    let role = match Role::from_str(&form.role) {
+        Some(role) => role,
+        None => return err(bad_request(locale)),
+    };
+    let rows = match import::parse_csv(&form.csv) {
+        Ok(rows) => rows,
+        Err(e) => return err(csv_error_msg(&e, locale)),
+    };
+
+    let summary = ImportPlan::prepare(rows, role, &db)?.apply(&db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/users"),
+        dict.flash_success_import(
+            summary.created as u64,
+            summary.updated as u64,
+            summary.skipped as u64,
+        ),
+    ))
+}
+
+fn csv_error_msg(e: &CsvError, locale: Locale) -> String {
+    let dict = dict::new(locale).admin_panel;
+
+    match *e {
+        CsvError::Empty => dict.csv_err_empty(),
+        CsvError::MissingUsernameColumn => dict.csv_err_missing_username_column(),
+        CsvError::UnterminatedQuote(line) => dict.csv_err_unterminated_quote(line as u64),
+        CsvError::EmptyUsername(line) => dict.csv_err_empty_username(line as u64),
+    }
+}
+
+
+#[derive(FromForm)]
+pub struct KillSessionsForm {
+    username: String,
+}
+
+/// Ends all login sessions of a user. This is useful if an account might be
+/// compromised.
+#[post("/admin_panel/kill_sessions", data = "<form>")]
+pub fn kill_sessions(
+    auth_user: Require<perm::ManageUsers>,
+    form: Form<KillSessionsForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    let user = match User::load_by_username(form.username.trim(), &db)? {
+        Some(user) => user,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel"),
+                dict.flash_err_user_not_found(),
+            ));
+        }
+    };
+    if !may_manage_account(&auth_user, &user) {
+        return Ok(Flash::error(
+            Redirect::to("/admin_panel"),
+            dict.flash_err_target_is_admin(user.username()),
+        ));
+    }
+
+    let count = Session::destroy_all_of_user(&user, None, &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel"),
+        dict.flash_success_sessions_killed(user.username(), count as u64),
+    ))
+}
+
+
+#[derive(FromForm)]
+pub struct PasswordResetForm {
+    username: String,
+}
+
+/// Creates a one-time link with which a user can set a new internal
+/// password. The link is only shown to the admin who has to pass it on to the
+/// user.
+#[post("/admin_panel/password_reset", data = "<form>")]
+pub fn password_reset(
+    auth_user: Require<perm::ManageUsers>,
+    form: Form<PasswordResetForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    let user = match User::load_by_username(form.username.trim(), &db)? {
+        Some(user) => user,
+        None => return Ok(Page::error(dict.flash_err_user_not_found())),
+    };
+    if !may_manage_account(&auth_user, &user) {
+        return Ok(Page::error(dict.flash_err_target_is_admin(user.username())));
+    }
+
+    // Users of external providers don't have a password we could reset.
+    if Password::load(&user, &db)?.is_none() {
+        return Ok(Page::error(dict.flash_err_no_internal_password(user.username())));
+    }
+
+    let (token, expires) = PasswordResetToken::create_for(&user, &db)?;
+
+    Page::empty()
+        .with_title(dict.password_reset_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::password_reset_link(locale, &user, &token, &expires))
+        .make_ok()
+}
+
+/// Whether `caller` may take over the account of `target`, e.g. by resetting
+/// their password, ending their sessions or impersonating them. Only admins may do so for other
+/// admins; otherwise, `ManageUsers` would be enough to become an admin.
+fn may_manage_account(caller: &User, target: &User) -> bool {
+    !target.is_admin() || caller.is_admin()
+}
+
+
+#[get("/admin_panel/impersonations")]
+pub fn impersonations(
+    _user: Require<perm::ImpersonateUsers>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let log = Impersonation::load_log(&db)?;
+
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.impersonations_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::impersonations(locale, &log))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct ImpersonateForm {
+    username: String,
+}
+
+/// Starts an impersonation of the given user: the admin is now logged in as
+/// that user (read only) until they end the impersonation.
+#[post("/admin_panel/impersonate", data = "<form>")]
+pub fn impersonate(
+    admin: Require<perm::ImpersonateUsers>,
+    form: Form<ImpersonateForm>,
+    client: ClientInfo,
+    mut cookies: Cookies,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    let user = match User::load_by_username(form.username.trim(), &db)? {
+        Some(ref user) if user.id() == admin.id() => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel/impersonations"),
+                dict.flash_err_impersonate_self(),
+            ));
+        }
+        Some(user) => user,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel/impersonations"),
+                dict.flash_err_user_not_found(),
+            ));
+        }
+    };
+    if !may_manage_account(&admin, &user) {
+        return Ok(Flash::error(
+            Redirect::to("/admin_panel/impersonations"),
+            dict.flash_err_target_is_admin(user.username()),
+        ));
+    }
+
+    Impersonation::start(&admin, &user, &client, &mut cookies, &db)?;
+
+    Ok(Flash::new(
+        Redirect::to("/"),
+        "info",
+        dict.flash_info_impersonation_started(user.username()),
+    ))
+}
+
+
+#[get("/admin_panel/permissions")]
+pub fn permissions(
+    _user: Require<perm::ManagePermissions>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let grants = permission::load_all_grants(&db)?;
+
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.permissions_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::permissions(locale, &grants))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct PermissionForm {
+    username: String,
+    permission: String,
+    action: String,
+}
+
+/// Grants a permission to a user or revokes it.
+#[post("/admin_panel/permissions", data = "<form>")]
+pub fn change_permission(
+    auth_user: Require<perm::ManagePermissions>,
+    form: Form<PermissionForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    let perm = match Permission::from_str(&form.permission) {
+        Some(perm) => perm,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel/permissions"),
+                bad_request(locale),
+            ));
+        }
+    };
+
+    let user = match User::load_by_username(form.username.trim(), &db)? {
+        Some(user) => user,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel/permissions"),
+                dict.flash_err_user_not_found(),
+            ));
+        }
+    };
+    let has_perm = auth_user.has_permission(perm, &db)?;
+    if !may_change_permission(&auth_user, has_perm, &user) {
+        return Ok(Flash::error(
+            Redirect::to("/admin_panel/permissions"),
+            dict.flash_err_change_permission_denied(user.username(), perm.as_str()),
+        ));
+    }
+
+    let msg = match form.action.as_str() {
+        "grant" => {
+            user.grant_permission(perm, &db)?;
+            dict.flash_success_permission_granted(user.username(), perm.as_str())
+        }
+        "revoke" => {
+            user.revoke_permission(perm, &db)?;
+            dict.flash_success_permission_revoked(user.username(), perm.as_str())
+        }
+        _ => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel/permissions"),
+                bad_request(locale),
+            ));
+        }
+    };
+
+    Ok(Flash::success(Redirect::to("/admin_panel/permissions"), msg))
+}
+
+/// Whether `caller` may grant a permission to `target` or revoke it.
+/// `caller_has_perm` says whether the caller has this permission. Admins may
+/// change everything. Everyone else may only pass on permissions they have
+/// and may change neither their own permissions nor those of admins;
+/// otherwise, `ManagePermissions` would be enough to get every permission.
+fn may_change_permission(caller: &User, caller_has_perm: bool, target: &User) -> bool {
+    caller.is_admin()
+        || (caller_has_perm && caller.id() != target.id() && may_manage_account(caller, target))
+}
+
+
 #[get("/admin_panel/state")]
 pub fn state(
-    _admin: AuthAdmin,
+    auth_user: Require<perm::ChangeAppState>,
     locale: Locale,
     db: State<Db>,
 ) -> Result<Page> {
-    let app_state = CurrentAppState::load(&db)?;
+    let dict = dict::new(locale).admin_panel;
+    let course = match auth_user.course() {
+        Some(course) => course,
+        None => return Ok(Page::error(dict.no_current_course())),
+    };
+    let app_state = CurrentAppState::load(course, &db)?;
 
     Page::empty()
-        .with_title(dict::new(locale).admin_panel.state_title())
+        .with_title(format!("{} – {}", dict.state_title(), course.name))
         .with_active_nav_route("/admin_panel")
         .with_content(html::state(locale, &app_state))
         .make_ok()
@@ -71,17 +903,22 @@ pub fn state(
 pub struct StateChange {
     state: String,
     reason: Option<String>,
+    notify: Option<String>,
 }
 
 #[post("/admin_panel/state", data = "<form>")]
 pub fn change_state(
-    _admin: AuthAdmin,
+    auth_user: Require<perm::ChangeAppState>,
     locale: Locale,
     form: Form<StateChange>,
     db: State<Db>,
 ) -> Result<Flash<Redirect>> {
     let dict = dict::new(locale).admin_panel;
     let form = form.into_inner();
+    let course = match auth_user.course() {
+        Some(course) => course,
+        None => return Ok(Flash::error(Redirect::to("/admin_panel"), dict.no_current_course())),
+    };
 
     let state = match form.state.as_str() {
         "preparation" => AppState::Preparation,
@@ -99,7 +936,22 @@ pub fn change_state(
     let reason = form.reason.filter(|r| !r.is_empty());
 
     // TODO: allow the user to specify the date
-    CurrentAppState::set(state, reason, None, &db)?;
+    let old_state = CurrentAppState::load(course, &db)?.state;
+    CurrentAppState::set(course, state, reason, None, &db)?;
+
+    // Tell students and tutors of the course that a new phase started. Whether a switch
+    // starts a new phase (e.g. "Frozen" -> "Running") cannot be known, so the
+    // admin decides.
+    let notification = match state {
+        AppState::Preparation => Some(Notification::PreparationStarted),
+        AppState::Running => Some(Notification::ScheduleReady),
+        AppState::Frozen => None,
+    };
+    if let Some(notification) = notification.filter(|_| form.notify.is_some() && old_state != state) {
+        let mut users = course.members(Role::Student, &db)?;
+        users.extend(course.members(Role::Tutor, &db)?);
+        notify::notify_all(&users, &notification, &db)?;
+    }
 
     Ok(Flash::success(
         Redirect::to("/admin_panel/state"),
@@ -110,15 +962,20 @@ pub fn change_state(
 
 #[get("/admin_panel/timeslots")]
 pub fn timeslots(
-    _admin: AuthAdmin,
+    auth_user: Require<perm::ManageTimeslots>,
     locale: Locale,
     db: State<Db>,
 ) -> Result<Page> {
-    let mut timeslots = TimeSlot::load_all(&db)?;
+    let dict = dict::new(locale).admin_panel;
+    let course = match auth_user.course() {
+        Some(course) => course,
+        None => return Ok(Page::error(dict.no_current_course())),
+    };
+    let mut timeslots = TimeSlot::load_all(course, &db)?;
     timeslots.sort();
 
     Page::empty()
-        .with_title(dict::new(locale).admin_panel.timeslots_title())
+        .with_title(format!("{} – {}", dict.timeslots_title(), course.name))
         .with_active_nav_route("/admin_panel")
         .with_content(html::timeslots(&timeslots, locale))
         .make_ok()
@@ -128,16 +985,26 @@ pub fn timeslots(
 pub struct NewTimeSlotForm {
     day: String,
     time: String,
+    duration: String,
 }
 
 #[post("/admin_panel/add_timeslot", data = "<form>")]
 pub fn add_timeslot(
-    _admin: AuthAdmin,
+    auth_user: Require<perm::ManageTimeslots>,
     form: Form<NewTimeSlotForm>,
     locale: Locale,
     db: State<Db>,
 ) -> Result<Flash<Redirect>> {
     let form = form.into_inner();
+    let course = match auth_user.course() {
+        Some(course) => course,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel"),
+                dict::new(locale).admin_panel.no_current_course(),
+            ));
+        }
+    };
 
     let day = match DayOfWeek::from_variant_str(&form.day) {
         Some(day) => day,
@@ -149,7 +1016,17 @@ pub fn add_timeslot(
         }
     };
 
-    let times = match timeslot::parse_time_interval(&form.time) {
+    let duration = match timeslot::parse_duration(&form.duration) {
+        Ok(duration) => duration,
+        Err(e) => {
+            return Ok(Flash::error(
+                Redirect::to("/admin_panel/timeslots"),
+                e,
+            ));
+        }
+    };
+
+    let times = match timeslot::parse_time_interval(&form.time, duration) {
         Ok(times) => times,
         Err(e) => {
             return Ok(Flash::error(
@@ -161,9 +1038,19 @@ pub fn add_timeslot(
 
     let count = times.len();
     let slots: Vec<_> = times.into_iter()
-        .map(|time| NewTimeSlot::new(day, time))
+        .map(|time| NewTimeSlot::new(course, day, time, duration))
         .collect();
 
+    // Timeslots with different lengths could overlap without starting at the
+    // same time.
+    let existing = TimeSlot::load_all(course, &db)?;
+    if slots.iter().any(|new| existing.iter().any(|slot| new.overlaps(slot))) {
+        return Ok(Flash::error(
+            Redirect::to("/admin_panel/timeslots"),
+            "(Some of) the given timeslots overlap existing ones! Please only add new ones!",
+        ));
+    }
+
 
     match TimeSlot::create_all(&slots, &db) {
         Ok(_) => {},
@@ -192,12 +1079,16 @@ pub struct DeleteTimeSlotForm {
 
 #[post("/admin_panel/delete_timeslot", data = "<form>")]
 pub fn delete_timeslot(
-    _admin: AuthAdmin,
+    auth_user: Require<perm::ManageTimeslots>,
     form: Form<DeleteTimeSlotForm>,
     db: State<Db>,
 ) -> Result<Flash<Redirect>> {
     let form = form.into_inner();
-    if TimeSlot::delete(form.id, &db)? {
+    let deleted = match auth_user.course() {
+        Some(course) => TimeSlot::delete(course, form.id, &db)?,
+        None => false,
+    };
+    if deleted {
         Ok(Flash::success(
             Redirect::to("/admin_panel/timeslots"),
             "Timeslot was successfully deleted",
@@ -209,3 +1100,491 @@ pub fn delete_timeslot(
         ))
     }
 }
+
+
+
+#[get("/admin_panel/rooms")]
+pub fn rooms(
+    auth_user: Require<perm::ManageTimeslots>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let dict = dict::new(locale).admin_panel;
+    let course = match auth_user.course() {
+        Some(course) => course,
+        None => return Ok(Page::error(dict.no_current_course())),
+    };
+
+    let mut timeslots = TimeSlot::load_all(course, &db)?;
+    timeslots.sort();
+    let mut rooms = Vec::new();
+    for room in Room::load_all(course, &db)? {
+        let available = room.available_timeslots(&db)?;
+        rooms.push((room, available));
+    }
+
+    Page::empty()
+        .with_title(format!("{} – {}", dict.rooms_title(), course.name))
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::rooms(locale, &rooms, &timeslots))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct NewRoomForm {
+    name: String,
+    capacity: String,
+}
+
+#[post("/admin_panel/rooms", data = "<form>")]
+pub fn create_room(
+    auth_user: Require<perm::ManageTimeslots>,
+    form: Form<NewRoomForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let err = |msg| Ok(Flash::error(Redirect::to("/admin_panel/rooms"), msg));
+
+    let course = match auth_user.course() {
+        Some(course) => course,
+        None => return err(dict.no_current_course()),
+    };
+    let name = form.name.trim();
+    if name.is_empty() {
+        return err(dict.flash_err_room_name_empty());
+    }
+    let capacity = match parse_capacity(&form.capacity) {
+        Some(capacity) => capacity,
+        None => return err(dict.flash_err_invalid_capacity(&form.capacity)),
+    };
+
+    match Room::create(course, name, capacity, &db) {
+        Ok(_) => {}
+        Err(Error(ErrorKind::Db(
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)
+        ), _)) => {
+            return err(dict.flash_err_room_exists(name));
+        }
+        Err(e) => bail!(e),
+    }
+
+    Ok(Flash::success(Redirect::to("/admin_panel/rooms"), dict.flash_success_room_created(name)))
+}
+
+/// Name, capacity and the timeslots in which the room is available. The
+/// timeslots are sent as checkboxes named `slot-<id>`.
+#[derive(Debug)]
+pub struct RoomForm {
+    name: String,
+    capacity: String,
+    slots: Vec<i16>,
+}
+
+impl<'f> FromForm<'f> for RoomForm {
+    type Error = ();
+    fn from_form(items: &mut FormItems<'f>, _: bool) -> StdResult<Self, Self::Error> {
+        let mut name = None;
+        let mut capacity = None;
+        let mut slots = Vec::new();
+
+        for (key, value) in items {
+            let value = value.url_decode().map_err(|_| ())?;
+            match key.as_str() {
+                "name" => name = Some(value),
+                "capacity" => capacity = Some(value),
+                key if key.starts_with("slot-") => {
+                    slots.push(key[5..].parse().map_err(|_| ())?);
+                }
+                _ => return Err(()),
+            }
+        }
+
+        match (name, capacity) {
+            (Some(name), Some(capacity)) => Ok(Self { name, capacity, slots }),
+            _ => Err(()),
+        }
+    }
+}
+
+#[post("/admin_panel/rooms/<id>", data = "<form>")]
+pub fn update_room(
+    id: i16,
+    auth_user: Require<perm::ManageTimeslots>,
+    form: Form<RoomForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+    let err = |msg| Ok(Flash::error(Redirect::to("/admin_panel/rooms"), msg));
+
+    let mut room = match auth_user.course() {
+        Some(course) => match Room::load_by_id(course, id, &db)? {
+            Some(room) => room,
+            None => return err(bad_request(locale)),
+        },
+        None => return err(dict.no_current_course()),
+    };
+    let name = form.name.trim();
+    if name.is_empty() {
+        return err(dict.flash_err_room_name_empty());
+    }
+    let capacity = match parse_capacity(&form.capacity) {
+        Some(capacity) => capacity,
+        None => return err(dict.flash_err_invalid_capacity(&form.capacity)),
+    };
+
+    match room.update(name, capacity, &form.slots, &db) {
+        Ok(()) => {}
+        Err(Error(ErrorKind::Db(
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)
+        ), _)) => {
+            return err(dict.flash_err_room_exists(name));
+        }
+        Err(e) => bail!(e),
+    }
+
+    Ok(Flash::success(Redirect::to("/admin_panel/rooms"), dict.flash_success_room_updated(name)))
+}
+
+#[post("/admin_panel/rooms/<id>/delete")]
+pub fn delete_room(
+    id: i16,
+    auth_user: Require<perm::ManageTimeslots>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let room = match auth_user.course() {
+        Some(course) => Room::load_by_id(course, id, &db)?,
+        None => None,
+    };
+
+    match room {
+        Some(room) => {
+            let name = room.name.clone();
+            room.delete(&db)?;
+            Ok(Flash::success(
+                Redirect::to("/admin_panel/rooms"),
+                dict::new(locale).admin_panel.flash_success_room_deleted(&name),
+            ))
+        }
+        None => Ok(Flash::error(Redirect::to("/admin_panel/rooms"), bad_request(locale))),
+    }
+}
+
+/// Parses the number of seats of a room, which has to be positive.
+fn parse_capacity(input: &str) -> Option<i16> {
+    input.trim().parse().ok().filter(|&capacity| capacity > 0)
+}
+
+
+#[get("/admin_panel/semester")]
+pub fn semester(
+    auth_user: Require<perm::ManageTimeslots>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let calendar = Calendar::load(&db)?;
+    let timeslots = match auth_user.course() {
+        Some(course) => TimeSlot::load_all(course, &db)?,
+        None => vec![],
+    };
+
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.semester_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::semester(locale, calendar.as_ref(), &timeslots))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct SemesterForm {
+    name: String,
+    lecture_start: String,
+    lecture_end: String,
+}
+
+#[post("/admin_panel/semester", data = "<form>")]
+pub fn change_semester(
+    _user: Require<perm::ManageTimeslots>,
+    form: Form<SemesterForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    fn err<S: AsRef<str>>(msg: S) -> Result<Flash<Redirect>> {
+        Ok(Flash::error(Redirect::to("/admin_panel/semester"), msg))
+    }
+
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    let name = form.name.trim();
+    if name.is_empty() {
+        return err(dict.flash_err_semester_name_empty());
+    }
+    let lecture_start = match parse_date(&form.lecture_start) {
+        Some(date) => date,
+        None => return err(dict.flash_err_invalid_date(&form.lecture_start)),
+    };
+    let lecture_end = match parse_date(&form.lecture_end) {
+        Some(date) => date,
+        None => return err(dict.flash_err_invalid_date(&form.lecture_end)),
+    };
+    if lecture_start > lecture_end {
+        return err(dict.flash_err_date_order());
+    }
+
+    Semester::set(name, lecture_start, lecture_end, &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/semester"),
+        dict.flash_success_semester_saved(),
+    ))
+}
+
+#[derive(FromForm)]
+pub struct DayOffForm {
+    first_day: String,
+    last_day: String,
+    description: String,
+}
+
+#[post("/admin_panel/semester/days_off", data = "<form>")]
+pub fn add_day_off(
+    _user: Require<perm::ManageTimeslots>,
+    form: Form<DayOffForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    fn err<S: AsRef<str>>(msg: S) -> Result<Flash<Redirect>> {
+        Ok(Flash::error(Redirect::to("/admin_panel/semester"), msg))
+    }
+
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    let first_day = match parse_date(&form.first_day) {
+        Some(date) => date,
+        None => return err(dict.flash_err_invalid_date(&form.first_day)),
+    };
+    let last_day = if form.last_day.trim().is_empty() {
+        first_day
+    } else {
+        match parse_date(&form.last_day) {
+            Some(date) => date,
+            None => return err(dict.flash_err_invalid_date(&form.last_day)),
+        }
+    };
+    if first_day > last_day {
+        return err(dict.flash_err_date_order());
+    }
+
+    let description = form.description.trim();
+    if description.is_empty() {
+        return err(dict.flash_err_day_off_description_empty());
+    }
+
+    DayOff::create(first_day, last_day, description, &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/semester"),
+        dict.flash_success_day_off_added(),
+    ))
+}
+
+#[post("/admin_panel/semester/days_off/<id>/delete")]
+pub fn delete_day_off(
+    id: i32,
+    _user: Require<perm::ManageTimeslots>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    if !DayOff::delete(id, &db)? {
+        return Ok(Flash::error(Redirect::to("/admin_panel/semester"), bad_request(locale)));
+    }
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/semester"),
+        dict::new(locale).admin_panel.flash_success_day_off_deleted(),
+    ))
+}
+
+/// Parses a date as sent by a `date` input (`2017-10-16`). Browsers without
+/// support for that input show a text field, so `16.10.2017` is accepted,
+/// too.
+fn parse_date(input: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%d.%m.%Y"].iter()
+        .filter_map(|format| NaiveDate::parse_from_str(input.trim(), format).ok())
+        .next()
+}
+
+#[get("/admin_panel/announcements")]
+pub fn announcements(
+    _user: Require<perm::ManageAnnouncements>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Page> {
+    let announcements = Announcement::load_all(&db)?;
+
+    Page::empty()
+        .with_title(dict::new(locale).admin_panel.announcements_title())
+        .with_active_nav_route("/admin_panel")
+        .with_content(html::announcements(locale, &announcements))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct AnnouncementForm {
+    body: String,
+    for_students: Option<String>,
+    for_tutors: Option<String>,
+    for_admins: Option<String>,
+    starts: String,
+    ends: String,
+}
+
+#[post("/admin_panel/announcements", data = "<form>")]
+pub fn create_announcement(
+    _user: Require<perm::ManageAnnouncements>,
+    form: Form<AnnouncementForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    fn err<S: AsRef<str>>(msg: S) -> Result<Flash<Redirect>> {
+        Ok(Flash::error(Redirect::to("/admin_panel/announcements"), msg))
+    }
+
+    let dict = dict::new(locale).admin_panel;
+    let form = form.into_inner();
+
+    if form.body.trim().is_empty() {
+        return err(dict.flash_err_announcement_empty());
+    }
+
+    let new = NewAnnouncement {
+        body: form.body.trim().to_owned(),
+        for_students: form.for_students.is_some(),
+        for_tutors: form.for_tutors.is_some(),
+        for_admins: form.for_admins.is_some(),
+        starts: match parse_local_datetime(&form.starts) {
+            Ok(starts) => starts,
+            Err(_) => return err(dict.flash_err_invalid_datetime(&form.starts)),
+        },
+        ends: match parse_local_datetime(&form.ends) {
+            Ok(ends) => ends,
+            Err(_) => return err(dict.flash_err_invalid_datetime(&form.ends)),
+        },
+    };
+
+    if !new.for_students && !new.for_tutors && !new.for_admins {
+        return err(dict.flash_err_announcement_no_roles());
+    }
+    if let (Some(starts), Some(ends)) = (new.starts, new.ends) {
+        if starts >= ends {
+            return err(dict.flash_err_announcement_window());
+        }
+    }
+
+    new.create(&db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/admin_panel/announcements"),
+        dict.flash_success_announcement_created(),
+    ))
+}
+
+/// Parses a point in time in the local timezone as sent by a
+/// `datetime-local` input (`2017-09-28T14:00`). Browsers without support for
+/// that input show a text field, so `28.09.2017 14:00` is accepted, too.
+/// Empty input is `None`.
+fn parse_local_datetime(input: &str) -> StdResult<Option<DateTime<Utc>>, ()> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+
+    ["%Y-%m-%dT%H:%M", "%d.%m.%Y %H:%M"].iter()
+        .filter_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .filter_map(|naive| Local.from_local_datetime(&naive).single())
+        .map(|local| Some(local.with_timezone(&Utc)))
+        .next()
+        .ok_or(())
+}
+
+#[post("/admin_panel/announcements/<id>/delete")]
+pub fn delete_announcement(
+    id: i64,
+    _user: Require<perm::ManageAnnouncements>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).admin_panel;
+
+    match Announcement::load_by_id(id, &db)? {
+        Some(announcement) => {
+            announcement.delete(&db)?;
+            Ok(Flash::success(
+                Redirect::to("/admin_panel/announcements"),
+                dict.flash_success_announcement_deleted(),
+            ))
+        }
+        None => Ok(Flash::error(Redirect::to("/admin_panel/announcements"), bad_request(locale))),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: i64, role: Role) -> User {
+        User {
+            id,
+            username: format!("user{}", id),
+            name: None,
+            role,
+            email: None,
+            matriculation_number: None,
+            study_program: None,
+            semester: None,
+        }
+    }
+
+    #[test]
+    fn only_admins_manage_admins() {
+        let admin = user(1, Role::Admin);
+        let other_admin = user(2, Role::Admin);
+        let tutor = user(3, Role::Tutor);
+        let student = user(4, Role::Student);
+
+        // Users with `ImpersonateUsers` or `ManageUsers` which aren't admins
+        // must not be able to impersonate admins or take over their accounts.
+        assert!(!may_manage_account(&tutor, &admin));
+        assert!(!may_manage_account(&student, &admin));
+
+        assert!(may_manage_account(&admin, &other_admin));
+        assert!(may_manage_account(&admin, &tutor));
+        assert!(may_manage_account(&tutor, &student));
+        assert!(may_manage_account(&tutor, &tutor));
+    }
+
+    #[test]
+    fn permissions_are_only_passed_on() {
+        let admin = user(1, Role::Admin);
+        let tutor = user(2, Role::Tutor);
+        let other_tutor = user(3, Role::Tutor);
+
+        assert!(may_change_permission(&admin, true, &tutor));
+        assert!(may_change_permission(&admin, true, &admin));
+        assert!(may_change_permission(&tutor, true, &other_tutor));
+
+        // Non-admins can neither grant permissions they don't have, nor
+        // change their own permissions or those of admins.
+        assert!(!may_change_permission(&tutor, false, &other_tutor));
+        assert!(!may_change_permission(&tutor, true, &tutor));
+        assert!(!may_change_permission(&tutor, false, &tutor));
+        assert!(!may_change_permission(&tutor, true, &admin));
+    }
+}
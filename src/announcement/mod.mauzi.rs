@@ -0,0 +1,4 @@
+unit dismiss {
+    De => "Ausblenden",
+    En => "Dismiss",
+}
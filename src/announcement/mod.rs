@@ -0,0 +1,237 @@
+//! Announcements by admins. **Has routes.**
+//!
+//! An announcement is a short Markdown text shown as info flash on every page
+//! (see `template::Page`). It can be restricted to certain roles and to a
+//! time window. Users can dismiss announcements they have read.
+
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use maud::{Markup, PreEscaped};
+use pulldown_cmark::{html, Event, Parser, Tag};
+
+use db::Db;
+use db::schema::{announcements, dismissed_announcements};
+use errors::*;
+use user::{Role, User};
+
+pub mod routes;
+
+
+/// An announcement, as stored in the database.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable)]
+#[table_name = "announcements"]
+pub struct Announcement {
+    id: i64,
+    pub body: String,
+    pub for_students: bool,
+    pub for_tutors: bool,
+    pub for_admins: bool,
+    pub starts: Option<DateTime<Utc>>,
+    pub ends: Option<DateTime<Utc>>,
+    pub created: DateTime<Utc>,
+}
+
+/// An announcement which is not yet stored in the database.
+#[derive(Debug, Clone, Insertable)]
+#[table_name = "announcements"]
+pub struct NewAnnouncement {
+    pub body: String,
+    pub for_students: bool,
+    pub for_tutors: bool,
+    pub for_admins: bool,
+    pub starts: Option<DateTime<Utc>>,
+    pub ends: Option<DateTime<Utc>>,
+}
+
+impl NewAnnouncement {
+    pub fn create(&self, db: &Db) -> Result<Announcement> {
+        diesel::insert(self)
+            .into(announcements::table)
+            .get_result(&*db.conn()?)
+            .chain_err(|| "failed to insert announcement")
+    }
+}
+
+impl Announcement {
+    /// Loads all announcements, newest first.
+    pub fn load_all(db: &Db) -> Result<Vec<Self>> {
+        announcements::table
+            .order(announcements::created.desc())
+            .load(&*db.conn()?)
+            .chain_err(|| "failed to load announcements")
+    }
+
+    pub fn load_by_id(id: i64, db: &Db) -> Result<Option<Self>> {
+        announcements::table
+            .find(id)
+            .first(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to load announcement")
+    }
+
+    /// Loads all announcements which should be shown to the given user right
+    /// now: they target `role` (the role of the user in the current course,
+    /// see `AuthUser::course_role()`), are in their time window and weren't
+    /// dismissed by the user. Oldest first.
+    pub fn load_active_for(user: &User, role: Role, db: &Db) -> Result<Vec<Self>> {
+        let conn = &*db.conn()?;
+        let now = Utc::now();
+
+        let dismissed = dismissed_announcements::table
+            .filter(dismissed_announcements::user_id.eq(user.id()))
+            .select(dismissed_announcements::announcement_id)
+            .load::<i64>(conn)?;
+
+        // There are only a few announcements at any time, so we filter them
+        // here instead of in the query.
+        let all = announcements::table
+            .filter(announcements::starts.is_null().or(announcements::starts.le(now)))
+            .filter(announcements::ends.is_null().or(announcements::ends.gt(now)))
+            .order(announcements::created.asc())
+            .load::<Self>(conn)?;
+
+        all.into_iter()
+            .filter(|a| a.is_for(role) && !dismissed.contains(&a.id))
+            .collect::<Vec<_>>()
+            .make_ok()
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// Returns whether users with the given role should see this
+    /// announcement.
+    pub fn is_for(&self, role: Role) -> bool {
+        match role {
+            Role::Student => self.for_students,
+            Role::Tutor => self.for_tutors,
+            Role::Admin => self.for_admins,
+        }
+    }
+
+    /// Renders the Markdown body as HTML. HTML in the body is escaped and
+    /// links and images with unsafe URLs (see `is_safe_url()`) are emptied.
+    pub fn render(&self) -> Markup {
+        let parser = Parser::new(&self.body).map(|event| match event {
+            Event::Html(s) | Event::InlineHtml(s) => Event::Text(s),
+            Event::Start(Tag::Link(ref dest, ref title)) if !is_safe_url(dest) => {
+                Event::Start(Tag::Link("".into(), title.clone()))
+            }
+            Event::Start(Tag::Image(ref dest, ref title)) if !is_safe_url(dest) => {
+                Event::Start(Tag::Image("".into(), title.clone()))
+            }
+            other => other,
+        });
+
+        let mut out = String::new();
+        html::push_html(&mut out, parser);
+        PreEscaped(out)
+    }
+
+    /// Hides this announcement for the given user.
+    pub fn dismiss_for(&self, user: &User, db: &Db) -> Result<()> {
+        #[derive(Insertable)]
+        #[table_name = "dismissed_announcements"]
+        struct Dismissal {
+            user_id: i64,
+            announcement_id: i64,
+        }
+
+        let dismissal = Dismissal {
+            user_id: user.id(),
+            announcement_id: self.id,
+        };
+
+        let res = diesel::insert(&dismissal)
+            .into(dismissed_announcements::table)
+            .execute(&*db.conn()?);
+
+        // Dismissing twice (e.g. with two open tabs) is fine.
+        match res {
+            Ok(_) | Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Ok(()),
+            Err(e) => Err(e).chain_err(|| "failed to dismiss announcement"),
+        }
+    }
+
+    pub fn delete(self, db: &Db) -> Result<()> {
+        diesel::delete(&self)
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to delete announcement")?;
+
+        Ok(())
+    }
+}
+
+/// Returns whether a link or image in an announcement may point to the given
+/// URL. Only relative URLs and the schemes `http`, `https` and `mailto` are
+/// allowed: `javascript:` URLs, for example, would run in the context of
+/// this website.
+fn is_safe_url(url: &str) -> bool {
+    // Browsers ignore leading whitespace and control characters.
+    let url = url.trim_left_matches(|c: char| c.is_whitespace() || c.is_control());
+
+    // A colon before the first `/`, `?` or `#` ends the scheme. Everything
+    // else is a relative URL.
+    let end = url.find(|c| c == '/' || c == '?' || c == '#').unwrap_or(url.len());
+    match url[..end].find(':') {
+        None => true,
+        Some(colon) => {
+            let scheme = url[..colon].to_lowercase();
+            scheme == "http" || scheme == "https" || scheme == "mailto"
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(body: &str) -> String {
+        let announcement = Announcement {
+            id: 1,
+            body: body.into(),
+            for_students: true,
+            for_tutors: true,
+            for_admins: true,
+            starts: None,
+            ends: None,
+            created: Utc::now(),
+        };
+
+        announcement.render().0
+    }
+
+    #[test]
+    fn render_links() {
+        assert!(render("[Forum](https://example.org/forum)").contains("href=\"https://example.org/forum\""));
+        assert!(!render("[Forum](javascript:alert(1))").contains("javascript"));
+        assert!(!render("<javascript:alert(1)>").contains("href=\"javascript"));
+        assert!(!render("![Bild](javascript:alert(1))").contains("javascript"));
+        assert!(!render("<script>alert(1)</script>").contains("<script>"));
+    }
+
+    #[test]
+    fn safe_urls() {
+        assert!(is_safe_url("https://www.uni-osnabrueck.de"));
+        assert!(is_safe_url("HTTP://example.org/a:b"));
+        assert!(is_safe_url("mailto:tutoren@example.org"));
+        assert!(is_safe_url("/settings"));
+        assert!(is_safe_url("settings?tab=a:b"));
+        assert!(is_safe_url("#top"));
+        assert!(is_safe_url(""));
+    }
+
+    #[test]
+    fn unsafe_urls() {
+        assert!(!is_safe_url("javascript:alert(1)"));
+        assert!(!is_safe_url("JavaScript:alert(1)"));
+        assert!(!is_safe_url(" \u{1}javascript:alert(1)"));
+        assert!(!is_safe_url("java\tscript:alert(1)"));
+        assert!(!is_safe_url("data:text/html,<script>alert(1)</script>"));
+        assert!(!is_safe_url("vbscript:msgbox(1)"));
+    }
+}
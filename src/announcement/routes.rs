@@ -0,0 +1,40 @@
+use rocket::State;
+use rocket::request::Form;
+use rocket::response::Redirect;
+
+use db::Db;
+use errors::*;
+use login;
+use super::Announcement;
+use user::AuthUser;
+
+
+#[derive(FromForm)]
+pub struct DismissForm {
+    /// The page the user was on. They are sent back there.
+    redirect: String,
+}
+
+#[post("/announcements/<id>/dismiss", data = "<form>")]
+pub fn dismiss(
+    id: i64,
+    auth_user: AuthUser,
+    form: Form<DismissForm>,
+    db: State<Db>,
+) -> Result<Redirect> {
+    // Only redirect to pages of this website.
+    let redirect = form.into_inner().redirect;
+    let redirect = if login::is_safe_redirect_target(&redirect) {
+        redirect
+    } else {
+        "/".into()
+    };
+
+    // The announcement might have been deleted in the meantime. Then there
+    // is nothing to dismiss.
+    if let Some(announcement) = Announcement::load_by_id(id, &db)? {
+        announcement.dismiss_for(&auth_user, &db)?;
+    }
+
+    Ok(Redirect::to(&redirect))
+}
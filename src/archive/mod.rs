@@ -0,0 +1,168 @@
+//! Archives of finished semesters.
+//!
+//! At the end of a semester, all data of a course is copied into a new
+//! PostgreSQL schema (e.g. `archive_default_20180216120000`) and the course
+//! is rolled over to the next semester: the schedule, all timeslot ratings
+//! and the preferences are reset and the app state is set to "preparation".
+//! User accounts, roles in the course, timeslots and rooms are kept.
+//!
+//! Archives are not shown on the website; they can be inspected with `psql`,
+//! e.g. `select * from archive_default_20180216120000.testats;`. A course can
+//! be archived in the admin panel or with `manage_db archive`.
+
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+
+use course::Course;
+use db::Db;
+use db::schema::{
+    archives, current_app_state, prep_student_preferences, testats, timeslot_ratings, timeslots,
+};
+use errors::*;
+use state::AppState;
+
+
+/// An archive of one course, as stored in the database.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable)]
+#[table_name = "archives"]
+pub struct Archive {
+    id: i32,
+
+    /// `None` if the course was deleted.
+    pub course_id: Option<i16>,
+    pub course_name: String,
+
+    /// The schema containing the archived data.
+    pub schema_name: String,
+    pub created: DateTime<Utc>,
+}
+
+impl Archive {
+    /// Loads all archives, the newest first.
+    pub fn load_all(db: &Db) -> Result<Vec<Self>> {
+        archives::table
+            .order(archives::id.desc())
+            .load(&*db.conn()?)
+            .chain_err(|| "failed to load archives")
+    }
+
+    /// Archives all data of the given course and rolls it over to the next
+    /// semester (see the module documentation). Either everything or nothing
+    /// is done.
+    pub fn create(course: &Course, db: &Db) -> Result<Self> {
+        #[derive(Insertable)]
+        #[table_name = "archives"]
+        struct NewArchive<'a> {
+            course_id: i16,
+            course_name: &'a str,
+            schema_name: &'a str,
+        }
+
+        // Slugs only consist of lowercase letters, digits and '-', so the
+        // name is a valid identifier and can be put into the SQL directly.
+        let schema_name = format!(
+            "archive_{}_{}",
+            course.slug.replace('-', "_"),
+            Utc::now().format("%Y%m%d%H%M%S"),
+        );
+
+        // Enum values are archived as text: otherwise, the enum types could
+        // not be recreated in future migrations.
+        let snapshot = format!(r#"
+            create schema {s};
+
+            create table {s}.members as
+                select users.id as user_id, users.username, users.name, users.email,
+                    users.matriculation_number, course_members.role::text as role
+                from course_members inner join users on users.id = course_members.user_id
+                where course_members.course_id = {id};
+
+            create table {s}.timeslots as
+                select id, day::text as day, time, duration
+                from timeslots
+                where course_id = {id};
+
+            create table {s}.preferences as
+                select user_id, partner, prefers_english
+                from prep_student_preferences
+                where course_id = {id};
+
+            create table {s}.ratings as
+                select user_id, timeslot_id, rating::text as rating
+                from timeslot_ratings
+                where timeslot_id in (select id from timeslots where course_id = {id});
+
+            create table {s}.rooms as
+                select id, name, capacity
+                from rooms
+                where course_id = {id};
+
+            create table {s}.room_availability as
+                select room_id, timeslot_id
+                from room_availability
+                where room_id in (select id from rooms where course_id = {id});
+
+            create table {s}.testats as
+                select student_id, tutor_id, timeslot_id, room_id
+                from testats
+                where course_id = {id};
+
+            create table {s}.semester as
+                select name, lecture_start, lecture_end from semester;
+
+            create table {s}.days_off as
+                select first_day, last_day, description from semester_days_off;
+        "#, s = schema_name, id = course.id());
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            conn.batch_execute(&snapshot)
+                .chain_err(|| format!("failed to copy data of course '{}' into archive", course.slug))?;
+
+            // Reset everything belonging to the semester. Ratings are
+            // recreated with default values when they are loaded next time.
+            diesel::delete(testats::table.filter(testats::course_id.eq(course.id())))
+                .execute(conn)?;
+
+            let slot_ids = timeslots::table
+                .filter(timeslots::course_id.eq(course.id()))
+                .select(timeslots::id)
+                .load::<i16>(conn)?;
+            diesel::delete(timeslot_ratings::table.filter(timeslot_ratings::timeslot_id.eq_any(slot_ids)))
+                .execute(conn)?;
+
+            diesel::update(
+                prep_student_preferences::table.filter(prep_student_preferences::course_id.eq(course.id()))
+            )
+                .set((
+                    prep_student_preferences::partner.eq(None::<String>),
+                    prep_student_preferences::prefers_english.eq(false),
+                ))
+                .execute(conn)?;
+
+            diesel::update(current_app_state::table.find(course.id()))
+                .set((
+                    current_app_state::state.eq(&AppState::Preparation),
+                    current_app_state::reason.eq(None::<String>),
+                    current_app_state::next_state_switch.eq(None::<DateTime<Utc>>),
+                ))
+                .execute(conn)
+                .chain_err(|| "failed to reset app state")?;
+
+            diesel::insert(&NewArchive {
+                course_id: course.id(),
+                course_name: &course.name,
+                schema_name: &schema_name,
+            })
+                .into(archives::table)
+                .get_result(conn)
+                .chain_err(|| "failed to insert archive")
+        })
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+}
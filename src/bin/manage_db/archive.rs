@@ -0,0 +1,34 @@
+use clap::ArgMatches;
+use std::io::{self, Write};
+
+use luten::archive::Archive;
+use luten::course::Course;
+use luten::db::Db;
+use luten::errors::*;
+
+use util::{self, Global};
+
+
+/// Archive a course and roll it over to the next semester.
+pub fn archive(util: &Global, matches: &ArgMatches, db: &Db) -> Result<()> {
+    let slug = matches.value_of("course").unwrap();
+    let course = Course::load_by_slug(slug, db)?
+        .ok_or_else(|| format!("course '{}' does not exist", slug))?;
+
+    util.debug_output(&course);
+    if !matches.is_present("yes") {
+        print!("Archive this course and reset ratings, preferences and testats? [y/N] ");
+        io::stdout().flush()?;
+        if util::read_trimmed_line()?.to_lowercase() != "y" {
+            println!("Aborted, nothing was changed.");
+            return Ok(());
+        }
+    }
+
+    let archive = Archive::create(&course, db)?;
+
+    println!("+-- Archived into schema '{}'. The course is in preparation now.", archive.schema_name);
+    util.debug_output(archive);
+
+    Ok(())
+}
@@ -62,6 +62,51 @@ pub fn create(util: &Global, matches: &ArgMatches, db: &Db) -> Result<()> {
             println!("+-- Inserted:");
             util.debug_output(result);
         }
+        "testat" => {
+            use luten::course::Course;
+            use luten::room::Room;
+            use luten::testat::Testat;
+            use luten::timeslot::TimeSlot;
+
+            let timeslot_id = util::read("timeslot id")?;
+            let timeslot = TimeSlot::load_from_id(timeslot_id, db)?
+                .ok_or_else(|| format!("timeslot with id {} does not exist", timeslot_id))?;
+            let course = Course::load_by_id(timeslot.course_id(), db)?
+                .ok_or("course of timeslot does not exist")?;
+            println!("");
+
+            println!("### Choose the student!");
+            let student = match find_user(db)?.into_student_of(&course, db)? {
+                Ok(student) => student,
+                Err(user) => return Err(
+                    format!("user '{}' is not a student in '{}'", user.username(), course.slug).into()
+                ),
+            };
+            println!("");
+
+            println!("### Choose the tutor!");
+            let tutor = match find_user(db)?.into_tutor_of(&course, db)? {
+                Ok(tutor) => tutor,
+                Err(user) => return Err(
+                    format!("user '{}' is not a tutor in '{}'", user.username(), course.slug).into()
+                ),
+            };
+            println!("");
+
+            let room_id: Option<i16> = util::read("room id")?;
+            let room = match room_id {
+                Some(id) => Some(
+                    Room::load_by_id(&course, id, db)?
+                        .ok_or_else(|| format!("room with id {} does not exist in '{}'", id, course.slug))?
+                ),
+                None => None,
+            };
+
+            let result = Testat::assign(&student, &tutor, &timeslot, room.as_ref(), db)?;
+
+            println!("+-- Inserted:");
+            util.debug_output(result);
+        }
         _ => unreachable!(),
     }
 
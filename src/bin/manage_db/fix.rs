@@ -1,11 +1,12 @@
 use clap::ArgMatches;
 use diesel::prelude::*;
 
+use luten::course::Course;
 use luten::db::Db;
-use luten::db::schema::{prep_student_preferences, users};
+use luten::db::schema::prep_student_preferences;
 use luten::errors::*;
 use luten::prep::StudentPreferences;
-use luten::user::User;
+use luten::user::Role;
 
 use util::Global;
 
@@ -14,19 +15,27 @@ use util::Global;
 pub fn fix(_glob: &Global, matches: &ArgMatches, db: &Db) -> Result<()> {
     match matches.subcommand_name().unwrap() {
         "missing_prep_preferences" => {
-            use diesel::expression::dsl::sql;
+            for course in Course::load_all(db)? {
+                // All students of the course with preferences
+                let with_prefs = prep_student_preferences::table
+                    .filter(prep_student_preferences::course_id.eq(course.id()))
+                    .select(prep_student_preferences::user_id)
+                    .load::<i64>(&*db.conn()?)?;
 
-            // All students
-            let res = users::table
-                .filter(sql("role='student'"))
-                .left_join(prep_student_preferences::table)
-                .filter(sql("user_id is null"))
-                .load::<(User, Option<StudentPreferences>)>(&*db.conn()?)?;
+                for user in course.members(Role::Student, db)? {
+                    if with_prefs.contains(&user.id()) {
+                        continue;
+                    }
 
-            for (user, _) in res {
-                let student = user.into_student().unwrap();
-                StudentPreferences::create_default(&student, db)?;
-                println!("Created preferences for #{} (@{})", student.id(), student.username());
+                    let student = user.into_student_of(&course, db)?.unwrap();
+                    StudentPreferences::create_default(&student, &course, db)?;
+                    println!(
+                        "Created preferences for #{} (@{}) in '{}'",
+                        student.id(),
+                        student.username(),
+                        course.slug,
+                    );
+                }
             }
         }
         _ => unreachable!(),
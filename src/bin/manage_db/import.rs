@@ -0,0 +1,84 @@
+use clap::ArgMatches;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use luten::db::Db;
+use luten::errors::*;
+use luten::user::Role;
+use luten::user::import::{self, ConflictKind, ImportPlan};
+
+use util::{self, Global};
+
+
+/// Import entities from files.
+pub fn import(util: &Global, matches: &ArgMatches, db: &Db) -> Result<()> {
+    match matches.subcommand() {
+        ("users", Some(matches)) => {
+            let path = matches.value_of("file").unwrap();
+            let role = matches.value_of("role").unwrap();
+            let role = Role::from_str(role)
+                .ok_or_else(|| format!("'{}' is not a valid user role", role))?;
+
+            let mut content = String::new();
+            File::open(path)
+                .and_then(|mut f| f.read_to_string(&mut content))
+                .chain_err(|| format!("failed to read '{}'", path))?;
+
+            let rows = import::parse_csv(&content)
+                .chain_err(|| format!("failed to parse '{}'", path))?;
+            let plan = ImportPlan::prepare(rows, role, db)?;
+
+            println!("+-- New users ({}):", plan.new_users.len());
+            for row in &plan.new_users {
+                util.debug_output(row);
+            }
+            println!("+-- Updated users ({}):", plan.updates.len());
+            for &(ref user, ref row) in &plan.updates {
+                util.debug_output(user);
+                println!("  -> {:?}", row);
+            }
+            println!("+-- Unchanged users: {}", plan.unchanged.len());
+            println!("+-- Conflicts, will be skipped ({}):", plan.conflicts.len());
+            for conflict in &plan.conflicts {
+                let reason = match conflict.kind {
+                    ConflictKind::DuplicateInFile => "duplicate username in file".to_owned(),
+                    ConflictKind::RoleMismatch(role) => {
+                        format!("user exists with role '{}'", role.as_str())
+                    }
+                    ConflictKind::InvalidProfile(ref e) => e.to_string(),
+                    ConflictKind::MatriculationNumberTaken => {
+                        "matriculation number belongs to another user".to_owned()
+                    }
+                };
+                println!("line {} (@{}): {}", conflict.row.line, conflict.row.username, reason);
+            }
+            println!("");
+
+            if plan.is_noop() {
+                println!("Nothing to do.");
+                return Ok(());
+            }
+
+            if !matches.is_present("yes") {
+                print!("Apply import? [y/N] ");
+                io::stdout().flush()?;
+                if util::read_trimmed_line()?.to_lowercase() != "y" {
+                    println!("Aborted, nothing was changed.");
+                    return Ok(());
+                }
+            }
+
+            let summary = plan.apply(db)?;
+            println!(
+                "+-- Done: {} created, {} updated, {} unchanged, {} skipped",
+                summary.created,
+                summary.updated,
+                summary.unchanged,
+                summary.skipped,
+            );
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
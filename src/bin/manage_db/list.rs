@@ -3,7 +3,7 @@ use clap::ArgMatches;
 use diesel::prelude::*;
 
 use luten;
-use luten::db::schema::{passwords, sessions, users};
+use luten::db::schema::{outbox, passwords, sessions, users};
 use luten::db::Db;
 use luten::errors::*;
 
@@ -34,6 +34,7 @@ pub fn list(util: &Global, matches: &ArgMatches, db: &Db) -> Result<()> {
         "users" => do_list!(users::table, luten::user::User),
         "sessions" => do_list!(sessions::table, luten::login::Session),
         "passwords" => do_list!(passwords::table, luten::login::password::Password),
+        "outbox" => do_list!(outbox::table, luten::notify::OutboxMail),
         _ => unreachable!(),
     }
 
@@ -27,10 +27,13 @@ use luten::db::Db;
 #[macro_use]
 mod util;
 
+mod archive;
 mod create;
 mod db_util;
 mod fix;
+mod import;
 mod list;
+mod reset;
 
 
 fn main() {
@@ -59,6 +62,7 @@ fn main() {
                     SubCommand::with_name("users"),
                     SubCommand::with_name("sessions"),
                     SubCommand::with_name("passwords"),
+                    SubCommand::with_name("outbox"),
                 ]),
             SubCommand::with_name("create")
                 .about("Create new entities in the database")
@@ -66,6 +70,11 @@ fn main() {
                 .subcommands(vec![
                     SubCommand::with_name("user"),
                     SubCommand::with_name("password"),
+                    SubCommand::with_name("testat")
+                        .about(
+                            "Assigns a student to a tutor, timeslot and (optionally) room. A \
+                             previous testat of the student is replaced."
+                        ),
                 ]),
             SubCommand::with_name("fix")
                 .about("Fixes several inconsistencies in the database")
@@ -77,6 +86,56 @@ fn main() {
                              not the case, this command will add default preferences."
                         ),
                 ]),
+            SubCommand::with_name("import")
+                .about("Import entities from files")
+                .setting(AppSettings::SubcommandRequired)
+                .subcommands(vec![
+                    SubCommand::with_name("users")
+                        .about(
+                            "Imports users from a CSV file (e.g. an enrollment export). New \
+                             users are created, names of existing users are updated. A preview \
+                             is shown before anything is changed."
+                        )
+                        .args(&[
+                            Arg::with_name("file")
+                                .help("The CSV file to import")
+                                .required(true),
+                            Arg::with_name("role")
+                                .long("role")
+                                .help("The role of newly created users")
+                                .takes_value(true)
+                                .default_value("student"),
+                            Arg::with_name("yes")
+                                .short("y")
+                                .long("yes")
+                                .help("Applies the import without asking"),
+                        ]),
+                ]),
+            SubCommand::with_name("archive")
+                .about(
+                    "Archives all data of a course at the end of the semester and resets \
+                     ratings, preferences and testats. The course goes back to preparation; \
+                     accounts, roles, timeslots and rooms are kept."
+                )
+                .args(&[
+                    Arg::with_name("course")
+                        .help("The slug of the course")
+                        .required(true),
+                    Arg::with_name("yes")
+                        .short("y")
+                        .long("yes")
+                        .help("Archives without asking"),
+                ]),
+            SubCommand::with_name("reset")
+                .about("Resets security settings of users")
+                .setting(AppSettings::SubcommandRequired)
+                .subcommands(vec![
+                    SubCommand::with_name("two_factor")
+                        .about(
+                            "Removes the second factor and the recovery codes of a user, e.g. if \
+                             they lost both their device and their recovery codes."
+                        ),
+                ]),
         ])
         .get_matches();
 
@@ -95,6 +154,9 @@ fn main() {
         "list" => list::list(&util, &sub_matches, &db),
         "create" => create::create(&util, &sub_matches, &db),
         "fix" => fix::fix(&util, &sub_matches, &db),
+        "import" => import::import(&util, &sub_matches, &db),
+        "archive" => archive::archive(&util, &sub_matches, &db),
+        "reset" => reset::reset(&util, &sub_matches, &db),
         _ => unreachable!(),
     };
 
@@ -0,0 +1,30 @@
+use clap::ArgMatches;
+
+use luten::db::Db;
+use luten::errors::*;
+use luten::login::totp::TotpSecret;
+
+use db_util::find_user;
+use util::Global;
+
+
+/// Reset security settings of users.
+pub fn reset(util: &Global, matches: &ArgMatches, db: &Db) -> Result<()> {
+    match matches.subcommand_name().unwrap() {
+        "two_factor" => {
+            println!("### Choose the user whose two-factor authentification should be reset!");
+            let user = find_user(db)?;
+            println!("");
+
+            util.debug_output(&user);
+            if TotpSecret::remove_for(&user, db)? {
+                println!("+-- Removed second factor and recovery codes. The user can set up a new one.");
+            } else {
+                println!("+-- The user has no second factor. Nothing to do.");
+            }
+        }
+        _ => unreachable!(),
+    }
+
+    Ok(())
+}
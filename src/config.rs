@@ -3,8 +3,21 @@
 //! Some of this stuff (like the WEBSITE_TITLE) could be specified in an
 //! external file (and we should think about moving it there). Other constants,
 //! however, need to be defined in Rust source code (like login providers).
+//!
+//! Everything that depends on the environment the app is deployed in (like
+//! the LDAP server or the OpenID Connect provider) is read from a TOML
+//! configuration file. See `FileConfig` and `luten.example.toml`.
+
+use dotenv::dotenv;
+use std::env;
+use std::fs::File;
+use std::io::{ErrorKind as IoErrorKind, Read};
+use toml;
 
+use errors::*;
 use login;
+use notify;
+use user;
 
 
 // ===========================================================================
@@ -19,18 +32,44 @@ pub const WEBSITE_TITLE: &str = "Info-A Testate";
 
 lazy_static! {
     /// A list of usable login providers.
-    pub static ref LOGIN_PROVIDERS: Vec<login::ProviderEntry> = vec![
-        login::ProviderEntry {
-            id: "internal_password",
-            dev_only: true,
-            imp: Box::new(login::password::Provider),
-        },
-        login::ProviderEntry {
-            id: "ldap",
-            dev_only: false,
-            imp: Box::new(login::ldap::Provider),
-        },
-    ];
+    ///
+    /// Providers which need to be configured are only available if their
+    /// section is present in the configuration file.
+    pub static ref LOGIN_PROVIDERS: Vec<login::ProviderEntry> = {
+        let mut providers = vec![
+            login::ProviderEntry {
+                id: "internal_password",
+                dev_only: true,
+                imp: Box::new(login::password::Provider),
+            },
+        ];
+
+        if let Some(ref ldap) = FILE.ldap {
+            providers.push(login::ProviderEntry {
+                id: login::ldap::PROVIDER_ID,
+                dev_only: false,
+                imp: Box::new(login::ldap::Provider::new(ldap.clone())),
+            });
+        }
+
+        if let Some(ref oidc) = FILE.oidc {
+            providers.push(login::ProviderEntry {
+                id: login::oidc::PROVIDER_ID,
+                dev_only: false,
+                imp: Box::new(login::oidc::Provider::new(oidc.clone())),
+            });
+        }
+
+        if let Some(ref saml) = FILE.saml {
+            providers.push(login::ProviderEntry {
+                id: login::saml::PROVIDER_ID,
+                dev_only: false,
+                imp: Box::new(login::saml::Provider::new(saml.clone())),
+            });
+        }
+
+        providers
+    };
 }
 
 /// The minimum number of timeslots a student has to rate as "good" before
@@ -53,6 +92,84 @@ pub const MIN_OK_SLOTS_TUTOR: u64 = 14;
 
 
 
+// ===========================================================================
+// Options from the configuration file
+// ===========================================================================
+
+/// The name of the environment variable which can be used to specify the path
+/// of the configuration file. Like `DATABASE_URL`, it can be set in `.env`.
+pub const CONFIG_FILE_ENV_VAR: &str = "LUTEN_CONFIG";
+
+/// The path of the configuration file if `CONFIG_FILE_ENV_VAR` is not set.
+pub const DEFAULT_CONFIG_FILE: &str = "luten.toml";
+
+lazy_static! {
+    /// The content of the configuration file. It is loaded on first access,
+    /// which happens in `start_server()`. If the file cannot be loaded, we
+    /// panic.
+    pub static ref FILE: FileConfig = FileConfig::load()
+        .unwrap_or_else(|e| panic!("failed to load configuration file: {}", e));
+}
+
+/// All options of the configuration file.
+///
+/// Every section is optional. If the file does not exist at all, the default
+/// (everything missing) is used.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FileConfig {
+    /// Configuration of the LDAP login provider. If missing, the provider is
+    /// disabled.
+    pub ldap: Option<login::ldap::Config>,
+
+    /// Configuration of the OpenID Connect login provider. If missing, the
+    /// provider is disabled.
+    pub oidc: Option<login::oidc::Config>,
+
+    /// Configuration of the SAML login provider. If missing, the provider is
+    /// disabled.
+    pub saml: Option<login::saml::Config>,
+
+    /// Settings for two-factor authentification.
+    #[serde(default)]
+    pub two_factor: login::totp::Config,
+
+    /// Users who may log in with `dev_only` login providers in production.
+    #[serde(default)]
+    pub developers: login::Developers,
+
+    /// Which profile fields users may change themselves.
+    #[serde(default)]
+    pub profile: user::profile::Config,
+
+    /// Email settings. If missing, no notifications are sent.
+    pub email: Option<notify::Config>,
+}
+
+impl FileConfig {
+    /// Loads the configuration file from the path specified in
+    /// `CONFIG_FILE_ENV_VAR` or `DEFAULT_CONFIG_FILE`.
+    pub fn load() -> Result<Self> {
+        dotenv().ok();
+        let path = env::var(CONFIG_FILE_ENV_VAR)
+            .unwrap_or_else(|_| DEFAULT_CONFIG_FILE.into());
+
+        let mut content = String::new();
+        match File::open(&path) {
+            Ok(mut file) => {
+                file.read_to_string(&mut content)
+                    .chain_err(|| format!("failed to read '{}'", path))?;
+            }
+            Err(ref e) if e.kind() == IoErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).chain_err(|| format!("failed to open '{}'", path)),
+        }
+
+        toml::from_str(&content)
+            .chain_err(|| format!("'{}' is not a valid configuration file", path))
+    }
+}
+
+
+
 // ===========================================================================
 // Internal options
 // ===========================================================================
@@ -70,12 +187,86 @@ pub const SESSION_COOKIE_NAME: &str = "session";
 /// since the length is checked there, too.
 pub const SESSION_ID_LEN: usize = 16;
 
+/// Number of bytes of the session id which are shown to the user to identify
+/// a session (e.g. in order to revoke it). The full session id is never shown
+/// as it is as secret as a password.
+pub const SESSION_PUBLIC_ID_LEN: usize = 4;
+
+/// The "last use" timestamp of a session is only updated if it is older than
+/// this many seconds. This avoids writing to the database on every request.
+pub const SESSION_LAST_USE_UPDATE_INTERVAL: i64 = 5 * 60;
+
+/// After the first login step, the user has this many seconds to enter their
+/// TOTP code before they have to start over.
+pub const TOTP_PENDING_LOGIN_TIMEOUT: i64 = 5 * 60;
+
+/// After this many wrong TOTP or recovery codes in a row, TOTP codes of the
+/// user are rejected for `TOTP_LOCK_DURATION` seconds. Recovery codes are
+/// still accepted.
+pub const TOTP_MAX_FAILED_ATTEMPTS: i32 = 5;
+
+/// Number of seconds TOTP codes are rejected after too many wrong codes. Each
+/// further wrong code doubles this, up to `TOTP_MAX_LOCK_DURATION` seconds.
+pub const TOTP_LOCK_DURATION: i64 = 60;
+
+pub const TOTP_MAX_LOCK_DURATION: i64 = 60 * 60;
+
+/// Number of recovery codes generated at once.
+pub const TOTP_RECOVERY_CODE_COUNT: usize = 10;
+
+/// The bcrypt cost parameter for new password hashes. If this is raised,
+/// existing hashes are transparently rehashed on the next successful login.
+pub const PASSWORD_BCRYPT_COST: u32 = 10;
+
+/// The minimum length of new internal passwords.
+pub const PASSWORD_MIN_LEN: usize = 10;
+
+/// The minimum number of distinct characters in a new internal password.
+pub const PASSWORD_MIN_DISTINCT_CHARS: usize = 5;
+
+/// Length of a password reset token in bytes.
+pub const PASSWORD_RESET_TOKEN_LEN: usize = 16;
+
+/// How long (in hours) a password reset link can be used.
+pub const PASSWORD_RESET_TOKEN_VALIDITY: i64 = 24;
+
+/// How often sending an email is attempted before giving up.
+pub const OUTBOX_MAX_ATTEMPTS: i32 = 5;
+
+/// The delay (in seconds) before sending a failed email is retried. The
+/// delay doubles with every failed attempt.
+pub const OUTBOX_RETRY_DELAY: i64 = 5 * 60;
+
+/// The outbox is checked for emails to send every this many seconds.
+pub const OUTBOX_INTERVAL: u64 = 60;
+
+/// The maximum number of emails sent in one go.
+pub const OUTBOX_BATCH_SIZE: i64 = 50;
+
+/// Length of the secret token in calendar feed links in bytes.
+pub const CALENDAR_TOKEN_LEN: usize = 16;
+
+/// Number of users shown per page in the user list of the admin panel.
+pub const ADMIN_USERS_PER_PAGE: usize = 50;
+
+/// Number of impersonations shown in the admin panel.
+pub const IMPERSONATION_LOG_LEN: i64 = 50;
+
 /// When a non-logged-in user requests a route that cannot be accessed when not
 /// logged in, they are redirected to `/login`. After a successful login, the
 /// user will be redirected to the route they initially requested. To store
 /// this route, we use a cookie. The name of that cookie is defined here.
 pub const INITIAL_REQ_COOKIE_NAME: &str = "initial_request_path";
 
-/// The length of one timeslot in minutes. For now, 60 needs to be divisible
-/// by this value!
-pub const TIMESLOT_LEN: u16 = 30;
+/// The name of the cookie storing the slug of the current course.
+pub const COURSE_COOKIE_NAME: &str = "course";
+
+/// The maximum length of the slug of a course.
+pub const MAX_COURSE_SLUG_LEN: usize = 32;
+
+/// The length of new timeslots in minutes, if nothing else is specified.
+/// Every timeslot has its own length.
+pub const DEFAULT_TIMESLOT_LEN: u16 = 30;
+
+/// The maximum length of one timeslot in minutes.
+pub const MAX_TIMESLOT_LEN: u16 = 240;
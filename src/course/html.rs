@@ -0,0 +1,47 @@
+use maud::{html, Markup};
+
+use dict::{self, Locale};
+use user::Role;
+use super::Course;
+
+
+pub fn courses(locale: Locale, courses: &[(Course, Role)], current: Option<&Course>) -> Markup {
+    let dict = dict::new(locale).course;
+
+    html! {
+        h1 (dict.courses_title())
+
+        @if courses.is_empty() {
+            p { i (dict.no_course()) }
+        } @else {
+            p (dict.courses_explanation())
+            table class="c-table" {
+                thead class="c-table__head" {
+                    tr class="c-table__row c-table__row--heading" {
+                        th class="c-table__cell" (dict.course_name())
+                        th class="c-table__cell" (dict.course_role())
+                        th class="c-table__cell" {}
+                    }
+                }
+                tbody class="c-table__body" {
+                    @for &(ref course, role) in courses {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" (course.name)
+                            td class="c-table__cell" (role.as_str())
+                            td class="c-table__cell" {
+                                @if Some(course) == current {
+                                    span class="c-badge c-badge--info" (dict.current_course())
+                                } @else {
+                                    a
+                                        class="c-button c-button--brand u-xsmall"
+                                        href={"/courses/" (course.slug)}
+                                        (dict.select_course())
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
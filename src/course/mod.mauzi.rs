@@ -0,0 +1,38 @@
+unit courses_title {
+    De => "Kurse",
+    En => "Courses",
+}
+unit courses_explanation {
+    De => "Alle Seiten zeigen die Daten des aktuellen Kurses. Hier kannst du zu einem \
+           anderen Kurs wechseln.",
+    En => "All pages show the data of the current course. Here, you can switch to \
+           another course.",
+}
+unit no_course {
+    De => "Du bist noch in keinem Kurs eingetragen. Bitte wende dich an deinen Tutor.",
+    En => "You are not a member of any course yet. Please contact your tutor.",
+}
+unit course_name {
+    De => "Kurs",
+    En => "Course",
+}
+unit course_role {
+    De => "Rolle",
+    En => "Role",
+}
+unit current_course {
+    De => "Aktueller Kurs",
+    En => "Current course",
+}
+unit select_course {
+    De => "Wechseln",
+    En => "Switch",
+}
+unit flash_err_not_a_member(name: &str) {
+    De => "Du bist nicht im Kurs „{name}“ eingetragen.",
+    En => "You are not a member of the course “{name}”.",
+}
+unit flash_success_selected(name: &str) {
+    De => "Du siehst jetzt den Kurs „{name}“.",
+    En => "You are now viewing the course “{name}”.",
+}
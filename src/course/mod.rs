@@ -0,0 +1,231 @@
+//! Courses. **Has routes.**
+//!
+//! Luten can manage several courses at once, e.g. different lectures or the
+//! same lecture in different semesters. Timeslots, preferences, ratings,
+//! testats and the app state belong to one course. Students and tutors are
+//! members of courses and may have a different role in each of them (e.g. a
+//! tutor in one course and a student in another); admins are admins in every
+//! course.
+//!
+//! Pages show the data of the *current course*. It is selected by visiting
+//! `/courses/<slug>` (which can be shared as link) and remembered in a
+//! cookie. Without a cookie, the newest course of the user is used. Since
+//! Rocket can't mount routes below a dynamic path, the course is not part of
+//! every URL.
+//!
+//! Routes:
+//!
+//! - GET `/courses`: lists all courses of the user
+//! - GET `/courses/<slug>`: makes the course the current course
+
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use rocket::http::{Cookie, Cookies};
+
+use config;
+use db::Db;
+use db::schema::{course_members, courses, current_app_state, users};
+use errors::*;
+use state::AppState;
+use user::{Role, User};
+
+mod html;
+pub mod routes;
+
+
+/// A course, as stored in the database.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable)]
+#[table_name = "courses"]
+pub struct Course {
+    id: i16,
+
+    /// Unique name used in URLs, see `is_valid_slug()`.
+    pub slug: String,
+
+    /// The name shown to users.
+    pub name: String,
+
+    /// Whether users logging in for the first time become members of this
+    /// course (with their global role).
+    pub auto_enroll: bool,
+
+    pub created: DateTime<Utc>,
+}
+
+impl Course {
+    /// Loads all courses, the newest first.
+    pub fn load_all(db: &Db) -> Result<Vec<Self>> {
+        courses::table
+            .order(courses::id.desc())
+            .load(&*db.conn()?)
+            .chain_err(|| "failed to load courses")
+    }
+
+    pub fn load_by_id(id: i16, db: &Db) -> Result<Option<Self>> {
+        courses::table
+            .find(id)
+            .first(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to load course")
+    }
+
+    pub fn load_by_slug(slug: &str, db: &Db) -> Result<Option<Self>> {
+        courses::table
+            .filter(courses::slug.eq(slug))
+            .first(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to load course")
+    }
+
+    /// Loads all courses the given user is a member of together with their
+    /// role, the newest first. Admins get all courses.
+    pub fn load_all_of_user(user: &User, db: &Db) -> Result<Vec<(Self, Role)>> {
+        if user.is_admin() {
+            return Ok(Self::load_all(db)?.into_iter().map(|c| (c, Role::Admin)).collect());
+        }
+
+        let conn = &*db.conn()?;
+        let memberships = course_members::table
+            .filter(course_members::user_id.eq(user.id()))
+            .select((course_members::course_id, course_members::role))
+            .load::<(i16, Role)>(conn)?;
+
+        let courses = courses::table
+            .filter(courses::id.eq_any(memberships.iter().map(|&(id, _)| id).collect::<Vec<_>>()))
+            .order(courses::id.desc())
+            .load::<Self>(conn)?;
+
+        Ok(courses.into_iter()
+            .filter_map(|course| {
+                memberships.iter()
+                    .find(|&&(id, _)| id == course.id)
+                    .map(|&(_, role)| (course, role))
+            })
+            .collect())
+    }
+
+    /// Creates a new course. Its app state is "frozen" until an admin sets
+    /// it up.
+    pub fn create(slug: &str, name: &str, auto_enroll: bool, db: &Db) -> Result<Self> {
+        #[derive(Insertable)]
+        #[table_name = "courses"]
+        struct NewCourse<'a> {
+            slug: &'a str,
+            name: &'a str,
+            auto_enroll: bool,
+        }
+
+        #[derive(Insertable)]
+        #[table_name = "current_app_state"]
+        struct NewAppState {
+            course_id: i16,
+            state: AppState,
+        }
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            let course = diesel::insert(&NewCourse { slug, name, auto_enroll })
+                .into(courses::table)
+                .get_result::<Self>(conn)
+                .chain_err(|| "failed to insert course")?;
+
+            diesel::insert(&NewAppState { course_id: course.id, state: AppState::Frozen })
+                .into(current_app_state::table)
+                .execute(conn)
+                .chain_err(|| "failed to insert app state of new course")?;
+
+            Ok(course)
+        })
+    }
+
+    /// Changes whether new users become members of this course.
+    pub fn set_auto_enroll(&mut self, auto_enroll: bool, db: &Db) -> Result<()> {
+        diesel::update(courses::table.find(self.id))
+            .set(courses::auto_enroll.eq(auto_enroll))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to update course")?;
+        self.auto_enroll = auto_enroll;
+
+        Ok(())
+    }
+
+    /// Loads all members with the given role (student or tutor), ordered by
+    /// username.
+    pub fn members(&self, role: Role, db: &Db) -> Result<Vec<User>> {
+        let conn = &*db.conn()?;
+        let ids = course_members::table
+            .filter(course_members::course_id.eq(self.id))
+            .filter(course_members::role.eq(role))
+            .select(course_members::user_id)
+            .load::<i64>(conn)?;
+
+        users::table
+            .filter(users::id.eq_any(ids))
+            .order(users::username)
+            .load(conn)
+            .chain_err(|| "failed to load members of course")
+    }
+
+    /// Counts the members with the given role.
+    pub fn count_members(&self, role: Role, db: &Db) -> Result<u64> {
+        course_members::table
+            .filter(course_members::course_id.eq(self.id))
+            .filter(course_members::role.eq(role))
+            .count()
+            .get_result::<i64>(&*db.conn()?)
+            .map(|count| count as u64)
+            .chain_err(|| "failed to count members of course")
+    }
+
+    pub fn id(&self) -> i16 {
+        self.id
+    }
+}
+
+/// Loads all courses new users become members of.
+pub fn load_auto_enroll_on(conn: &PgConnection) -> Result<Vec<Course>> {
+    courses::table
+        .filter(courses::auto_enroll.eq(true))
+        .load(conn)
+        .chain_err(|| "failed to load courses with auto enrollment")
+}
+
+/// Returns whether the given string can be used as slug of a course: it must
+/// not be empty and may only consist of lowercase ASCII letters, digits and
+/// `-`.
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= config::MAX_COURSE_SLUG_LEN
+        && slug.chars().all(|c| match c {
+            'a'...'z' | '0'...'9' | '-' => true,
+            _ => false,
+        })
+}
+
+/// Determines the current course of the given user and their role in it: the
+/// course from the cookie, if the user may see it, or else their newest
+/// course. Returns `None` if the user isn't a member of any course.
+pub fn current_of(user: &User, cookies: &Cookies, db: &Db) -> Result<Option<(Course, Role)>> {
+    let mut courses = Course::load_all_of_user(user, db)?;
+
+    let selected = cookies.get(config::COURSE_COOKIE_NAME)
+        .and_then(|cookie| courses.iter().position(|&(ref c, _)| c.slug == cookie.value()));
+
+    match selected {
+        Some(pos) => Ok(Some(courses.swap_remove(pos))),
+        None if courses.is_empty() => Ok(None),
+        None => Ok(Some(courses.remove(0))),
+    }
+}
+
+/// Makes the given course the current course.
+pub fn select(course: &Course, cookies: &mut Cookies) {
+    // The cookie is set by `/courses/<slug>`, so the path has to be set
+    // explicitly.
+    let cookie = Cookie::build(config::COURSE_COOKIE_NAME, course.slug.clone())
+        .path("/")
+        .finish();
+    cookies.add(cookie);
+}
@@ -0,0 +1,53 @@
+use rocket::State;
+use rocket::http::Cookies;
+use rocket::response::{Flash, Redirect};
+
+use db::Db;
+use dict::{self, Locale};
+use errors::*;
+use super::{html, Course};
+use template::Page;
+use user::AuthUser;
+
+
+/// Lists all courses of the user to switch between them.
+#[get("/courses")]
+pub fn courses(auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<Page> {
+    let courses = Course::load_all_of_user(&auth_user, &db)?;
+
+    Page::empty()
+        .with_title(dict::new(locale).course.courses_title())
+        .with_active_nav_route("/courses")
+        .with_content(html::courses(locale, &courses, auth_user.course()))
+        .make_ok()
+}
+
+/// Makes the course with the given slug the current course. This link can be
+/// shared, e.g. in emails to students.
+#[get("/courses/<slug>")]
+pub fn select(
+    slug: String,
+    auth_user: AuthUser,
+    mut cookies: Cookies,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Option<Flash<Redirect>>> {
+    let course = match Course::load_by_slug(&slug, &db)? {
+        Some(course) => course,
+        None => return Ok(None),
+    };
+
+    if auth_user.role_in(&course, &db)?.is_none() {
+        return Ok(Some(Flash::error(
+            Redirect::to("/courses"),
+            dict::new(locale).course.flash_err_not_a_member(&course.name),
+        )));
+    }
+
+    super::select(&course, &mut cookies);
+
+    Ok(Some(Flash::success(
+        Redirect::to("/"),
+        dict::new(locale).course.flash_success_selected(&course.name),
+    )))
+}
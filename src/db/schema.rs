@@ -1,14 +1,138 @@
+table! {
+    announcements (id) {
+        id -> Int8,
+        body -> Text,
+        for_students -> Bool,
+        for_tutors -> Bool,
+        for_admins -> Bool,
+        starts -> Nullable<Timestamptz>,
+        ends -> Nullable<Timestamptz>,
+        created -> Timestamptz,
+    }
+}
+
+table! {
+    archives (id) {
+        id -> Int4,
+        course_id -> Nullable<Int2>,
+        course_name -> Text,
+        schema_name -> Text,
+        created -> Timestamptz,
+    }
+}
+joinable!(archives -> courses(course_id));
+
+table! {
+    calendar_tokens (user_id) {
+        user_id -> Int8,
+        token -> Text,
+    }
+}
+joinable!(calendar_tokens -> users(user_id));
+
+table! {
+    use diesel::types::*;
+    use db::types::UserRole;
+
+    course_members (course_id, user_id) {
+        course_id -> Int2,
+        user_id -> Int8,
+        role -> UserRole,
+    }
+}
+joinable!(course_members -> courses(course_id));
+joinable!(course_members -> users(user_id));
+
+table! {
+    courses (id) {
+        id -> Int2,
+        slug -> Text,
+        name -> Text,
+        auto_enroll -> Bool,
+        created -> Timestamptz,
+    }
+}
+
 table! {
     use diesel::types::*;
     use db::types::AppState;
 
-    current_app_state (id) {
-        id -> Bool,
+    current_app_state (course_id) {
         state -> AppState,
         reason -> Nullable<Text>,
         next_state_switch -> Nullable<Timestamptz>,
+        course_id -> Int2,
+    }
+}
+joinable!(current_app_state -> courses(course_id));
+
+table! {
+    dismissed_announcements (user_id, announcement_id) {
+        user_id -> Int8,
+        announcement_id -> Int8,
+    }
+}
+joinable!(dismissed_announcements -> announcements(announcement_id));
+joinable!(dismissed_announcements -> users(user_id));
+
+table! {
+    external_accounts (provider, external_id) {
+        provider -> Text,
+        external_id -> Text,
+        user_id -> Int8,
+    }
+}
+joinable!(external_accounts -> users(user_id));
+
+table! {
+    impersonations (id) {
+        id -> Int8,
+        admin_id -> Nullable<Int8>,
+        user_id -> Nullable<Int8>,
+        started -> Timestamptz,
+        ended -> Nullable<Timestamptz>,
+    }
+}
+
+table! {
+    use diesel::types::*;
+    use db::types::NotificationKind;
+
+    notifications (id) {
+        id -> Int8,
+        user_id -> Int8,
+        kind -> NotificationKind,
+        argument -> Nullable<Text>,
+        created -> Timestamptz,
+        read -> Bool,
     }
 }
+joinable!(notifications -> users(user_id));
+
+table! {
+    outbox (id) {
+        id -> Int8,
+        user_id -> Int8,
+        recipient -> Text,
+        subject -> Text,
+        body -> Text,
+        created -> Timestamptz,
+        attempts -> Int4,
+        next_attempt -> Timestamptz,
+        last_error -> Nullable<Text>,
+        sent -> Nullable<Timestamptz>,
+    }
+}
+joinable!(outbox -> users(user_id));
+
+table! {
+    password_reset_tokens (hash) {
+        hash -> Bytea,
+        user_id -> Int8,
+        expires -> Timestamptz,
+    }
+}
+joinable!(password_reset_tokens -> users(user_id));
 
 table! {
     passwords (user_id) {
@@ -19,23 +143,96 @@ table! {
 joinable!(passwords -> users(user_id));
 
 table! {
-    prep_student_preferences (user_id) {
+    use diesel::types::*;
+    use db::types::Permission;
+
+    permission_grants (user_id, permission) {
+        user_id -> Int8,
+        permission -> Permission,
+    }
+}
+joinable!(permission_grants -> users(user_id));
+
+table! {
+    prep_student_preferences (user_id, course_id) {
         user_id -> Int8,
         partner -> Nullable<Text>,
         prefers_english -> Bool,
+        course_id -> Int2,
     }
 }
 joinable!(prep_student_preferences -> users(user_id));
+joinable!(prep_student_preferences -> courses(course_id));
+
+table! {
+    recovery_codes (user_id, hash) {
+        user_id -> Int8,
+        hash -> Bytea,
+    }
+}
+joinable!(recovery_codes -> users(user_id));
+
+table! {
+    room_availability (room_id, timeslot_id) {
+        room_id -> Int2,
+        timeslot_id -> Int2,
+    }
+}
+joinable!(room_availability -> rooms(room_id));
+joinable!(room_availability -> timeslots(timeslot_id));
+
+table! {
+    rooms (id) {
+        id -> Int2,
+        course_id -> Int2,
+        name -> Text,
+        capacity -> Int2,
+    }
+}
+joinable!(rooms -> courses(course_id));
+
+table! {
+    semester (id) {
+        id -> Bool,
+        name -> Text,
+        lecture_start -> Date,
+        lecture_end -> Date,
+    }
+}
+
+table! {
+    semester_days_off (id) {
+        id -> Int4,
+        first_day -> Date,
+        last_day -> Date,
+        description -> Text,
+    }
+}
 
 table! {
     sessions (id) {
         id -> Bytea,
         user_id -> Int8,
         birth -> Timestamptz,
+        last_use -> Timestamptz,
+        user_agent -> Nullable<Text>,
+        ip_address -> Nullable<Text>,
+        impersonation_id -> Nullable<Int8>,
     }
 }
 joinable!(sessions -> users(user_id));
 
+table! {
+    testats (student_id, course_id) {
+        student_id -> Int8,
+        tutor_id -> Int8,
+        timeslot_id -> Int2,
+        course_id -> Int2,
+        room_id -> Nullable<Int2>,
+    }
+}
+joinable!(testats -> timeslots(timeslot_id));
+
 table! {
     use diesel::types::*;
     use db::types::TimeslotRating;
@@ -57,8 +254,22 @@ table! {
         id -> Int2,
         day -> DayOfWeek,
         time -> Time,
+        course_id -> Int2,
+        duration -> Int2,
+    }
+}
+joinable!(timeslots -> courses(course_id));
+
+table! {
+    totp_secrets (user_id) {
+        user_id -> Int8,
+        secret -> Bytea,
+        last_used_step -> Int8,
+        failed_attempts -> Int4,
+        locked_until -> Nullable<Timestamptz>,
     }
 }
+joinable!(totp_secrets -> users(user_id));
 
 table! {
     use diesel::types::*;
@@ -69,5 +280,9 @@ table! {
         username -> Text,
         name -> Nullable<Text>,
         role -> UserRole,
+        email -> Nullable<Text>,
+        matriculation_number -> Nullable<Text>,
+        study_program -> Nullable<Text>,
+        semester -> Nullable<Int2>,
     }
 }
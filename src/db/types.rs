@@ -12,6 +12,8 @@ use std::error::Error;
 use std::io::Write;
 
 use user::Role;
+use user::permission::Permission as RealPermission;
+use notify::NotificationKind as RealNotificationKind;
 use state::AppState as RealAppState;
 use timeslot::{DayOfWeek as RealDayOfWeek, Rating};
 
@@ -124,3 +126,23 @@ enum_pg_type! ("timeslot_rating", TimeslotRating, Rating; {
     Tolerable => b"tolerable",
     Bad => b"bad",
 });
+
+enum_pg_type! ("permission", Permission, RealPermission; {
+    ViewAdminPanel => b"view_admin_panel",
+    ManageUsers => b"manage_users",
+    ImpersonateUsers => b"impersonate_users",
+    ManagePermissions => b"manage_permissions",
+    ChangeAppState => b"change_app_state",
+    ManageTimeslots => b"manage_timeslots",
+    ViewAllRatings => b"view_all_ratings",
+    GradeStudents => b"grade_students",
+    ManageAnnouncements => b"manage_announcements",
+    ManageCourses => b"manage_courses",
+});
+
+enum_pg_type! ("notification_kind", NotificationKind, RealNotificationKind; {
+    PreparationStarted => b"preparation_started",
+    ScheduleReady => b"schedule_ready",
+    PartnerChosen => b"partner_chosen",
+    PartnerAccepted => b"partner_accepted",
+});
@@ -52,9 +52,14 @@ mauzi! {
     }
 
     mod admin_panel;
+    mod announcement;
+    mod course;
     mod login;
+    mod notify;
     mod prep;
+    mod testat;
     mod timeslot;
+    mod user;
 
 
     // Below are translation units which are somewhat global. Everything that
@@ -124,10 +129,32 @@ mauzi! {
                auftritt, wende dich bitte an einen Übungsleiter!",
     }
 
+    // Impersonation by admins
+    unit impersonation_banner(username: &str) {
+        De => "Du siehst die Seite gerade als '{username}'. Änderungen sind nicht möglich.",
+        En => "You are currently viewing the site as '{username}'. Changes are not possible.",
+    }
+    unit impersonation_end {
+        De => "Zurück zum eigenen Account",
+        En => "Back to your own account",
+    }
+    unit impersonation_read_only_flash {
+        De => "Während du einen anderen Nutzer ansiehst, kannst du nichts ändern.",
+        En => "You can't change anything while viewing the site as another user.",
+    }
+
     // Page navigation
     unit nav_account {
         _ => "Account",
     }
+    unit nav_courses {
+        De => "Kurse",
+        En => "Courses",
+    }
+    unit nav_inbox {
+        De => "Benachrichtigungen",
+        En => "Notifications",
+    }
     unit nav_settings {
         De => "Einstellungen",
         En => "Settings",
@@ -12,9 +12,11 @@ use pwhash;
 use r2d2;
 use rocket;
 use std;
+use toml;
 
 use dict::{self, Locale};
 use login;
+use user;
 
 
 /// We will define our own `Result` type later. In order to still use the one
@@ -33,9 +35,11 @@ error_chain! {
         Db(diesel::result::Error);
         Hashing(pwhash::error::Error);
         Io(std::io::Error);
+        Toml(toml::de::Error);
 
         // Our own errors (basically the recoverable ones)
         LoginError(login::LoginError);
+        ProfileError(user::profile::ProfileError);
     }
 
     errors {
@@ -21,13 +21,18 @@
 //!
 //! - **Helper/internal modules**: these modules don't contain anything that is
 //!   directly shown on the website. Rather, stuff from these modules is used
-//!   in other modules. List: [`config`](config/index.html),
-//!   [`db`](db/index.html), [`errors`](errors/index.html),
-//!   [`state`](state/index.html) and [`template`](template/index.html).
+//!   in other modules. List: [`archive`](archive/index.html),
+//!   [`config`](config/index.html), [`db`](db/index.html),
+//!   [`errors`](errors/index.html), [`room`](room/index.html),
+//!   [`semester`](semester/index.html), [`state`](state/index.html) and
+//!   [`template`](template/index.html).
 //! - **Route modules**: these modules are directly responsible for what is
 //!   visible on the website. They contain the route-handlers. List:
-//!   [`admin_panel`](admin_panel/index.html), [`login`](login/index.html),
-//!   [`prep`](prep/index.html), [`special`](special/index.html) and
+//!   [`admin_panel`](admin_panel/index.html),
+//!   [`announcement`](announcement/index.html), [`course`](course/index.html),
+//!   [`login`](login/index.html), [`notify`](notify/index.html),
+//!   [`prep`](prep/index.html),
+//!   [`special`](special/index.html), [`testat`](testat/index.html) and
 //!   [`user`](user/index.html).
 //! - **Dictionary modules**: the root dictionary module is
 //!   [`dict`](dict/index.html). Please see its documentation for more
@@ -48,40 +53,60 @@
 #![allow(unused_doc_comment)]
 
 extern crate accept_language;
+extern crate base64;
 extern crate chrono;
 #[macro_use] extern crate diesel;
 #[macro_use] extern crate diesel_codegen;
 extern crate dotenv;
+extern crate elementtree;
 #[macro_use] extern crate error_chain;
+extern crate flate2;
 extern crate hex;
+extern crate hyper;
+extern crate hyper_native_tls;
 #[macro_use] extern crate lazy_static;
 extern crate ldap3;
 extern crate maud;
 extern crate mauzi;
+extern crate native_tls;
 extern crate option_filter;
 extern crate palette;
+extern crate pulldown_cmark;
 extern crate pwhash;
+extern crate qrcode;
 #[macro_use] extern crate quick_error;
 extern crate r2d2;
 extern crate r2d2_diesel;
 extern crate rand;
+extern crate ring;
 extern crate rocket;
 extern crate rocket_contrib;
 extern crate serde;
 #[macro_use] extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+extern crate untrusted;
+extern crate url;
 
 
 pub mod api;
 pub mod admin_panel;
+pub mod announcement;
+pub mod archive;
 pub mod config;
+pub mod course;
 pub mod db;
 pub mod dict;
 pub mod errors;
 pub mod login;
+pub mod notify;
 pub mod prep;
+pub mod room;
+pub mod semester;
 pub mod special;
 pub mod state;
 pub mod template;
+pub mod testat;
 pub mod timeslot;
 pub mod user;
 
@@ -95,6 +120,12 @@ pub fn start_server() {
     use db::Db;
     use rocket::fairing::AdHoc;
 
+    // Load the configuration file right away to notice errors early.
+    lazy_static::initialize(&config::FILE);
+
+    // Send queued emails in the background.
+    notify::start_worker();
+
     rocket::ignite()
         .manage(Db::open_connection())
         .attach(AdHoc::on_attach(|rocket| {
@@ -105,26 +136,91 @@ pub fn start_server() {
         }))
         .mount("/", routes![
             admin_panel::routes::index,
+            admin_panel::routes::users_search,
+            admin_panel::routes::users,
+            admin_panel::routes::user_details,
+            admin_panel::routes::users_import,
+            admin_panel::routes::users_import_preview,
+            admin_panel::routes::users_import_apply,
+            admin_panel::routes::create_user,
+            admin_panel::routes::edit_user,
+            admin_panel::routes::delete_user,
+            admin_panel::routes::change_course_role,
+            admin_panel::routes::kill_sessions,
+            admin_panel::routes::password_reset,
+            admin_panel::routes::impersonations,
+            admin_panel::routes::impersonate,
+            admin_panel::routes::permissions,
+            admin_panel::routes::change_permission,
             admin_panel::routes::state,
             admin_panel::routes::change_state,
             admin_panel::routes::timeslots,
             admin_panel::routes::add_timeslot,
             admin_panel::routes::delete_timeslot,
+            admin_panel::routes::rooms,
+            admin_panel::routes::create_room,
+            admin_panel::routes::update_room,
+            admin_panel::routes::delete_room,
+            admin_panel::routes::semester,
+            admin_panel::routes::change_semester,
+            admin_panel::routes::add_day_off,
+            admin_panel::routes::delete_day_off,
+            admin_panel::routes::announcements,
+            admin_panel::routes::create_announcement,
+            admin_panel::routes::delete_announcement,
+            admin_panel::routes::courses,
+            admin_panel::routes::create_course,
+            admin_panel::routes::change_auto_enroll,
+            admin_panel::routes::archive_course,
+
+            announcement::routes::dismiss,
+
+            course::routes::courses,
+            course::routes::select,
 
             login::routes::login_form,
             login::routes::validate_data,
+            login::routes::start_redirect_login,
+            login::routes::redirect_callback,
+            login::routes::redirect_callback_post,
+            login::routes::saml_metadata,
+            login::routes::two_factor_form,
+            login::routes::two_factor_validate,
+            login::routes::password_reset_form,
+            login::routes::password_reset,
+            login::routes::end_impersonation,
             login::routes::logout,
 
-            prep::routes::overview,
+            notify::routes::inbox,
+            notify::routes::dismiss,
+            notify::routes::dismiss_all,
+
+            prep::routes::overview_student,
+            prep::routes::overview_staff,
             prep::routes::set_general_settings,
-            prep::routes::timeslots,
-            prep::routes::update_timeslots,
+            prep::routes::timeslots_student,
+            prep::routes::timeslots_tutor,
+            prep::routes::timeslots_admin,
+            prep::routes::update_timeslots_student,
+            prep::routes::update_timeslots_tutor,
 
             special::routes::static_files,
             special::routes::scss_files,
             special::routes::index,
 
+            testat::routes::overview,
+            testat::routes::calendar,
+            testat::routes::create_calendar_token,
+
             user::routes::settings,
+            user::routes::revoke_session,
+            user::routes::revoke_other_sessions,
+            user::routes::change_password,
+            user::routes::change_profile,
+            user::routes::two_factor_setup,
+            user::routes::two_factor_confirm_setup,
+            user::routes::two_factor_recovery_codes,
+            user::routes::two_factor_disable,
         ])
         .mount("/api", routes![
             api::routes::user::by_username,
@@ -1,10 +1,21 @@
 use maud::{html, Markup};
+use qrcode::QrCode;
 
+use config;
 use dict::{self, Locale};
+use errors::*;
 use login::ProviderEntry;
+use login::totp;
+use user::User;
 
 pub fn login_page(providers: &[&ProviderEntry], locale: Locale) -> Markup {
     let dict = dict::new(locale).login;
+
+    // Redirect providers don't use the login form, but are shown as buttons
+    // below it.
+    let (redirect_providers, form_providers): (Vec<_>, Vec<_>) = providers.iter()
+        .partition(|prov| prov.imp.as_redirect().is_some());
+
     html! {
         div class="o-grid o-grid--small-full u-letter-box--small" {
             // Empty grid cell to have a blank buffer on the side
@@ -17,58 +28,82 @@ pub fn login_page(providers: &[&ProviderEntry], locale: Locale) -> Markup {
             {
                 div class="c-card" {
                     div class="c-card__item c-card__item--brand c-card__item--divider" (dict.box_title())
-                    div class="c-card__item" {
-                        form method="post" action="/login" class="u-pillar-box--small" {
-                            // Field: "username"
-                            div class="u-letter-box--small login-field-container u-large" {
-                                div class="o-field o-field--icon-left" {
-                                    i class="fa fa-fw fa-user c-icon" {}
-                                    input
-                                        type="text"
-                                        class="c-field"
-                                        name="id"
-                                        placeholder=(dict.username_placeholder());
-                                    // TODO: this hint is not really correct. The hint depends
-                                    // on the chosen login-provider. So we have to adjust the hints
-                                    // dynamically. We should. In the future, probably.
-                                    div class="c-hint" (dict.username_hint())
+                    @if !form_providers.is_empty() {
+                        div class="c-card__item" {
+                            form method="post" action="/login" class="u-pillar-box--small" {
+                                // Field: "username"
+                                div class="u-letter-box--small login-field-container u-large" {
+                                    div class="o-field o-field--icon-left" {
+                                        i class="fa fa-fw fa-user c-icon" {}
+                                        input
+                                            type="text"
+                                            class="c-field"
+                                            name="id"
+                                            placeholder=(dict.username_placeholder());
+                                        // TODO: this hint is not really correct. The hint depends
+                                        // on the chosen login-provider. So we have to adjust the hints
+                                        // dynamically. We should. In the future, probably.
+                                        div class="c-hint" (dict.username_hint())
+                                    }
                                 }
-                            }
 
-                            // Field: "password"
-                            div class="u-letter-box--small login-field-container u-large" {
-                                div class="o-field o-field--icon-left" {
-                                    i class="fa fa-fw fa-key c-icon" {}
-                                    input
-                                        type="password"
-                                        class="c-field"
-                                        name="secret"
-                                        placeholder=(dict.password_placeholder());
-                                    div class="c-hint" (dict.password_hint())
+                                // Field: "password"
+                                div class="u-letter-box--small login-field-container u-large" {
+                                    div class="o-field o-field--icon-left" {
+                                        i class="fa fa-fw fa-key c-icon" {}
+                                        input
+                                            type="password"
+                                            class="c-field"
+                                            name="secret"
+                                            placeholder=(dict.password_placeholder());
+                                        div class="c-hint" (dict.password_hint())
+                                    }
                                 }
-                            }
 
-                            // Login provider drop-down menu. It is hidden if
-                            // there is only one provider.
-                            div
-                                class="o-form-element"
-                                style=(if providers.len() == 1 { "display:none " } else { "" })
-                            {
-                                label class="c-label" for="login_provider" (dict.login_provider_label())
-                                select class="c-field" name="login_provider" {
-                                    @for provider in providers {
-                                        option value=(provider.id) (provider.imp.name(locale))
+                                // Login provider drop-down menu. It is hidden if
+                                // there is only one provider.
+                                div
+                                    class="o-form-element"
+                                    style=(if form_providers.len() == 1 { "display:none " } else { "" })
+                                {
+                                    label class="c-label" for="login_provider" (dict.login_provider_label())
+                                    select class="c-field" name="login_provider" {
+                                        @for provider in &form_providers {
+                                            option value=(provider.id) (provider.imp.name(locale))
+                                        }
                                     }
                                 }
+
+                                // Button: "Login"
+                                div class="u-letter-box--small" {
+                                    input
+                                        type="submit"
+                                        value=(dict.login_button_label())
+                                        class="c-button c-button--success"
+                                        style="width: 100%";
+                                }
                             }
+                        }
+                    }
 
-                            // Button: "Login"
-                            div class="u-letter-box--small" {
-                                input
-                                    type="submit"
-                                    value=(dict.login_button_label())
-                                    class="c-button c-button--success"
-                                    style="width: 100%";
+                    // Buttons for the redirect providers
+                    @if !redirect_providers.is_empty() {
+                        div class="c-card__item u-pillar-box--small" {
+                            @if !form_providers.is_empty() {
+                                div class="u-letter-box--small" (dict.redirect_providers_label())
+                            }
+                            @for provider in &redirect_providers {
+                                div class="u-letter-box--small" {
+                                    a
+                                        class="c-button c-button--brand u-large"
+                                        href=(format!("/login/{}", provider.id))
+                                        style="width: 100%"
+                                    {
+                                        i class="fa fa-fw fa-sign-in" {}
+                                        " "
+                                        (provider.imp.name(locale))
+                                    }
+                                }
                             }
                         }
                     }
@@ -95,3 +130,153 @@ pub fn login_page(providers: &[&ProviderEntry], locale: Locale) -> Markup {
         }
     }
 }
+
+/// The second login step: a form asking for a TOTP code or a recovery code.
+pub fn two_factor_page(locale: Locale) -> Markup {
+    let dict = dict::new(locale).login;
+
+    html! {
+        div class="o-container o-container--small u-letter-box--small" {
+            div class="c-card" {
+                div class="c-card__item c-card__item--brand c-card__item--divider" (dict.two_factor_title())
+                div class="c-card__item" {
+                    p (dict.two_factor_explanation())
+                    (code_form("/login/two_factor", locale))
+                }
+            }
+        }
+    }
+}
+
+/// Instructions to set up two-factor authentification with the given secret:
+/// a QR code to scan and a form to confirm the setup with a first code, which
+/// is sent to `action`.
+pub fn two_factor_setup_page(
+    secret: &[u8],
+    user: &User,
+    action: &str,
+    locale: Locale,
+) -> Result<Markup> {
+    let dict = dict::new(locale).login;
+    let qr_code = qr_code(&totp::provisioning_uri(secret, user.username()))?;
+
+    Ok(html! {
+        div class="o-container o-container--small u-letter-box--small" {
+            div class="c-card" {
+                div class="c-card__item c-card__item--brand c-card__item--divider" (dict.two_factor_setup_title())
+                div class="c-card__item" {
+                    p (dict.two_factor_setup_explanation())
+                    div class="u-centered" (qr_code)
+                    p {
+                        (dict.two_factor_setup_manual_secret())
+                        " "
+                        code (totp::encode_secret(secret))
+                    }
+                    (code_form(action, locale))
+                }
+            }
+        }
+    })
+}
+
+/// A form to set a new password with a reset token.
+pub fn password_reset_page(user: &User, token: &str, locale: Locale) -> Markup {
+    let dict = dict::new(locale).login;
+
+    html! {
+        div class="o-container o-container--small u-letter-box--small" {
+            div class="c-card" {
+                div class="c-card__item c-card__item--brand c-card__item--divider" (dict.password_reset_title())
+                div class="c-card__item" {
+                    p (dict.password_reset_explanation(user.username()))
+                    form method="post" action=(format!("/password_reset/{}", token)) {
+                        div class="u-letter-box--small" {
+                            label class="c-label" for="new_password" (dict.new_password_label())
+                            input
+                                id="new_password"
+                                type="password"
+                                class="c-field"
+                                name="new_password"
+                                autocomplete="new-password";
+                            div class="c-hint" (dict.new_password_hint(config::PASSWORD_MIN_LEN as u64))
+                        }
+                        div class="u-letter-box--small" {
+                            label class="c-label" for="new_password_confirm" (dict.new_password_confirm_label())
+                            input
+                                id="new_password_confirm"
+                                type="password"
+                                class="c-field"
+                                name="new_password_confirm"
+                                autocomplete="new-password";
+                        }
+                        div class="u-letter-box--small" {
+                            input
+                                type="submit"
+                                value=(dict.password_reset_submit())
+                                class="c-button c-button--success"
+                                style="width: 100%";
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn code_form(action: &str, locale: Locale) -> Markup {
+    let dict = dict::new(locale).login;
+
+    html! {
+        form method="post" action=(action) {
+            div class="u-letter-box--small u-large" {
+                div class="o-field o-field--icon-left" {
+                    i class="fa fa-fw fa-lock c-icon" {}
+                    input
+                        type="text"
+                        class="c-field"
+                        name="code"
+                        autocomplete="off"
+                        autofocus?[true]
+                        placeholder=(dict.two_factor_code_placeholder());
+                    div class="c-hint" (dict.two_factor_code_hint())
+                }
+            }
+            div class="u-letter-box--small" {
+                input
+                    type="submit"
+                    value=(dict.two_factor_submit())
+                    class="c-button c-button--success"
+                    style="width: 100%";
+            }
+        }
+    }
+}
+
+/// Renders the given data as QR code (SVG).
+fn qr_code(data: &str) -> Result<Markup> {
+    let code = QrCode::new(data.as_bytes())
+        .map_err(|e| format!("failed to create QR code: {:?}", e))?;
+    let width = code.width();
+
+    // One square per dark module. The code needs a "quiet zone" of four
+    // modules around it.
+    let path = code.to_vec()
+        .iter()
+        .enumerate()
+        .filter(|&(_, &dark)| dark)
+        .map(|(i, _)| format!("M{},{}h1v1h-1z", i % width + 4, i / width + 4))
+        .collect::<String>();
+    let size = width + 8;
+
+    Ok(html! {
+        svg
+            xmlns="http://www.w3.org/2000/svg"
+            viewBox=(format!("0 0 {} {}", size, size))
+            width="200"
+            height="200"
+        {
+            rect width=(size) height=(size) fill="#fff" {}
+            path d=(path) fill="#000" {}
+        }
+    })
+}
@@ -0,0 +1,200 @@
+//! Admins viewing the app as another user.
+//!
+//! When a user reports that a page looks wrong, admins can start an
+//! impersonation of that user in the admin panel. This replaces the admin's
+//! session with a session of the impersonated user which references the
+//! impersonation. While impersonating, a banner is shown on every page and
+//! all mutating requests are rejected (see `AuthUser`), so the admin can look,
+//! but not touch. Ending the impersonation gives the admin a fresh session of
+//! their own.
+//!
+//! Every impersonation is recorded in the database and listed in the admin
+//! panel. The records are kept when one of the users involved is deleted.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use rocket::http::{Cookies, Method};
+
+use config;
+use db::Db;
+use db::schema::{impersonations, users};
+use errors::*;
+use super::{start_session, ClientInfo, Session};
+use user::{AuthUser, User};
+
+
+/// Paths which can be requested with any method while impersonating. All
+/// other paths only allow `GET` and `HEAD` requests.
+const ALLOWED_PATHS: &[&str] = &["/impersonation/end", "/logout"];
+
+/// Returns whether a request with the given method and path is allowed in an
+/// impersonation session.
+pub fn is_allowed(method: Method, path: &str) -> bool {
+    method == Method::Get || method == Method::Head || ALLOWED_PATHS.contains(&path)
+}
+
+/// One record of an admin impersonating another user. The user ids are `None`
+/// if the respective user was deleted.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable)]
+pub struct Impersonation {
+    pub id: i64,
+    pub admin_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub started: DateTime<Utc>,
+    pub ended: Option<DateTime<Utc>>,
+}
+
+/// An impersonation record together with both users, as shown in the admin
+/// panel.
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub impersonation: Impersonation,
+    pub admin: Option<User>,
+    pub user: Option<User>,
+}
+
+impl Impersonation {
+    /// Starts impersonating `user` as `admin`: the impersonation is recorded,
+    /// the session of the admin is replaced by an impersonation session.
+    pub fn start(
+        admin: &AuthUser,
+        user: &User,
+        client: &ClientInfo,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<()> {
+        #[derive(Insertable)]
+        #[table_name = "impersonations"]
+        struct NewImpersonation {
+            admin_id: Option<i64>,
+            user_id: Option<i64>,
+        }
+
+        let new = NewImpersonation {
+            admin_id: Some(admin.id()),
+            user_id: Some(user.id()),
+        };
+        let impersonation = diesel::insert(&new)
+            .into(impersonations::table)
+            .get_result::<Impersonation>(&*db.conn()?)
+            .chain_err(|| "failed to insert impersonation")?;
+
+        // Creating the new session overwrites the session cookie.
+        Session::create(user, Some(impersonation.id), client, cookies, db)?;
+        admin.session().remove(db)
+    }
+
+    /// Ends the impersonation the given session belongs to and logs the admin
+    /// back in. Returns the admin or `None` if the session doesn't belong to
+    /// an impersonation.
+    pub fn end(
+        auth_user: AuthUser,
+        client: &ClientInfo,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<Option<AuthUser>> {
+        let admin = match Self::finish(auth_user.session(), db)? {
+            Some(Impersonation { admin_id: Some(id), .. }) => User::load_by_id(id, db)?,
+            Some(_) => None,
+            None => return Ok(None),
+        };
+
+        auth_user.session().remove(db)?;
+        match admin {
+            Some(admin) => start_session(admin, client, cookies, db).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Marks the impersonation of the given session as ended (if it belongs
+    /// to one) and returns it. The session itself is not touched.
+    pub fn finish(session: &Session, db: &Db) -> Result<Option<Self>> {
+        let id = match session.impersonation_id {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        diesel::update(impersonations::table.find(id))
+            .set(impersonations::ended.eq(Utc::now()))
+            .get_result::<Self>(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to end impersonation")
+    }
+
+    /// Loads the most recent impersonations (see
+    /// `config::IMPERSONATION_LOG_LEN`) with the users involved, the most
+    /// recent first.
+    pub fn load_log(db: &Db) -> Result<Vec<LogEntry>> {
+        let conn = &*db.conn()?;
+        let impersonations = impersonations::table
+            .order(impersonations::started.desc())
+            .limit(config::IMPERSONATION_LOG_LEN)
+            .load::<Self>(conn)?;
+
+        let ids: Vec<_> = impersonations.iter()
+            .flat_map(|i| i.admin_id.into_iter().chain(i.user_id))
+            .collect();
+        let users = users::table
+            .filter(users::id.eq_any(ids))
+            .load::<User>(conn)?
+            .into_iter()
+            .map(|u| (u.id(), u))
+            .collect::<HashMap<_, _>>();
+
+        impersonations.into_iter()
+            .map(|impersonation| LogEntry {
+                admin: impersonation.admin_id.and_then(|id| users.get(&id).cloned()),
+                user: impersonation.user_id.and_then(|id| users.get(&id).cloned()),
+                impersonation,
+            })
+            .collect::<Vec<_>>()
+            .make_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use user::Role;
+
+    /// Needs a migrated database in `DATABASE_URL`. Run with
+    /// `cargo test -- --ignored`; nothing is committed.
+    #[test]
+    #[ignore]
+    fn deleting_users_keeps_the_record() {
+        #[derive(Insertable)]
+        #[table_name = "impersonations"]
+        struct NewImpersonation {
+            admin_id: Option<i64>,
+            user_id: Option<i64>,
+        }
+
+        let db = Db::open_connection();
+        let conn = &*db.conn().unwrap();
+        conn.begin_test_transaction().unwrap();
+
+        let admin = User::create_on("impersonating-admin".into(), None, Role::Admin, conn).unwrap();
+        let user = User::create_on("impersonated-user".into(), None, Role::Student, conn).unwrap();
+        let new = NewImpersonation {
+            admin_id: Some(admin.id()),
+            user_id: Some(user.id()),
+        };
+        let id = diesel::insert(&new)
+            .into(impersonations::table)
+            .get_result::<Impersonation>(conn)
+            .unwrap()
+            .id;
+        let load = || impersonations::table.find(id).first::<Impersonation>(conn).unwrap();
+
+        diesel::delete(users::table.find(admin.id())).execute(conn).unwrap();
+        assert_eq!(load().admin_id, None);
+        assert_eq!(load().user_id, Some(user.id()));
+
+        diesel::delete(users::table.find(user.id())).execute(conn).unwrap();
+        assert_eq!(load().admin_id, None);
+        assert_eq!(load().user_id, None);
+    }
+}
@@ -3,30 +3,377 @@
 //! Within Luten, users are usally authenticated via LDAP. This module provides
 //! any logic necessary to communicate with the LDAP server in order to verify
 //! the users credentials.
+//!
+//! The LDAP server and the structure of the directory are configured in the
+//! `[ldap]` section of the configuration file (see `Config`). If that section
+//! is missing, the LDAP provider is not available.
+
+use std::collections::HashMap;
+use std::time::Duration;
 
-use ldap3::{LdapConn, Scope, SearchEntry};
+use ldap3::{LdapConn, LdapConnSettings, Scope, SearchEntry};
+use option_filter::OptionFilterExt;
 
 use db::Db;
 use dict::{self, Locale};
 use errors::*;
-use login::{self, LoginError};
-use user::{User, Role};
+use login::{self, ExternalAccount, ExternalProfile, LinkExisting, LoginError, RoleMapping};
+use user::User;
+
+
+/// The ID of this provider (see `login::ProviderEntry`).
+pub const PROVIDER_ID: &str = "ldap";
+
+/// The placeholder in DN and filter templates which is replaced by the
+/// (properly escaped) username.
+const USERNAME_PLACEHOLDER: &str = "{username}";
+
+/// Escapes a string to be used as attribute value in a DN (RFC 4514, section
+/// 2.4).
+///
+/// Without escaping, a username like `foo,ou=admins` would change the
+/// structure of the DN.
+pub fn escape_dn_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let last = value.chars().count().saturating_sub(1);
+
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            // These always need to be escaped. `=` doesn't strictly need to
+            // be escaped, but it's allowed and some servers are confused by it
+            // otherwise.
+            '"' | '+' | ',' | ';' | '<' | '>' | '\\' | '=' => {
+                out.push('\\');
+                out.push(c);
+            }
+
+            // A leading space or `#` and a trailing space need to be escaped.
+            ' ' if i == 0 || i == last => out.push_str("\\ "),
+            '#' if i == 0 => out.push_str("\\#"),
+
+            // NUL bytes have to be hex-encoded.
+            '\0' => out.push_str("\\00"),
+
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Escapes a string to be used as assertion value in a search filter (RFC
+/// 4515, section 3).
+///
+/// Without escaping, a username like `*` would match all users and a username
+/// like `foo)(uid=*` would change the structure of the filter.
+pub fn escape_filter_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '*' => out.push_str("\\2a"),
+            '(' => out.push_str("\\28"),
+            ')' => out.push_str("\\29"),
+            '\\' => out.push_str("\\5c"),
+            '\0' => out.push_str("\\00"),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
+/// Configuration of the LDAP provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The LDAP server address, e.g. `ldaps://ldap.uni-osnabrueck.de`. Use
+    /// the `ldaps://` scheme for LDAP over TLS.
+    pub url: String,
+
+    /// Whether or not to upgrade an unencrypted `ldap://` connection via
+    /// StartTLS. Don't combine this with `ldaps://`.
+    #[serde(default)]
+    pub starttls: bool,
+
+    /// Timeout in seconds for establishing the connection and for each
+    /// operation.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+
+    /// The base directory in which LDAP should search for users.
+    pub base: String,
+
+    /// How to find the DN of a user in order to verify their password.
+    pub bind: BindMode,
+
+    /// The names of the LDAP attributes we are interested in.
+    #[serde(default)]
+    pub attributes: Attributes,
+
+    /// How to derive the role of a user from their LDAP groups (the values of
+    /// the `groups` attribute). If this is missing, LDAP doesn't influence the
+    /// roles of users: new users are students and can only be promoted
+    /// manually.
+    pub roles: Option<RoleMapping>,
+}
+
+fn default_timeout() -> u64 {
+    5
+}
+
+/// Determines how we bind as a user to verify their password.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum BindMode {
+    /// The DN of a user is obtained by replacing `{username}` in the given
+    /// template, e.g. `uid={username},ou=people,dc=uni-osnabrueck,dc=de`.
+    /// The username is escaped with `escape_dn_value()`.
+    Direct {
+        dn: String,
+    },
+
+    /// We first bind with a service account (`dn` and `password`) and search
+    /// the user with the given `filter` (e.g. `(uid={username})`) below the
+    /// base directory. Afterwards, we bind with the DN found in that search.
+    /// The username is escaped with `escape_filter_value()`.
+    ///
+    /// This is necessary if the DN of users cannot be derived from their
+    /// username.
+    SearchThenBind {
+        dn: String,
+        password: String,
+        filter: String,
+    },
+}
+
+/// Names of the LDAP attributes which store information about a user.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Attributes {
+    /// The user's ID as stored in the directory (default: `uid`). This is used
+    /// as username instead of what the user typed: LDAP compares most
+    /// attributes case-insensitively, so `BOB` can log in as `bob`.
+    pub username: String,
+
+    /// The user's real name (default: `cn`).
+    pub name: String,
+
+    /// The user's email address (default: `mail`).
+    pub email: String,
+
+    /// The groups the user is a member of (default: `memberOf`).
+    pub groups: String,
+
+    /// The user's matriculation number. There is no standard attribute for
+    /// this, so it's only loaded if configured.
+    pub matriculation_number: Option<String>,
+
+    /// The user's study program (only loaded if configured).
+    pub study_program: Option<String>,
+
+    /// The user's semester (only loaded if configured).
+    pub semester: Option<String>,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            username: "uid".into(),
+            name: "cn".into(),
+            email: "mail".into(),
+            groups: "memberOf".into(),
+            matriculation_number: None,
+            study_program: None,
+            semester: None,
+        }
+    }
+}
+
+/// The information about a user we obtained from LDAP.
+#[derive(Debug, Clone, Default)]
+pub struct Entry {
+    pub username: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub matriculation_number: Option<String>,
+    pub study_program: Option<String>,
+    pub semester: Option<i16>,
+    pub groups: Vec<String>,
+}
+
+/// An entry found by `Directory::search()`.
+#[derive(Debug, Clone)]
+struct FoundEntry {
+    dn: String,
+    attrs: HashMap<String, Vec<String>>,
+}
+
+/// The operations on an LDAP directory we need. This is implemented for a
+/// connection to an LDAP server and, in tests, for an in-memory directory.
+trait Directory {
+    /// Binds with the given DN and password. Returns `false` if the server
+    /// rejects the credentials.
+    fn bind(&self, dn: &str, password: &str) -> Result<bool>;
 
-// TODO: Use a configuration file
-/// Determines the LDAP server address.
-const LDAP_URL: &'static str = "ldaps://ldap.uni-osnabrueck.de";
+    /// Returns all entries matching the given filter in the given scope
+    /// below `base` with the requested attributes.
+    fn search(&self, base: &str, scope: Scope, filter: &str, attrs: Vec<&str>) -> Result<Vec<FoundEntry>>;
+}
+
+/// A connection to an LDAP server.
+struct Connection {
+    ldap: LdapConn,
+    timeout: Duration,
+}
+
+impl Directory for Connection {
+    fn bind(&self, dn: &str, password: &str) -> Result<bool> {
+        let res = self.ldap.with_timeout(self.timeout).simple_bind(dn, password)?;
+        Ok(res.success().is_ok())
+    }
 
-/// Determines the base directory in which LDAP should search for users.
-const LDAP_BASE: &'static str = "ou=people,dc=uni-osnabrueck,dc=de";
+    fn search(&self, base: &str, scope: Scope, filter: &str, attrs: Vec<&str>) -> Result<Vec<FoundEntry>> {
+        let (rs, _) = self.ldap.with_timeout(self.timeout)
+            .search(base, scope, filter, attrs)?
+            .success()?;
 
-/// Determines the LDAP distiguished name which refers to the user's common name.
-const LDAP_CN: &'static str = "cn";
+        Ok(rs.into_iter()
+            .map(|entry| {
+                let entry = SearchEntry::construct(entry);
+                FoundEntry { dn: entry.dn, attrs: entry.attrs }
+            })
+            .collect())
+    }
+}
 
-/// Determines the LDAP distiguished name which refers to the username.
-const LDAP_UID: &'static str = "uid";
 
 /// Authenticating users via LDAP.
-pub struct Provider;
+pub struct Provider {
+    config: Config,
+}
+
+impl Provider {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.config.timeout)
+    }
+
+    /// Opens a connection to the LDAP server.
+    fn connect(&self) -> Result<Connection> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(self.timeout())
+            .set_starttls(self.config.starttls);
+
+        let ldap = LdapConn::with_settings(settings, &self.config.url)
+            .chain_err(|| format!("failed to connect to LDAP server '{}'", self.config.url))?;
+
+        Ok(Connection { ldap, timeout: self.timeout() })
+    }
+
+    /// Returns the DN of the user with the given username.
+    ///
+    /// Depending on the bind mode, this requires a search as service account.
+    /// If no such user exists, `CredentialsIncorrect` is returned (we don't
+    /// want to leak which usernames exist).
+    fn user_dn(&self, dir: &Directory, username: &str) -> Result<String> {
+        match self.config.bind {
+            BindMode::Direct { ref dn } => {
+                Ok(dn.replace(USERNAME_PLACEHOLDER, &escape_dn_value(username)))
+            }
+            BindMode::SearchThenBind { ref dn, ref password, ref filter } => {
+                if !dir.bind(dn, password)? {
+                    bail!("failed to bind to LDAP with the service account");
+                }
+
+                // We don't need any attributes, only the DN. "1.1" is the
+                // LDAP way to say "no attributes".
+                let filter = filter.replace(USERNAME_PLACEHOLDER, &escape_filter_value(username));
+                let mut rs = dir.search(&self.config.base, Scope::Subtree, &filter, vec!["1.1"])?;
+
+                if rs.len() != 1 {
+                    bail!(LoginError::CredentialsIncorrect);
+                }
+                Ok(rs.remove(0).dn)
+            }
+        }
+    }
+
+    /// Loads the attributes of the entry with the given DN. `username` is what
+    /// the user typed to log in; its lowercase form is only used if the entry
+    /// doesn't have the username attribute.
+    fn load_entry(&self, dir: &Directory, dn: &str, username: &str) -> Result<Entry> {
+        let attrs = &self.config.attributes;
+        let mut requested = vec![
+            attrs.username.as_str(),
+            attrs.name.as_str(),
+            attrs.email.as_str(),
+            attrs.groups.as_str(),
+        ];
+        let optional = [&attrs.matriculation_number, &attrs.study_program, &attrs.semester];
+        requested.extend(optional.iter().filter_map(|a| a.as_ref().map(|a| a.as_str())));
+
+        let mut rs = dir.search(dn, Scope::Base, "(objectClass=*)", requested)?;
+
+        if rs.is_empty() {
+            return Ok(Entry { username: username.to_lowercase(), ..Entry::default() });
+        }
+        let mut values = rs.remove(0).attrs;
+        let groups = values.remove(&attrs.groups).unwrap_or_default();
+
+        // Helper to get the first value of an attribute.
+        let mut first_value = |attr: &str| {
+            values.remove(attr).and_then(|mut v| {
+                if v.is_empty() {
+                    None
+                } else {
+                    Some(v.remove(0))
+                }
+            })
+        };
+
+        // The optional attributes are only loaded if they are configured.
+        let matriculation_number = attrs.matriculation_number.as_ref()
+            .and_then(|attr| first_value(attr));
+        let study_program = attrs.study_program.as_ref()
+            .and_then(|attr| first_value(attr));
+        let semester = attrs.semester.as_ref()
+            .and_then(|attr| first_value(attr))
+            .and_then(|s| s.trim().parse::<i16>().ok())
+            .filter(|&s| s > 0);
+
+        Ok(Entry {
+            username: first_value(&attrs.username).unwrap_or_else(|| username.to_lowercase()),
+            name: first_value(&attrs.name),
+            email: first_value(&attrs.email),
+            matriculation_number,
+            study_program,
+            semester,
+            groups,
+        })
+    }
+
+    /// Checks the credentials of the given user and loads their entry.
+    fn authenticate(&self, dir: &Directory, id: &str, secret: &str) -> Result<Entry> {
+        // A simple bind with an empty password is an "unauthenticated bind"
+        // (RFC 4513, section 5.1.2) which many servers report as successful
+        // without checking anything. So we have to reject empty passwords
+        // ourselves.
+        if id.is_empty() || secret.is_empty() {
+            bail!(LoginError::CredentialsIncorrect);
+        }
+
+        let dn = self.user_dn(dir, id)?;
+        if !dir.bind(&dn, secret)? {
+            bail!(LoginError::CredentialsIncorrect);
+        }
+
+        // Load the real name, profile fields and groups.
+        self.load_entry(dir, &dn, id)
+    }
+}
 
 impl login::Provider for Provider {
     /// Returns a user facing name of this login provider, e.g. `LDAP`.
@@ -38,43 +385,360 @@ impl login::Provider for Provider {
     /// server, verifying the credentials, finding the user in the database,
     /// or - if the user authenticates for the first time - creating a new
     /// database entry.
+    ///
+    /// The name and (if a role mapping is configured) the role of existing
+    /// users are updated with the values from LDAP on each login.
     fn auth(&self, id: &str, secret: &str, db: &Db) -> Result<User> {
-        // Open a connection to the LDAP server
-        let ldap = LdapConn::new(LDAP_URL)?;
-
-        // Authenticate
-        let ldap_user = format!("{}={},{}", LDAP_UID, id, LDAP_BASE);
-        ldap.simple_bind(&ldap_user, secret)?
-            .success()
-            .chain_err(|| ErrorKind::LoginError(LoginError::CredentialsIncorrect))?;
-
-        // Find the user in the database...
-        if let Some(user) = User::load_by_username(id, db)? {
-            Ok(user)
+        // Open a connection to the LDAP server and authenticate
+        let ldap = self.connect()?;
+        let entry = self.authenticate(&ldap, id, secret)?;
+
+        let profile = ExternalProfile {
+            name: entry.name,
+            email: entry.email,
+            matriculation_number: entry.matriculation_number,
+            study_program: entry.study_program,
+            semester: entry.semester,
+            groups: entry.groups,
+        };
+
+        // The username is the user's ID in the directory, spelled as stored
+        // there. Users who logged in via LDAP before external accounts were
+        // stored are linked on their next login, so all existing users can be
+        // linked.
+        let id = &entry.username;
+        let account = ExternalAccount { provider: PROVIDER_ID, id };
+        login::sync_user(account, id, LinkExisting::All, profile, self.config.roles.as_ref(), db)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use toml;
+    use user::Role;
+
+    const BASE: &str = "ou=people,dc=example,dc=org";
+    const SERVICE_DN: &str = "cn=luten,ou=services,dc=example,dc=org";
+    const SERVICE_PASSWORD: &str = "service-secret";
+    const ADMINS: &str = "cn=luten-admins,ou=groups,dc=example,dc=org";
+    const TUTORS: &str = "cn=infoa-tutors,ou=groups,dc=example,dc=org";
+
+    /// A user in `FakeDirectory`.
+    struct FakeEntry {
+        dn: &'static str,
+        password: &'static str,
+        attrs: Vec<(&'static str, Vec<&'static str>)>,
+    }
+
+    /// An LDAP directory in memory. It only understands the filters luten
+    /// uses: equality filters like `(uid=alice)` for searches and
+    /// `(objectClass=*)` for reading an entry.
+    struct FakeDirectory {
+        entries: Vec<FakeEntry>,
+    }
+
+    impl FakeEntry {
+        fn new(
+            dn: &'static str,
+            password: &'static str,
+            uid: &'static str,
+            cn: &'static str,
+            groups: Vec<&'static str>,
+        ) -> Self {
+            FakeEntry {
+                dn,
+                password,
+                attrs: vec![
+                    ("uid", vec![uid]),
+                    ("cn", vec![cn]),
+                    ("mail", vec![]),
+                    ("memberOf", groups),
+                    ("matrikelnummer", vec!["970001"]),
+                ],
+            }
+        }
+    }
+
+    impl FakeDirectory {
+        fn new() -> Self {
+            FakeDirectory {
+                entries: vec![
+                    FakeEntry::new(
+                        "uid=alice,ou=people,dc=example,dc=org",
+                        "secret",
+                        "alice",
+                        "Alice Liddell",
+                        vec![TUTORS],
+                    ),
+                    FakeEntry::new(
+                        "uid=bob,ou=people,dc=example,dc=org",
+                        "hunter2",
+                        "bob",
+                        "Bob",
+                        vec![ADMINS, TUTORS],
+                    ),
+                    FakeEntry::new(
+                        "uid=carol,ou=people,dc=example,dc=org",
+                        "pa$$word",
+                        "carol",
+                        "Carol",
+                        vec![],
+                    ),
+                ],
+            }
+        }
+    }
+
+    impl Directory for FakeDirectory {
+        fn bind(&self, dn: &str, password: &str) -> Result<bool> {
+            // Like many real servers, we accept unauthenticated binds.
+            if password.is_empty() {
+                return Ok(true);
+            }
+
+            Ok((dn == SERVICE_DN && password == SERVICE_PASSWORD)
+                || self.entries.iter().any(|e| e.dn == dn && e.password == password))
+        }
+
+        fn search(&self, base: &str, scope: Scope, filter: &str, attrs: Vec<&str>) -> Result<Vec<FoundEntry>> {
+            let found = self.entries.iter()
+                .filter(|entry| match scope {
+                    Scope::Base => entry.dn == base && filter == "(objectClass=*)",
+                    _ => entry.dn.ends_with(&format!(",{}", base)) && matches(entry, filter),
+                })
+                .map(|entry| FoundEntry {
+                    dn: entry.dn.into(),
+                    attrs: entry.attrs.iter()
+                        .filter(|&&(name, _)| attrs.contains(&name))
+                        .map(|&(name, ref values)| {
+                            (name.into(), values.iter().map(|&v| v.into()).collect())
+                        })
+                        .collect(),
+                })
+                .collect();
+
+            Ok(found)
+        }
+    }
+
+    /// Returns whether the given entry matches the given equality filter.
+    fn matches(entry: &FakeEntry, filter: &str) -> bool {
+        // Unescaped special characters mean that the filter was injected.
+        let inner = filter.trim_left_matches('(').trim_right_matches(')');
+        let eq = inner.find('=');
+        let valid = filter.starts_with('(') && filter.ends_with(')')
+            && eq.is_some()
+            && !inner.contains(|c| c == '*' || c == '(' || c == ')');
+        if !valid {
+            panic!("unsupported filter '{}'", filter);
+        }
+        let (attr, value) = inner.split_at(eq.unwrap());
+        let value = &value[1..];
+
+        // Decode `\XX` escapes.
+        let mut bytes = Vec::new();
+        let mut rest = value.as_bytes();
+        while !rest.is_empty() {
+            if rest[0] == b'\\' {
+                let hex = ::std::str::from_utf8(&rest[1..3]).unwrap();
+                bytes.push(u8::from_str_radix(hex, 16).unwrap());
+                rest = &rest[3..];
+            } else {
+                bytes.push(rest[0]);
+                rest = &rest[1..];
+            }
+        }
+        let value = String::from_utf8(bytes).unwrap();
+
+        entry.attrs.iter()
+            .filter(|&&(name, _)| name.eq_ignore_ascii_case(attr))
+            .any(|&(_, ref values)| values.iter().any(|v| v.eq_ignore_ascii_case(&value)))
+    }
+
+    fn provider(bind: &str) -> Provider {
+        let config = format!(r#"
+            url = "ldap://localhost:3389"
+            base = "{}"
+
+            [bind]
+            {}
+
+            [attributes]
+            matriculation_number = "matrikelnummer"
+
+            [roles]
+            admin_groups = ["{}"]
+        "#, BASE, bind, ADMINS);
+
+        Provider::new(toml::from_str(&config).unwrap())
+    }
+
+    fn direct() -> Provider {
+        provider(r#"
+            mode = "direct"
+            dn = "uid={username},ou=people,dc=example,dc=org"
+        "#)
+    }
+
+    fn search_then_bind(service_password: &str) -> Provider {
+        provider(&format!(r#"
+            mode = "search_then_bind"
+            dn = "{}"
+            password = "{}"
+            filter = "(uid={{username}})"
+        "#, SERVICE_DN, service_password))
+    }
+
+    fn assert_incorrect(res: Result<Entry>) {
+        match res {
+            Err(Error(ErrorKind::LoginError(LoginError::CredentialsIncorrect), _)) => {}
+            other => panic!("credentials were not rejected: {:?}", other),
         }
+    }
+
+    #[test]
+    fn direct_bind() {
+        let entry = direct().authenticate(&FakeDirectory::new(), "alice", "secret").unwrap();
+
+        assert_eq!(entry.username, "alice");
+        assert_eq!(entry.name, Some("Alice Liddell".into()));
+        assert_eq!(entry.email, None);
+        assert_eq!(entry.matriculation_number, Some("970001".into()));
+        assert_eq!(entry.groups, vec![TUTORS]);
+    }
+
+    #[test]
+    fn direct_bind_wrong_credentials() {
+        let dir = FakeDirectory::new();
+        assert_incorrect(direct().authenticate(&dir, "alice", "hunter2"));
+        assert_incorrect(direct().authenticate(&dir, "dave", "secret"));
+    }
+
+    #[test]
+    fn search_then_bind_search() {
+        let dir = FakeDirectory::new();
+        let provider = search_then_bind(SERVICE_PASSWORD);
+        let entry = provider.authenticate(&dir, "BOB", "hunter2").unwrap();
+
+        // The search ignores the case, but the user is `bob` either way.
+        assert_eq!(entry.username, "bob");
+        assert_eq!(provider.authenticate(&dir, "bob", "hunter2").unwrap().username, "bob");
+        assert_eq!(entry.name, Some("Bob".into()));
+        assert_eq!(entry.groups, vec![ADMINS, TUTORS]);
+    }
+
+    #[test]
+    fn search_then_bind_wrong_credentials() {
+        let dir = FakeDirectory::new();
+        let provider = search_then_bind(SERVICE_PASSWORD);
+        assert_incorrect(provider.authenticate(&dir, "bob", "secret"));
+        assert_incorrect(provider.authenticate(&dir, "dave", "secret"));
+    }
 
-        // ... or create a new entry
-        else {
-            // Load the real name
-            let (mut rs, _) = ldap.search(
-                LDAP_BASE,
-                Scope::Subtree,
-                &format!("{}={}", LDAP_UID, id),
-                vec![LDAP_CN]
-            )?.success()?;
-            let name = SearchEntry::construct(rs.remove(0))
-                .attrs
-                .remove(LDAP_CN)
-                .and_then(|mut v| {
-                    if v.is_empty() {
-                        None
-                    } else {
-                        Some(v.remove(0))
-                    }
-                });
-
-            // Create the entry
-            Ok(User::create(id.into(), name, Role::Student, db)?)
+    #[test]
+    fn search_then_bind_wrong_service_password() {
+        // This is a configuration error, not the user's fault.
+        let res = search_then_bind("wrong").authenticate(&FakeDirectory::new(), "bob", "hunter2");
+        match res {
+            Err(Error(ErrorKind::LoginError(_), _)) => panic!("service bind failure treated as login error"),
+            Err(_) => {}
+            Ok(_) => panic!("service bind with wrong password succeeded"),
         }
     }
+
+    #[test]
+    fn escape_dn_special_characters() {
+        assert_eq!(escape_dn_value("alice"), "alice");
+        assert_eq!(escape_dn_value("alice,ou=admins"), "alice\\,ou\\=admins");
+        assert_eq!(escape_dn_value("a+b;c<d>e\"f"), "a\\+b\\;c\\<d\\>e\\\"f");
+        assert_eq!(escape_dn_value("back\\slash"), "back\\\\slash");
+        assert_eq!(escape_dn_value("nul\0byte"), "nul\\00byte");
+
+        // `*` has no meaning in DNs.
+        assert_eq!(escape_dn_value("*"), "*");
+    }
+
+    #[test]
+    fn escape_dn_leading_and_trailing() {
+        assert_eq!(escape_dn_value("#alice"), "\\#alice");
+        assert_eq!(escape_dn_value("ali#ce"), "ali#ce");
+        assert_eq!(escape_dn_value(" alice"), "\\ alice");
+        assert_eq!(escape_dn_value("alice "), "alice\\ ");
+        assert_eq!(escape_dn_value("ali ce"), "ali ce");
+        assert_eq!(escape_dn_value(" "), "\\ ");
+        assert_eq!(escape_dn_value(""), "");
+    }
+
+    #[test]
+    fn escape_filter_special_characters() {
+        assert_eq!(escape_filter_value("alice"), "alice");
+        assert_eq!(escape_filter_value("*"), "\\2a");
+        assert_eq!(escape_filter_value(")(uid=*"), "\\29\\28uid=\\2a");
+        assert_eq!(escape_filter_value("back\\slash"), "back\\5cslash");
+        assert_eq!(escape_filter_value("nul\0byte"), "nul\\00byte");
+        assert_eq!(escape_filter_value(",ou=admins"), ",ou=admins");
+    }
+
+    #[test]
+    fn injected_usernames() {
+        let dir = FakeDirectory::new();
+
+        // Without escaping, this would be the DN of alice.
+        assert_incorrect(direct().authenticate(&dir, "alice,ou=people", "secret"));
+
+        // Without escaping, this would find alice or all users.
+        let provider = search_then_bind(SERVICE_PASSWORD);
+        assert_incorrect(provider.authenticate(&dir, "*", "secret"));
+        assert_incorrect(provider.authenticate(&dir, "*)(uid=alice", "secret"));
+    }
+
+    #[test]
+    fn empty_password() {
+        // The directory accepts unauthenticated binds, so an empty password
+        // would log in anyone.
+        let dir = FakeDirectory::new();
+        assert_incorrect(direct().authenticate(&dir, "alice", ""));
+        assert_incorrect(search_then_bind(SERVICE_PASSWORD).authenticate(&dir, "alice", ""));
+        assert_incorrect(direct().authenticate(&dir, "", "secret"));
+    }
+
+    #[test]
+    fn role_mapping() {
+        let provider = direct();
+        let roles = provider.config.roles.as_ref().unwrap();
+        let dir = FakeDirectory::new();
+        let groups = |uid, password| provider.authenticate(&dir, uid, password).unwrap().groups;
+
+        assert_eq!(roles.role_for(&groups("bob", "hunter2"), Some(Role::Student)), Role::Admin);
+        assert_eq!(roles.role_for(&groups("bob", "hunter2"), None), Role::Admin);
+        assert_eq!(roles.role_for(&groups("carol", "pa$$word"), None), Role::Student);
+
+        // Groups don't make anyone a tutor and other roles are kept: they
+        // depend on the course.
+        assert_eq!(roles.role_for(&groups("alice", "secret"), None), Role::Student);
+        assert_eq!(roles.role_for(&groups("alice", "secret"), Some(Role::Tutor)), Role::Tutor);
+
+        // Group names are compared case insensitively.
+        let upper = vec![ADMINS.to_uppercase()];
+        assert_eq!(roles.role_for(&upper, None), Role::Admin);
+
+        // Admins who were promoted manually are not demoted by default...
+        assert_eq!(roles.role_for(&groups("alice", "secret"), Some(Role::Admin)), Role::Admin);
+
+        // ... unless `demote_admins` is set.
+        let mut demoting = roles.clone();
+        demoting.demote_admins = true;
+        assert_eq!(demoting.role_for(&groups("alice", "secret"), Some(Role::Admin)), Role::Student);
+    }
+
+    #[test]
+    fn tutor_groups_are_rejected() {
+        let config = r#"
+            admin_groups = ["luten-admins"]
+            tutor_groups = ["infoa-tutors"]
+        "#;
+        assert!(toml::from_str::<RoleMapping>(config).is_err());
+    }
 }
@@ -13,12 +13,44 @@ unit err_credentials_incorrect {
 unit err_provider_not_usable {
     De => "Der angegebene Nutzer kann nicht mit der gewählten Methode authentifiziert werden.",
 }
+unit err_external_login_failed {
+    De => "Die Anmeldung beim externen Anbieter ist fehlgeschlagen oder wurde abgebrochen.",
+    En => "The login at the external provider failed or was cancelled.",
+}
+unit two_factor_needed {
+    De => "Bitte bestätige deine Anmeldung mit deinem zweiten Faktor.",
+    En => "Please confirm your login with your second factor.",
+}
+unit flash_err_two_factor_expired {
+    De => "Die Anmeldung hat zu lange gedauert. Bitte melde dich erneut an.",
+    En => "The login took too long. Please log in again.",
+}
+unit flash_err_two_factor_incorrect {
+    De => "Der eingegebene Code ist falsch.",
+    En => "The given code is incorrect.",
+}
+unit flash_err_two_factor_locked {
+    De => "Es wurden zu viele falsche Codes eingegeben. Bitte versuche es später erneut \
+           oder verwende einen Wiederherstellungscode.",
+    En => "Too many incorrect codes were entered. Please try again later or use a \
+           recovery code.",
+}
+unit flash_two_factor_set_up {
+    De => "Die Zwei-Faktor-Authentifizierung ist eingerichtet. Erzeuge jetzt \
+           Wiederherstellungscodes, falls du dein Gerät verlierst!",
+    En => "Two-factor authentication is set up. Now generate recovery codes in \
+           case you lose your device!",
+}
 unit successful_login {
     De => "Du wurdest erfolgreich angemeldet.",
 }
 unit successful_logout {
     De => "Du wurdest erfolgreich abgemeldet.",
 }
+unit flash_success_impersonation_ended {
+    De => "Du bist wieder mit deinem eigenen Account angemeldet.",
+    En => "You are logged in with your own account again.",
+}
 
 // The box containing the actual login-form
 unit box_title {
@@ -51,11 +83,109 @@ unit password_hint {
 unit login_provider_label {
     De => "Anmelden mit:",
 }
+unit redirect_providers_label {
+    De => "Oder anmelden mit:",
+    En => "Or log in with:",
+}
 unit login_button_label { // TODO: it would be nice to say `login_button = box_title;`
     De => "Einloggen",
 }
 
 
+// Two-factor authentication
+unit two_factor_title {
+    De => "Zwei-Faktor-Authentifizierung",
+    En => "Two-factor authentication",
+}
+unit two_factor_explanation {
+    De => "Gib den Code aus deiner Authenticator-App ein. Falls du keinen Zugriff \
+           auf dein Gerät hast, kannst du einen Wiederherstellungscode benutzen.",
+    En => "Enter the code from your authenticator app. If you don't have access \
+           to your device, you can use a recovery code.",
+}
+unit two_factor_setup_title {
+    De => "Zwei-Faktor-Authentifizierung einrichten",
+    En => "Set up two-factor authentication",
+}
+unit two_factor_setup_explanation {
+    De => "Scanne diesen QR-Code mit einer Authenticator-App (z.B. FreeOTP oder \
+           Google Authenticator) und gib zur Bestätigung den angezeigten Code ein.",
+    En => "Scan this QR code with an authenticator app (e.g. FreeOTP or Google \
+           Authenticator) and enter the displayed code to confirm.",
+}
+unit two_factor_setup_manual_secret {
+    De => "Falls du den Code nicht scannen kannst, gib diesen Schlüssel ein:",
+    En => "If you cannot scan the code, enter this key:",
+}
+unit two_factor_code_placeholder {
+    De => "Code",
+    En => "Code",
+}
+unit two_factor_code_hint {
+    De => "Sechsstelliger Code oder Wiederherstellungscode",
+    En => "Six digit code or recovery code",
+}
+unit two_factor_submit {
+    De => "Bestätigen",
+    En => "Confirm",
+}
+
+
+// Passwords
+unit password_reset_title {
+    De => "Neues Passwort festlegen",
+    En => "Set a new password",
+}
+unit password_reset_explanation(username: &str) {
+    De => "Lege ein neues Passwort für den Account '{username}' fest. Danach \
+           wirst du auf allen Geräten abgemeldet.",
+    En => "Choose a new password for the account '{username}'. Afterwards, \
+           you will be logged out on all devices.",
+}
+unit new_password_label {
+    De => "Neues Passwort",
+    En => "New password",
+}
+unit new_password_hint(min_len: u64) {
+    De => "Mindestens {min_len} Zeichen, darf deinen Nutzernamen nicht enthalten",
+    En => "At least {min_len} characters, must not contain your username",
+}
+unit new_password_confirm_label {
+    De => "Neues Passwort wiederholen",
+    En => "Repeat new password",
+}
+unit password_reset_submit {
+    De => "Passwort speichern",
+    En => "Save password",
+}
+unit err_password_mismatch {
+    De => "Die beiden Passwörter stimmen nicht überein.",
+    En => "The two passwords don't match.",
+}
+unit err_password_too_short(min_len: u64) {
+    De => "Das Passwort muss mindestens {min_len} Zeichen lang sein.",
+    En => "The password has to be at least {min_len} characters long.",
+}
+unit err_password_too_simple {
+    De => "Das Passwort ist zu einfach: es besteht aus zu wenigen \
+           unterschiedlichen Zeichen.",
+    En => "The password is too simple: it consists of too few distinct \
+           characters.",
+}
+unit err_password_contains_username {
+    De => "Das Passwort darf den Nutzernamen nicht enthalten.",
+    En => "The password must not contain the username.",
+}
+unit flash_err_reset_token_invalid {
+    De => "Dieser Link ist ungültig oder abgelaufen.",
+    En => "This link is invalid or expired.",
+}
+unit flash_success_password_reset {
+    De => "Dein Passwort wurde geändert. Du kannst dich jetzt anmelden.",
+    En => "Your password was changed. You can log in now.",
+}
+
+
 // The box containing further information about the login process
 unit notice_box_title {
     De => "Hinweise",
@@ -5,32 +5,57 @@
 //! built-in login-provider: the simple password provider. See the module
 //! `password` for more information. Another good example is the LDAP-provider.
 //!
+//! Some providers don't ask for a username and secret, but redirect the user
+//! to an external identity provider (see `RedirectProvider`). The OpenID
+//! Connect provider in `oidc` and the SAML provider in `saml` are examples.
+//!
 //! In addition to the main functionality around login providers and the
 //! session-system, this module also provides routes for:
 //!
 //! - GET `/login`
 //! - POST `/login`
+//! - GET `/login/<provider>`: start login with a redirect provider
+//! - GET and POST `/login/<provider>/callback`: the user returns from the
+//!   external identity provider
+//! - GET `/login/saml/metadata`: SAML service provider metadata
+//! - GET and POST `/login/two_factor`: second login step for users with
+//!   two-factor authentification (see `totp`)
+//! - GET and POST `/password_reset/<token>`: set a new internal password with
+//!   a reset token (see `password`)
+//! - POST `/impersonation/end`: an admin stops viewing the app as another
+//!   user (see `impersonation`)
 //! - GET `/logout`
 
-use chrono::DateTime;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration};
 use chrono::offset::Utc;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel;
 use hex;
 use option_filter::OptionFilterExt;
 use rand::{self, Rng};
+use rocket::Outcome;
+use rocket::config::Environment;
 use rocket::http::{Cookie, Cookies};
+use rocket::request::{self, FromRequest, Request};
 
 use config;
 use db::Db;
-use db::schema::{sessions, users};
+use db::schema::{external_accounts, sessions, users};
 use dict::{self, Locale};
 use errors::*;
-use user::{AuthUser, User};
+use user::{AuthUser, Role, User};
+use user::profile::{check_email, ProfileError};
 
-mod html;
+pub mod html;
+pub mod impersonation;
 pub mod ldap;
+pub mod oidc;
 pub mod password;
+pub mod saml;
+pub mod totp;
 pub mod routes;
 
 
@@ -39,38 +64,439 @@ pub trait Provider: 'static + Sync {
     /// Returns a user facing name of the login provider.
     fn name(&self, locale: Locale) -> String;
 
-    /// Tries to authenticate with this provider.
-    fn auth(&self, id: &str, secret: &str, db: &Db) -> Result<User>;
+    /// Tries to authenticate with this provider using the username and secret
+    /// from the login form.
+    ///
+    /// Redirect providers don't need to implement this method.
+    fn auth(&self, _id: &str, _secret: &str, _db: &Db) -> Result<User> {
+        bail!(LoginError::ProviderNotUsable)
+    }
+
+    /// Returns `Some` if this provider doesn't use the login form, but
+    /// redirects the user to an external identity provider.
+    fn as_redirect(&self) -> Option<&RedirectProvider> {
+        None
+    }
+}
+
+/// A login-provider which redirects the user to an external identity
+/// provider (e.g. the university's single sign-on). After logging in there,
+/// the user is sent back to `/login/<provider>/callback`.
+pub trait RedirectProvider {
+    /// Returns the URL to which the user is redirected to log in.
+    ///
+    /// Everything needed in `finish()` to verify the response can be stored
+    /// in cookies.
+    fn start(&self, cookies: &mut Cookies) -> Result<String>;
+
+    /// Verifies the response of the identity provider and returns the
+    /// authenticated user. `params` are the query parameters (or form fields)
+    /// of the callback request.
+    fn finish(
+        &self,
+        params: &HashMap<String, String>,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<User>;
 }
 
 
 pub struct ProviderEntry {
     pub id: &'static str,
+
+    /// Providers which are `dev_only` can only be used by developers in
+    /// production (see `Developers`). In all other environments, everyone
+    /// can use them.
     pub dev_only: bool,
     pub imp: Box<Provider>,
 }
 
+impl ProviderEntry {
+    /// Returns whether this provider is offered at all in the given
+    /// environment.
+    pub fn is_offered(&self, env: Environment) -> bool {
+        !self.dev_only
+            || env != Environment::Production
+            || !config::FILE.developers.usernames.is_empty()
+    }
+
+    /// Returns whether the user with the given username may log in with this
+    /// provider in the given environment.
+    pub fn is_usable_by(&self, username: &str, env: Environment) -> bool {
+        !self.dev_only
+            || env != Environment::Production
+            || config::FILE.developers.contains(username)
+    }
+}
+
+/// The developers who may use `dev_only` login providers in production.
+///
+/// This list is configured in the `[developers]` section of the configuration
+/// file, so that it's always visible who has this kind of access. It is also
+/// shown in the admin panel.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Developers {
+    pub usernames: Vec<String>,
+}
+
+impl Developers {
+    pub fn contains(&self, username: &str) -> bool {
+        self.usernames.iter().any(|u| u == username)
+    }
+}
+
+
+/// The state of a login after the user was authenticated by a login provider.
+#[derive(Debug, Clone)]
+pub enum LoginStep {
+    /// A session was created: the user is logged in.
+    Done(AuthUser),
+
+    /// The user has to enter a TOTP code (or set up two-factor
+    /// authentification) at `/login/two_factor` first.
+    SecondFactor,
+}
 
 /// Tries to login a user with the given login provider. On success, creates a
-/// session and returns the authenticated user.
+/// session and returns the authenticated user, unless the user still needs to
+/// provide a second factor.
 pub fn login(
     username: &str,
     secret: &str,
     provider: &Provider,
-    cookies: Cookies,
+    client: &ClientInfo,
+    cookies: &mut Cookies,
     db: &Db,
-) -> Result<AuthUser> {
+) -> Result<LoginStep> {
     // Try to authenticate with the given provider. If it fails, we return an
     // error.
     let user = provider.auth(username, secret, db)?;
 
+    finish_first_step(user, client, cookies, db)
+}
+
+/// Continues the login of a user who was authenticated by a login provider:
+/// if they need a second factor, they are remembered as pending login.
+/// Otherwise, a session is created.
+pub fn finish_first_step(
+    user: User,
+    client: &ClientInfo,
+    cookies: &mut Cookies,
+    db: &Db,
+) -> Result<LoginStep> {
+    if totp::is_needed_for(&user, db)? {
+        totp::PendingLogin::start(&user, cookies);
+        Ok(LoginStep::SecondFactor)
+    } else {
+        start_session(user, client, cookies, db).map(LoginStep::Done)
+    }
+}
+
+/// Creates a session for an already authenticated user and returns it as
+/// `AuthUser`.
+pub fn start_session(
+    user: User,
+    client: &ClientInfo,
+    cookies: &mut Cookies,
+    db: &Db,
+) -> Result<AuthUser> {
     // Create a session in the database and set it as cookie.
-    let session = Session::create_for(&user, cookies, db)?;
+    let session = Session::create_for(&user, client, cookies, db)?;
 
     Ok(AuthUser::new(user, session))
 }
 
 
+/// Information about a user obtained from an external source (LDAP, an
+/// identity provider, ...) during login.
+#[derive(Debug, Clone, Default)]
+pub struct ExternalProfile {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub matriculation_number: Option<String>,
+    pub study_program: Option<String>,
+    pub semester: Option<i16>,
+    pub groups: Vec<String>,
+}
+
+/// Maps groups of an external source (e.g. LDAP groups) to the admin role.
+///
+/// Whether someone is a student or a tutor depends on the course (see
+/// `User::role_in()`) and is managed in the admin panel, so groups only
+/// decide who is an admin: if this mapping is configured for a provider,
+/// members of the `admin_groups` become admins on each login. Group names
+/// are compared case insensitively.
+///
+/// Unknown fields are rejected, so that a `tutor_groups` list of an old
+/// configuration isn't silently ignored.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RoleMapping {
+    /// Members of any of these groups are admins.
+    #[serde(default)]
+    pub admin_groups: Vec<String>,
+
+    /// Whether or not an admin is demoted if they are not in any of the
+    /// `admin_groups`. By default, this is `false`, so that admins who were
+    /// promoted manually don't lose their privileges on their next login.
+    #[serde(default)]
+    pub demote_admins: bool,
+}
+
+impl RoleMapping {
+    /// Returns the global role for a user with the given groups who
+    /// currently has the role `current` (`None` for new users). Roles other
+    /// than admin are kept; new users are students.
+    pub fn role_for(&self, groups: &[String], current: Option<Role>) -> Role {
+        let is_admin = groups.iter()
+            .any(|g| self.admin_groups.iter().any(|a| a.eq_ignore_ascii_case(g)));
+
+        match current {
+            _ if is_admin => Role::Admin,
+            Some(Role::Admin) if !self.demote_admins => Role::Admin,
+            Some(Role::Admin) | None => Role::Student,
+            Some(role) => role,
+        }
+    }
+}
+
+/// An account of a user at an external login provider.
+///
+/// Users of external providers are identified by the provider and the ID the
+/// provider uses for them, not by their username: usernames of different
+/// providers might collide and some providers let users choose their
+/// username.
+#[derive(Debug, Clone, Copy)]
+pub struct ExternalAccount<'a> {
+    /// The ID of the provider, e.g. `oidc`.
+    pub provider: &'a str,
+
+    /// The ID of the user at the provider, e.g. the `sub` claim of OpenID
+    /// Connect.
+    pub id: &'a str,
+}
+
+/// Which existing users a login provider may link to an external account the
+/// first time it is used.
+///
+/// Existing users which are not linked to any external account were created
+/// by admins, by the CSV import or before the provider was configured. Users
+/// with an internal password and users linked to another external account
+/// are never linked: otherwise, whoever controls a username at the provider
+/// could take over their account.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LinkExisting {
+    /// A new user is always created. If the username is taken, the login is
+    /// refused.
+    Never,
+
+    /// Existing users who are not admins are linked.
+    NonAdmins,
+
+    /// All existing users are linked.
+    All,
+}
+
+/// Loads the user with the given external account and updates their name,
+/// role and profile with the given external profile. If no user is linked to
+/// this account yet, the user with the given username is linked to it (as
+/// far as `link` allows) or a new user is created.
+///
+/// This is used by providers which don't store any information about users
+/// themselves.
+pub fn sync_user(
+    account: ExternalAccount,
+    username: &str,
+    link: LinkExisting,
+    profile: ExternalProfile,
+    roles: Option<&RoleMapping>,
+    db: &Db,
+) -> Result<User> {
+    let linked = external_accounts::table
+        .find((account.provider, account.id))
+        .select(external_accounts::user_id)
+        .first::<i64>(&*db.conn()?)
+        .optional()
+        .chain_err(|| "failed to load external account")?;
+
+    let existing = match linked {
+        Some(user_id) => {
+            match User::load_by_id(user_id, db)? {
+                Some(user) => Some(user),
+                None => bail!("user {} of external account doesn't exist", user_id),
+            }
+        }
+        None => {
+            match User::load_by_username(username, db)? {
+                Some(user) => {
+                    if !may_link(&user, link, db)? {
+                        bail!(LoginError::ProviderNotUsable);
+                    }
+                    link_account(account, &user, &*db.conn()?)?;
+                    Some(user)
+                }
+                None => None,
+            }
+        }
+    };
+
+    // Update the user we found...
+    let mut user = if let Some(mut user) = existing {
+        // If the external source doesn't know the name, we keep the one we
+        // have.
+        if profile.name.is_some() && profile.name != user.name {
+            user.set_name(profile.name, db)?;
+        }
+
+        if let Some(roles) = roles {
+            let role = roles.role_for(&profile.groups, Some(user.role()));
+            if role != user.role() {
+                user.set_role(role, db)?;
+            }
+        }
+
+        user
+    }
+
+    // ... or create a new entry
+    else {
+        let role = roles
+            .map(|roles| roles.role_for(&profile.groups, None))
+            .unwrap_or(Role::Student);
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            let user = User::create_on(username.into(), profile.name, role, conn)?;
+            link_account(account, &user, conn)?;
+
+            Ok(user)
+        })?
+    };
+
+    // Same for the profile fields: unknown fields are kept. Invalid email
+    // addresses are ignored, too, instead of refusing the login.
+    let mut new_profile = user.profile();
+    if let Some(email) = profile.email.filter(|email| check_email(email).is_ok()) {
+        new_profile.email = Some(email);
+    }
+    if profile.matriculation_number.is_some() {
+        new_profile.matriculation_number = profile.matriculation_number;
+    }
+    if profile.study_program.is_some() {
+        new_profile.study_program = profile.study_program;
+    }
+    if profile.semester.is_some() {
+        new_profile.semester = profile.semester;
+    }
+
+    if new_profile != user.profile() {
+        match user.set_profile(new_profile.clone(), db) {
+            Ok(()) => {}
+
+            // We don't want to refuse the login just because an admin
+            // assigned the matriculation number to the wrong user. Instead,
+            // the old number is kept.
+            Err(Error(ErrorKind::ProfileError(ProfileError::MatriculationNumberTaken), _)) => {
+                new_profile.matriculation_number = user.profile().matriculation_number;
+                user.set_profile(new_profile, db)?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(user)
+}
+
+/// Returns whether the given existing user, who is not linked to the
+/// external account in question, may be linked to it (see `LinkExisting`).
+fn may_link(user: &User, link: LinkExisting, db: &Db) -> Result<bool> {
+    let allowed = match link {
+        LinkExisting::Never => false,
+        LinkExisting::NonAdmins => !user.is_admin(),
+        LinkExisting::All => true,
+    };
+    if !allowed {
+        return Ok(false);
+    }
+
+    let other_accounts = external_accounts::table
+        .filter(external_accounts::user_id.eq(user.id()))
+        .count()
+        .get_result::<i64>(&*db.conn()?)
+        .chain_err(|| "failed to load external accounts")?;
+
+    Ok(other_accounts == 0 && password::Password::load(user, db)?.is_none())
+}
+
+fn link_account(account: ExternalAccount, user: &User, conn: &PgConnection) -> Result<()> {
+    #[derive(Insertable)]
+    #[table_name = "external_accounts"]
+    struct NewAccount<'a> {
+        provider: &'a str,
+        external_id: &'a str,
+        user_id: i64,
+    }
+
+    diesel::insert(&NewAccount {
+        provider: account.provider,
+        external_id: account.id,
+        user_id: user.id(),
+    })
+        .into(external_accounts::table)
+        .execute(conn)
+        .chain_err(|| "failed to link user to external account")?;
+
+    Ok(())
+}
+
+/// Returns the path the user initially requested before they were redirected
+/// to `/login` and removes the cookie storing that path.
+///
+/// The cookie is set by `special::catchers::unauthorized`. Since cookies can
+/// be freely modified by the client, `None` is returned if the stored value is
+/// not a safe redirect target (see `is_safe_redirect_target()`).
+pub fn take_initial_request_path(cookies: &mut Cookies) -> Option<String> {
+    let path = cookies.get(config::INITIAL_REQ_COOKIE_NAME)
+        .map(|cookie| cookie.value().to_owned());
+
+    if path.is_some() {
+        // The cookie was set with path `/`. To remove it, we have to pass a
+        // cookie with the same path.
+        let cookie = Cookie::build(config::INITIAL_REQ_COOKIE_NAME, "")
+            .path("/")
+            .finish();
+        cookies.remove(cookie);
+    }
+
+    path.filter(|path| is_safe_redirect_target(path))
+}
+
+/// Checks if we can redirect to the given target without leaving this
+/// website.
+///
+/// We only allow absolute paths on our own host. Everything else could be
+/// used to redirect users to a malicious website which looks like ours (open
+/// redirect). Redirecting to the login page itself is not allowed either.
+pub fn is_safe_redirect_target(target: &str) -> bool {
+    // Relative paths and full URLs (`https://evil.com`) don't start with a
+    // slash.
+    target.starts_with('/')
+        // `//evil.com` is a protocol-relative URL pointing to another host.
+        && !target.starts_with("//")
+        // Browsers treat backslashes as slashes, so `/\evil.com` is the same
+        // as `//evil.com`.
+        && !target.contains('\\')
+        // Browsers silently remove tabs and newlines from URLs (which would
+        // allow `/\t/evil.com`) and CR/LF could be used to inject headers.
+        && !target.chars().any(|c| c.is_control() || c.is_whitespace())
+        // It makes no sense to show the login page after a successful login.
+        && target != "/login"
+        && !target.starts_with("/login?")
+        && !target.starts_with("/login/")
+}
+
+
 quick_error! {
     #[derive(Debug)]
     pub enum LoginError {
@@ -93,6 +519,12 @@ quick_error! {
         ProviderNotUsable {
             description("the given user cannot be authenticated with this provider")
         }
+
+        /// The login at an external identity provider was cancelled or its
+        /// response was invalid.
+        ExternalLoginFailed {
+            description("the login at the external identity provider failed")
+        }
     }
 }
 
@@ -105,24 +537,74 @@ impl LoginError {
             LoginError::SecretIncorrect => dict.err_incorrect_secret(),
             LoginError::CredentialsIncorrect => dict.err_credentials_incorrect(),
             LoginError::ProviderNotUsable => dict.err_provider_not_usable(),
+            LoginError::ExternalLoginFailed => dict.err_external_login_failed(),
         }
     }
 }
 
 
 
+/// Information about the client sending a request.
+///
+/// This is stored alongside a session to help users to recognize their
+/// sessions. This type implements `FromRequest` and never fails.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ClientInfo {
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ClientInfo {
+    type Error = ();
+
+    fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(ClientInfo {
+            user_agent: req.headers().get_one("User-Agent").map(|s| s.to_owned()),
+            ip_address: req.remote().map(|addr| addr.ip().to_string()),
+        })
+    }
+}
+
+
 #[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable, Associations)]
 pub struct Session {
     /// A binary string, `config::SESSION_ID_LEN` bytes long.
     pub id: Vec<u8>,
     pub user_id: i64,
     pub birth: DateTime<Utc>,
+
+    /// The last time this session was used. This value is only updated every
+    /// `config::SESSION_LAST_USE_UPDATE_INTERVAL` seconds.
+    pub last_use: DateTime<Utc>,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+
+    /// If this session was created for an admin to view the app as this user,
+    /// the id of that impersonation (see `impersonation`).
+    pub impersonation_id: Option<i64>,
 }
 
 impl Session {
     /// Creates a new session for the given user, inserts it into the database
     /// and sets a session cookie.
-    pub fn create_for(user: &User, mut cookies: Cookies, db: &Db) -> Result<Self> {
+    pub fn create_for(
+        user: &User,
+        client: &ClientInfo,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<Self> {
+        Self::create(user, None, client, cookies, db)
+    }
+
+    /// Like `create_for()`, but the session belongs to the given
+    /// impersonation.
+    fn create(
+        user: &User,
+        impersonation_id: Option<i64>,
+        client: &ClientInfo,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<Self> {
         // Generate a random session id.
         let mut id = [0u8; config::SESSION_ID_LEN];
         let mut rng = rand::os::OsRng::new()
@@ -135,11 +617,17 @@ impl Session {
         pub struct NewSession {
             pub id: Vec<u8>,
             pub user_id: i64,
+            pub user_agent: Option<String>,
+            pub ip_address: Option<String>,
+            pub impersonation_id: Option<i64>,
         }
 
         let new_session = NewSession {
             id: id.to_vec(),
             user_id: user.id(),
+            user_agent: client.user_agent.clone(),
+            ip_address: client.ip_address.clone(),
+            impersonation_id,
         };
         let inserted_session = diesel::insert(&new_session)
             .into(sessions::table)
@@ -167,13 +655,105 @@ impl Session {
 
         // Try to find a session with the given id, load the user owning that
         // session and create an `AuthUser` from it.
-        sessions::table
+        let res = sessions::table
             .find(session_id)
             .inner_join(users::table)
             .first::<(Session, User)>(&*db.conn()?)
-            .optional()?
-            .map(|(session, user)| AuthUser::new(user, session))
-            .make_ok()
+            .optional()?;
+
+        match res {
+            None => Ok(None),
+            Some((mut session, user)) => {
+                session.touch(db)?;
+                Ok(Some(AuthUser::new(user, session)))
+            }
+        }
+    }
+
+    /// Loads all sessions of the given user, the most recently used first.
+    pub fn load_all_of_user(user: &User, db: &Db) -> Result<Vec<Self>> {
+        sessions::table
+            .filter(sessions::user_id.eq(user.id()))
+            .order(sessions::last_use.desc())
+            .load::<Self>(&*db.conn()?)
+            .chain_err(|| "failed to load sessions of user")
+    }
+
+    /// Returns a short identifier of this session which can be shown to the
+    /// user (see `config::SESSION_PUBLIC_ID_LEN`).
+    pub fn public_id(&self) -> String {
+        hex::encode(&self.id[..config::SESSION_PUBLIC_ID_LEN])
+    }
+
+    /// Sets the time of last use to now, if the stored value is older than
+    /// `config::SESSION_LAST_USE_UPDATE_INTERVAL`.
+    fn touch(&mut self, db: &Db) -> Result<()> {
+        let now = Utc::now();
+        let interval = Duration::seconds(config::SESSION_LAST_USE_UPDATE_INTERVAL);
+        if now.signed_duration_since(self.last_use) > interval {
+            diesel::update(sessions::table.find(&self.id))
+                .set(sessions::last_use.eq(now))
+                .execute(&*db.conn()?)
+                .chain_err(|| "failed to update last use of session")?;
+            self.last_use = now;
+        }
+
+        Ok(())
+    }
+
+    /// Removes the session of the given user with the given public id (see
+    /// `public_id()`) from the database. Returns the number of removed
+    /// sessions.
+    ///
+    /// The session `keep` (the current one) is never removed; that's what
+    /// logging out is for. Public ids are short and thus not necessarily
+    /// unique. In the unlikely event that the user has multiple other sessions
+    /// with the same public id, all of them are removed.
+    pub fn destroy_by_public_id(
+        user: &User,
+        public_id: &str,
+        keep: &Session,
+        db: &Db,
+    ) -> Result<usize> {
+        let ids: Vec<_> = Self::load_all_of_user(user, db)?
+            .into_iter()
+            .filter(|session| session.id != keep.id && session.public_id() == public_id)
+            .map(|session| session.id)
+            .collect();
+
+        diesel::delete(sessions::table.filter(sessions::id.eq_any(ids)))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to delete session")
+    }
+
+    /// Returns whether this session was created for an impersonation.
+    pub fn is_impersonation(&self) -> bool {
+        self.impersonation_id.is_some()
+    }
+
+    /// Removes this session from the database without touching any cookies.
+    fn remove(&self, db: &Db) -> Result<()> {
+        diesel::delete(sessions::table.find(&self.id))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to delete session")?;
+
+        Ok(())
+    }
+
+    /// Removes all sessions of the given user from the database, except the
+    /// one given as `keep` (if any). Returns the number of removed sessions.
+    ///
+    /// This logs out the user everywhere (except in the session `keep`).
+    pub fn destroy_all_of_user(user: &User, keep: Option<&Session>, db: &Db) -> Result<usize> {
+        let of_user = sessions::table.filter(sessions::user_id.eq(user.id()));
+
+        match keep {
+            Some(keep) => {
+                diesel::delete(of_user.filter(sessions::id.ne(&keep.id)))
+                    .execute(&*db.conn()?)
+            }
+            None => diesel::delete(of_user).execute(&*db.conn()?),
+        }.chain_err(|| "failed to delete sessions of user")
     }
 
     /// Ends a login session, removing the entry from the database and removing
@@ -198,3 +778,44 @@ impl Session {
         Ok(())
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_redirect_targets() {
+        assert!(is_safe_redirect_target("/"));
+        assert!(is_safe_redirect_target("/settings"));
+        assert!(is_safe_redirect_target("/admin_panel/users?search=foo"));
+        assert!(is_safe_redirect_target("/logins"));
+    }
+
+    #[test]
+    fn other_hosts() {
+        assert!(!is_safe_redirect_target("https://evil.com"));
+        assert!(!is_safe_redirect_target("//evil.com"));
+        assert!(!is_safe_redirect_target("/\\evil.com"));
+        assert!(!is_safe_redirect_target("\\\\evil.com"));
+        assert!(!is_safe_redirect_target("evil.com"));
+        assert!(!is_safe_redirect_target("javascript:alert(1)"));
+        assert!(!is_safe_redirect_target(""));
+    }
+
+    #[test]
+    fn control_characters() {
+        assert!(!is_safe_redirect_target("/\t/evil.com"));
+        assert!(!is_safe_redirect_target("/\r/evil.com"));
+        assert!(!is_safe_redirect_target("/\n/evil.com"));
+        assert!(!is_safe_redirect_target("/settings\r\nSet-Cookie: a=b"));
+        assert!(!is_safe_redirect_target("/ /evil.com"));
+    }
+
+    #[test]
+    fn login_page() {
+        assert!(!is_safe_redirect_target("/login"));
+        assert!(!is_safe_redirect_target("/login?redirect=/settings"));
+        assert!(!is_safe_redirect_target("/login/oidc"));
+    }
+}
@@ -0,0 +1,721 @@
+//! OpenID Connect authentification.
+//!
+//! Many universities run a single sign-on service speaking OpenID Connect
+//! (OIDC). With this provider, the user is redirected to that service and sent
+//! back to us with an authorization code. We exchange this code for an ID
+//! token, verify the token and find (or create) the user from its claims.
+//!
+//! We use the authorization code flow with PKCE (RFC 7636). Only ID tokens
+//! signed with `RS256` are accepted. The provider is configured in the
+//! `[oidc]` section of the configuration file (see `Config`).
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use base64;
+use chrono::Utc;
+use hyper::Client;
+use hyper::header::ContentType;
+use hyper::net::HttpsConnector;
+use hyper_native_tls::NativeTlsClient;
+use rand::{self, Rng};
+use ring::{digest, signature};
+use rocket::http::{Cookie, Cookies, SameSite};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+use untrusted;
+use url::Url;
+use url::form_urlencoded;
+
+use db::Db;
+use dict::Locale;
+use errors::*;
+use login::{
+    self, ExternalAccount, ExternalProfile, LinkExisting, LoginError, RedirectProvider, RoleMapping,
+};
+use user::User;
+
+
+/// The ID of this provider (see `login::ProviderEntry`).
+pub const PROVIDER_ID: &str = "oidc";
+
+/// The name of the private cookie which stores state, nonce and PKCE code
+/// verifier between `start()` and `finish()`.
+const STATE_COOKIE_NAME: &str = "oidc_login";
+
+/// How many seconds the clocks of the identity provider and this server may
+/// differ when checking the lifetime of an ID token.
+const ALLOWED_CLOCK_SKEW: i64 = 60;
+
+/// Configuration of the OpenID Connect provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The name of this provider shown on the login page, e.g. `Uni-SSO`.
+    pub display_name: String,
+
+    /// The issuer identifier of the identity provider, e.g.
+    /// `https://sso.uni-osnabrueck.de`. The provider metadata is loaded from
+    /// `<issuer>/.well-known/openid-configuration`.
+    pub issuer: String,
+
+    /// The client credentials we got when registering luten at the identity
+    /// provider.
+    pub client_id: String,
+    pub client_secret: String,
+
+    /// The absolute URL of `/login/oidc/callback`, exactly as registered at
+    /// the identity provider.
+    pub redirect_uri: String,
+
+    /// The scopes to request. `openid` is always requested.
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<String>,
+
+    /// Timeout in seconds for requests to the identity provider.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+
+    /// The names of the claims we are interested in.
+    #[serde(default)]
+    pub claims: Claims,
+
+    /// How to derive the role of a user from the `groups` claim. If this is
+    /// missing, the identity provider doesn't influence the roles of users.
+    pub roles: Option<RoleMapping>,
+
+    /// Users are identified by the `sub` claim. If this is `true`, existing
+    /// users (who are not admins and have no internal password) are linked
+    /// to the identity provider on their first login with the username from
+    /// the `username` claim. Only enable this if the identity provider
+    /// guarantees that this claim is unique and can't be changed by users.
+    #[serde(default)]
+    pub link_existing_users: bool,
+}
+
+fn default_scopes() -> Vec<String> {
+    vec!["openid".into(), "profile".into()]
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+/// Names of the ID token claims which contain information about the user.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Claims {
+    /// The username of new users in luten (default: `preferred_username`).
+    /// Users are identified by the `sub` claim, so this is only used when
+    /// they log in for the first time.
+    pub username: String,
+
+    /// The user's real name (default: `name`).
+    pub name: String,
+
+    /// The user's email address (default: `email`). Requires the `email`
+    /// scope with most identity providers.
+    pub email: String,
+
+    /// A list of groups the user is a member of (default: `groups`).
+    pub groups: String,
+}
+
+impl Default for Claims {
+    fn default() -> Self {
+        Self {
+            username: "preferred_username".into(),
+            name: "name".into(),
+            email: "email".into(),
+            groups: "groups".into(),
+        }
+    }
+}
+
+/// The part of the provider metadata (OpenID Connect Discovery 1.0) we need.
+#[derive(Debug, Clone, Deserialize)]
+struct Metadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+}
+
+/// The part of the token endpoint's response we need.
+#[derive(Debug, Clone, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// A JSON web key (RFC 7517) of the identity provider. We only support RSA
+/// keys.
+#[derive(Debug, Clone, Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JwtHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+
+/// Authenticating users via an OpenID Connect identity provider.
+pub struct Provider {
+    config: Config,
+}
+
+impl Provider {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    fn http_client(&self) -> Result<Client> {
+        let tls = NativeTlsClient::new()
+            .chain_err(|| "failed to initialize TLS")?;
+        let mut client = Client::with_connector(HttpsConnector::new(tls));
+
+        let timeout = Duration::from_secs(self.config.timeout);
+        client.set_read_timeout(Some(timeout));
+        client.set_write_timeout(Some(timeout));
+
+        Ok(client)
+    }
+
+    /// Sends a GET request to the given URL and parses the JSON response.
+    fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let res = self.http_client()?
+            .get(url)
+            .send()
+            .chain_err(|| format!("request to '{}' failed", url))?;
+
+        if !res.status.is_success() {
+            bail!("request to '{}' failed with status {}", url, res.status);
+        }
+
+        serde_json::from_reader(res)
+            .chain_err(|| format!("invalid JSON response from '{}'", url))
+    }
+
+    /// Loads the provider metadata.
+    ///
+    /// We don't cache the metadata (and the keys) since logins are rare
+    /// enough to justify the additional requests.
+    fn metadata(&self) -> Result<Metadata> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer.trim_right_matches('/'),
+        );
+        let metadata: Metadata = self.get_json(&url)?;
+
+        if metadata.issuer != self.config.issuer {
+            bail!(
+                "issuer in provider metadata ('{}') doesn't match the configured issuer",
+                metadata.issuer,
+            );
+        }
+
+        Ok(metadata)
+    }
+
+    /// Exchanges the authorization code for an ID token.
+    fn exchange_code(&self, metadata: &Metadata, code: &str, verifier: &str) -> Result<String> {
+        let body = form_urlencoded::Serializer::new(String::new())
+            .append_pair("grant_type", "authorization_code")
+            .append_pair("code", code)
+            .append_pair("redirect_uri", &self.config.redirect_uri)
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("client_secret", &self.config.client_secret)
+            .append_pair("code_verifier", verifier)
+            .finish();
+
+        let res = self.http_client()?
+            .post(&metadata.token_endpoint)
+            .header(ContentType::form_url_encoded())
+            .body(&body[..])
+            .send()
+            .chain_err(|| "request to token endpoint failed")?;
+
+        // The code might have been used already or it might have expired.
+        if !res.status.is_success() {
+            return login_failed(&format!("token endpoint responded with {}", res.status));
+        }
+
+        serde_json::from_reader::<_, TokenResponse>(res)
+            .chain_err(|| "invalid JSON response from token endpoint")
+            .map(|tokens| tokens.id_token)
+    }
+
+    /// Verifies the signature and the claims of the given ID token (OpenID
+    /// Connect Core 1.0, section 3.1.3.7) and returns its claims. `keys` are
+    /// the signing keys of the identity provider.
+    fn verify_id_token(
+        &self,
+        token: &str,
+        keys: &JwkSet,
+        nonce: &str,
+    ) -> Result<HashMap<String, Value>> {
+        let parts: Vec<_> = token.split('.').collect();
+        if parts.len() != 3 {
+            return login_failed("ID token is not a JWS in compact serialization");
+        }
+        let header: JwtHeader = decode_json_part(parts[0])?;
+        let claims: HashMap<String, Value> = decode_json_part(parts[1])?;
+        let signature = decode_part(parts[2])?;
+
+        // We only accept the algorithm we expect. Otherwise, an attacker
+        // could use `none` or a symmetric algorithm.
+        if header.alg != "RS256" {
+            return login_failed(&format!("unsupported JWS algorithm '{}'", header.alg));
+        }
+
+        // Find the key the token was signed with. If the token doesn't say
+        // which key it is, there must be only one.
+        let keys: Vec<_> = keys.keys
+            .iter()
+            .filter(|key| key.kty == "RSA")
+            .filter(|key| header.kid.is_none() || key.kid == header.kid)
+            .collect();
+        if keys.len() != 1 {
+            return login_failed("no unique signing key for ID token found");
+        }
+        let (n, e) = match (&keys[0].n, &keys[0].e) {
+            (&Some(ref n), &Some(ref e)) => (decode_part(n)?, decode_part(e)?),
+            _ => bail!("RSA key of identity provider is incomplete"),
+        };
+
+        let signing_input = format!("{}.{}", parts[0], parts[1]);
+        signature::verify(
+            &signature::RSA_PKCS1_2048_8192_SHA256,
+            untrusted::Input::from(&rsa_public_key_der(&n, &e)),
+            untrusted::Input::from(signing_input.as_bytes()),
+            untrusted::Input::from(&signature),
+        ).or_else(|_| login_failed("invalid ID token signature"))?;
+
+        // Now that we know the token is genuine, we check whether it is meant
+        // for us and is still valid.
+        let claim_str = |name: &str| claims.get(name).and_then(Value::as_str);
+
+        if claim_str("iss") != Some(&self.config.issuer) {
+            return login_failed("ID token was issued by someone else");
+        }
+
+        let client_id = self.config.client_id.as_str();
+        let audience_ok = match claims.get("aud") {
+            Some(&Value::String(ref aud)) => aud == client_id,
+            Some(&Value::Array(ref auds)) => {
+                auds.iter().any(|aud| aud.as_str() == Some(client_id))
+                    && (auds.len() == 1 || claim_str("azp") == Some(client_id))
+            }
+            _ => false,
+        };
+        if !audience_ok {
+            return login_failed("ID token is not meant for us");
+        }
+
+        let now = Utc::now().timestamp();
+        match claims.get("exp").and_then(Value::as_i64) {
+            Some(exp) if exp + ALLOWED_CLOCK_SKEW > now => {}
+            _ => return login_failed("ID token is expired"),
+        }
+        if let Some(iat) = claims.get("iat").and_then(Value::as_i64) {
+            if iat - ALLOWED_CLOCK_SKEW > now {
+                return login_failed("ID token was issued in the future");
+            }
+        }
+
+        // The nonce binds the token to the login started in this browser and
+        // prevents replay attacks.
+        if claim_str("nonce") != Some(nonce) {
+            return login_failed("ID token has an invalid nonce");
+        }
+
+        Ok(claims)
+    }
+}
+
+impl login::Provider for Provider {
+    fn name(&self, _: Locale) -> String {
+        self.config.display_name.clone()
+    }
+
+    fn as_redirect(&self) -> Option<&RedirectProvider> {
+        Some(self)
+    }
+}
+
+impl RedirectProvider for Provider {
+    fn start(&self, cookies: &mut Cookies) -> Result<String> {
+        let metadata = self.metadata()?;
+
+        let state = random_token(16)?;
+        let nonce = random_token(16)?;
+        let verifier = random_token(32)?;
+        let challenge = base64::encode_config(
+            digest::digest(&digest::SHA256, verifier.as_bytes()).as_ref(),
+            base64::URL_SAFE_NO_PAD,
+        );
+
+        // We remember those values in an encrypted cookie. The identity
+        // provider redirects the user back to us, which is a cross-site
+        // navigation: thus the cookie must not be `SameSite=Strict`.
+        let value = format!("{} {} {}", state, nonce, verifier);
+        let cookie = Cookie::build(STATE_COOKIE_NAME, value)
+            .path("/login")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .finish();
+        cookies.add_private(cookie);
+
+        let scope = Some("openid").into_iter()
+            .chain(self.config.scopes.iter().map(|s| s.as_str()).filter(|&s| s != "openid"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let url = Url::parse_with_params(&metadata.authorization_endpoint, &[
+            ("response_type", "code"),
+            ("client_id", self.config.client_id.as_str()),
+            ("redirect_uri", self.config.redirect_uri.as_str()),
+            ("scope", scope.as_str()),
+            ("state", state.as_str()),
+            ("nonce", nonce.as_str()),
+            ("code_challenge", challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ]).chain_err(|| "invalid authorization endpoint in provider metadata")?;
+
+        Ok(url.into_string())
+    }
+
+    fn finish(
+        &self,
+        params: &HashMap<String, String>,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<User> {
+        // The values from `start()` can only be used once.
+        let stored = cookies.get_private(STATE_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_owned());
+        cookies.remove_private(Cookie::build(STATE_COOKIE_NAME, "").path("/login").finish());
+
+        let stored = match stored {
+            Some(v) => v,
+            None => return login_failed("no OIDC login in progress"),
+        };
+        let stored: Vec<_> = stored.split(' ').collect();
+        if stored.len() != 3 {
+            return login_failed("invalid OIDC state cookie");
+        }
+        let (state, nonce, verifier) = (stored[0], stored[1], stored[2]);
+
+        // The user cancelled the login or the identity provider rejected our
+        // request.
+        if let Some(error) = params.get("error") {
+            return login_failed(&format!("identity provider returned error '{}'", error));
+        }
+
+        // The response has to belong to the login started in this browser
+        // (protects against CSRF).
+        if params.get("state").map(|s| s.as_str()) != Some(state) {
+            return login_failed("OIDC state mismatch");
+        }
+
+        let code = match params.get("code") {
+            Some(code) => code,
+            None => return login_failed("no authorization code in response"),
+        };
+
+        let metadata = self.metadata()?;
+        let id_token = self.exchange_code(&metadata, code, verifier)?;
+        let keys = self.get_json::<JwkSet>(&metadata.jwks_uri)?;
+        let claims = self.verify_id_token(&id_token, &keys, nonce)?;
+
+        let sub = match claims.get("sub").and_then(Value::as_str) {
+            Some(sub) => sub,
+            None => return login_failed("ID token doesn't contain the claim 'sub'"),
+        };
+
+        let claim_names = &self.config.claims;
+        let username = match claims.get(&claim_names.username).and_then(Value::as_str) {
+            Some(username) => username,
+            None => bail!("ID token doesn't contain the claim '{}'", claim_names.username),
+        };
+        let profile = ExternalProfile {
+            name: claims.get(&claim_names.name)
+                .and_then(Value::as_str)
+                .map(|s| s.to_owned()),
+            email: claims.get(&claim_names.email)
+                .and_then(Value::as_str)
+                .map(|s| s.to_owned()),
+            groups: claims.get(&claim_names.groups)
+                .and_then(Value::as_array)
+                .map(|groups| {
+                    groups.iter()
+                        .filter_map(Value::as_str)
+                        .map(|s| s.to_owned())
+                        .collect()
+                })
+                .unwrap_or_default(),
+            ..ExternalProfile::default()
+        };
+
+        let account = ExternalAccount { provider: PROVIDER_ID, id: sub };
+        let link = if self.config.link_existing_users {
+            LinkExisting::NonAdmins
+        } else {
+            LinkExisting::Never
+        };
+        login::sync_user(account, username, link, profile, self.config.roles.as_ref(), db)
+    }
+}
+
+
+/// Returns an error that is shown to the user as `ExternalLoginFailed`. The
+/// given reason is kept in the error chain.
+fn login_failed<T>(reason: &str) -> Result<T> {
+    Err(Error::from(reason))
+        .chain_err(|| ErrorKind::LoginError(LoginError::ExternalLoginFailed))
+}
+
+/// Returns `len` random bytes encoded as URL-safe base64.
+fn random_token(len: usize) -> Result<String> {
+    let mut bytes = vec![0u8; len];
+    let mut rng = rand::os::OsRng::new()
+        .chain_err(|| "Unable to use system RNG")?;
+    rng.fill_bytes(&mut bytes);
+
+    Ok(base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD))
+}
+
+/// Decodes one base64url encoded part of a JWT.
+fn decode_part(part: &str) -> Result<Vec<u8>> {
+    base64::decode_config(part, base64::URL_SAFE_NO_PAD)
+        .or_else(|_| login_failed("ID token contains invalid base64"))
+}
+
+/// Decodes one base64url encoded JSON part of a JWT.
+fn decode_json_part<T: DeserializeOwned>(part: &str) -> Result<T> {
+    serde_json::from_slice(&decode_part(part)?)
+        .or_else(|_| login_failed("ID token contains invalid JSON"))
+}
+
+/// Encodes the RSA public key with the modulus `n` and the exponent `e` (both
+/// big-endian) as DER `RSAPublicKey` (RFC 3447, appendix A.1.1). This is the
+/// format *ring* expects, while JSON web keys contain `n` and `e` directly.
+fn rsa_public_key_der(n: &[u8], e: &[u8]) -> Vec<u8> {
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        let len = content.len();
+        if len < 0x80 {
+            out.push(len as u8);
+        } else {
+            // Long form: number of length bytes followed by the length in
+            // big-endian.
+            let len_bytes: Vec<u8> = (0..8).rev()
+                .map(|i| (len >> (i * 8)) as u8)
+                .skip_while(|&b| b == 0)
+                .collect();
+            out.push(0x80 | len_bytes.len() as u8);
+            out.extend_from_slice(&len_bytes);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    fn integer(bytes: &[u8]) -> Vec<u8> {
+        // Strip leading zeros, but add one if the highest bit is set, since
+        // DER integers are signed.
+        let first_non_zero = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+        let bytes = &bytes[first_non_zero..];
+
+        let mut content = Vec::with_capacity(bytes.len() + 1);
+        if bytes.first().map_or(true, |&b| b & 0x80 != 0) {
+            content.push(0);
+        }
+        content.extend_from_slice(bytes);
+        tlv(0x02, &content)
+    }
+
+    let mut sequence = integer(n);
+    sequence.extend(integer(e));
+    tlv(0x30, &sequence)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISSUER: &str = "https://sso.example.org";
+    const CLIENT_ID: &str = "luten";
+    const NONCE: &str = "n-0S6_WzA2Mj";
+
+    // The tokens were signed with the key in `jwks.json`. They expire in
+    // 2100, except `expired.jwt`.
+    const VALID_TOKEN: &str = include_str!("testdata/oidc/valid.jwt");
+    const EXPIRED_TOKEN: &str = include_str!("testdata/oidc/expired.jwt");
+
+    fn provider(issuer: &str, client_id: &str) -> Provider {
+        Provider::new(Config {
+            display_name: "SSO".into(),
+            issuer: issuer.into(),
+            client_id: client_id.into(),
+            client_secret: "secret".into(),
+            redirect_uri: "https://luten.example.org/login/oidc/callback".into(),
+            scopes: default_scopes(),
+            timeout: default_timeout(),
+            claims: Claims::default(),
+            roles: None,
+            link_existing_users: false,
+        })
+    }
+
+    fn keys() -> JwkSet {
+        serde_json::from_str(include_str!("testdata/oidc/jwks.json")).unwrap()
+    }
+
+    fn assert_rejected(res: Result<HashMap<String, Value>>) {
+        match res {
+            Err(Error(ErrorKind::LoginError(LoginError::ExternalLoginFailed), _)) => {}
+            other => panic!("ID token was not rejected: {:?}", other),
+        }
+    }
+
+    /// Replaces the claims of the given token, keeping header and signature.
+    fn with_claims(token: &str, f: &Fn(&mut Value)) -> String {
+        let parts: Vec<_> = token.trim().split('.').collect();
+        let mut claims: Value = decode_json_part(parts[1]).unwrap();
+        f(&mut claims);
+        let payload = base64::encode_config(
+            serde_json::to_string(&claims).unwrap().as_bytes(),
+            base64::URL_SAFE_NO_PAD,
+        );
+
+        format!("{}.{}.{}", parts[0], payload, parts[2])
+    }
+
+    #[test]
+    fn valid_token() {
+        let claims = provider(ISSUER, CLIENT_ID)
+            .verify_id_token(VALID_TOKEN.trim(), &keys(), NONCE)
+            .unwrap();
+
+        assert_eq!(claims["sub"], "248289761001");
+        assert_eq!(claims["preferred_username"], "alice");
+    }
+
+    #[test]
+    fn tampered_claims() {
+        let token = with_claims(VALID_TOKEN, &|claims| {
+            claims["preferred_username"] = Value::from("admin");
+        });
+        assert_rejected(provider(ISSUER, CLIENT_ID).verify_id_token(&token, &keys(), NONCE));
+    }
+
+    #[test]
+    fn signed_with_other_key() {
+        let keys = serde_json::from_str(include_str!("testdata/oidc/jwks_other_key.json")).unwrap();
+        assert_rejected(provider(ISSUER, CLIENT_ID).verify_id_token(VALID_TOKEN.trim(), &keys, NONCE));
+    }
+
+    #[test]
+    fn unsigned_token() {
+        let header = base64::encode_config(br#"{"alg":"none"}"#, base64::URL_SAFE_NO_PAD);
+        let payload = VALID_TOKEN.split('.').nth(1).unwrap();
+        let token = format!("{}.{}.", header, payload);
+        assert_rejected(provider(ISSUER, CLIENT_ID).verify_id_token(&token, &keys(), NONCE));
+    }
+
+    #[test]
+    fn malformed_token() {
+        let provider = provider(ISSUER, CLIENT_ID);
+        assert_rejected(provider.verify_id_token("", &keys(), NONCE));
+        assert_rejected(provider.verify_id_token("a.b", &keys(), NONCE));
+        assert_rejected(provider.verify_id_token("a.b.c.d", &keys(), NONCE));
+        assert_rejected(provider.verify_id_token("!!.??.##", &keys(), NONCE));
+    }
+
+    #[test]
+    fn wrong_issuer() {
+        let provider = provider("https://sso.example.com", CLIENT_ID);
+        assert_rejected(provider.verify_id_token(VALID_TOKEN.trim(), &keys(), NONCE));
+    }
+
+    #[test]
+    fn wrong_audience() {
+        let provider = provider(ISSUER, "some-other-client");
+        assert_rejected(provider.verify_id_token(VALID_TOKEN.trim(), &keys(), NONCE));
+    }
+
+    #[test]
+    fn expired_token() {
+        let provider = provider(ISSUER, CLIENT_ID);
+        assert_rejected(provider.verify_id_token(EXPIRED_TOKEN.trim(), &keys(), NONCE));
+    }
+
+    #[test]
+    fn wrong_nonce() {
+        let provider = provider(ISSUER, CLIENT_ID);
+        assert_rejected(provider.verify_id_token(VALID_TOKEN.trim(), &keys(), "n-other"));
+    }
+
+    #[test]
+    fn public_key_der_matches_openssl() {
+        // `public_key.der` was exported from the key in `jwks.json` with
+        // OpenSSL.
+        let keys = keys();
+        let n = decode_part(keys.keys[0].n.as_ref().unwrap()).unwrap();
+        let e = decode_part(keys.keys[0].e.as_ref().unwrap()).unwrap();
+
+        assert_eq!(
+            rsa_public_key_der(&n, &e),
+            &include_bytes!("testdata/oidc/public_key.der")[..],
+        );
+    }
+
+    #[test]
+    fn public_key_der_short_integers() {
+        assert_eq!(
+            rsa_public_key_der(&[0x7f], &[0x01, 0x00, 0x01]),
+            vec![0x30, 0x08, 0x02, 0x01, 0x7f, 0x02, 0x03, 0x01, 0x00, 0x01],
+        );
+
+        // Leading zeros are removed...
+        assert_eq!(
+            rsa_public_key_der(&[0x00, 0x00, 0x7f], &[0x00, 0x03]),
+            vec![0x30, 0x06, 0x02, 0x01, 0x7f, 0x02, 0x01, 0x03],
+        );
+
+        // ... but a zero is added if the highest bit is set, since DER
+        // integers are signed.
+        assert_eq!(
+            rsa_public_key_der(&[0x00, 0x80], &[0x03]),
+            vec![0x30, 0x07, 0x02, 0x02, 0x00, 0x80, 0x02, 0x01, 0x03],
+        );
+    }
+
+    #[test]
+    fn public_key_der_long_lengths() {
+        // A 2048 bit modulus needs 257 bytes with the leading zero: the
+        // length is encoded in two bytes, and so is the one of the sequence.
+        let n = vec![0xff; 256];
+        let der = rsa_public_key_der(&n, &[0x01, 0x00, 0x01]);
+
+        assert_eq!(der.len(), 4 + 266);
+        assert_eq!(&der[..9], &[0x30, 0x82, 0x01, 0x0a, 0x02, 0x82, 0x01, 0x01, 0x00]);
+        assert_eq!(&der[9..265], &n[..]);
+        assert_eq!(&der[265..], &[0x02, 0x03, 0x01, 0x00, 0x01]);
+
+        // The integer still fits the short form, the sequence (132 bytes)
+        // doesn't.
+        let der = rsa_public_key_der(&[0x7f; 127], &[0x03]);
+        assert_eq!(&der[..5], &[0x30, 0x81, 0x84, 0x02, 0x7f]);
+    }
+}
@@ -4,11 +4,16 @@
 //! used for users in development or for dummy-users. Real users should
 //! probably be authenticated via LDAP or something like that.
 
+use chrono::{DateTime, Duration, Utc};
 use diesel;
 use diesel::prelude::*;
-use pwhash::bcrypt;
+use hex;
+use pwhash::bcrypt::{self, BcryptSetup};
+use rand::{self, Rng};
+use ring::digest;
 
-use db::schema::passwords;
+use config;
+use db::schema::{password_reset_tokens, passwords};
 use db::Db;
 use dict::{self, Locale};
 use errors::*;
@@ -45,10 +50,38 @@ impl Password {
             .make_ok()
     }
 
+    /// Sets the password of the given user to the given plain password: an
+    /// existing password is replaced, otherwise a new one is created.
+    pub fn set_for(user: &User, plain_pw: &str, db: &Db) -> Result<Self> {
+        let hash = Self::hash_of(plain_pw)?;
+
+        let updated = diesel::update(passwords::table.find(user.id))
+            .set(passwords::hash.eq(&hash))
+            .get_result::<Self>(&*db.conn()?)
+            .optional()
+            .chain_err(|| "Error updating a password")?;
+
+        match updated {
+            Some(password) => Ok(password),
+            None => {
+                diesel::insert(&Self { user_id: user.id, hash })
+                    .into(passwords::table)
+                    .get_result::<Self>(&*db.conn()?)
+                    .chain_err(|| "Error inserting a new password")
+            }
+        }
+    }
+
     /// Returns the raw hash for the given plain text password. This hash will
     /// be stored in the database when `create_for()` is called.
     pub fn hash_of(plain_pw: &str) -> Result<String> {
-        bcrypt::hash(plain_pw).map_err(|e| e.into())
+        let setup = BcryptSetup {
+            salt: None,
+            cost: Some(config::PASSWORD_BCRYPT_COST),
+            variant: None,
+        };
+
+        bcrypt::hash_with(setup, plain_pw).map_err(|e| e.into())
     }
 
     /// Loads the password of the given user from the database. Returns `None`
@@ -65,6 +98,139 @@ impl Password {
     pub fn verify(&self, password: &str) -> bool {
         bcrypt::verify(password, &self.hash)
     }
+
+    /// Returns `true` if this hash was created with a lower cost than
+    /// `config::PASSWORD_BCRYPT_COST`.
+    pub fn needs_rehash(&self) -> bool {
+        // The hash looks like `$2b$10$<salt and hash>`.
+        self.hash.split('$')
+            .nth(2)
+            .and_then(|cost| cost.parse::<u32>().ok())
+            .map_or(true, |cost| cost < config::PASSWORD_BCRYPT_COST)
+    }
+}
+
+/// Reasons why a new password is rejected.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PasswordRejection {
+    /// The password and its confirmation don't match.
+    Mismatch,
+    TooShort,
+    TooSimple,
+    ContainsUsername,
+}
+
+impl PasswordRejection {
+    pub fn msg(&self, locale: Locale) -> String {
+        let dict = dict::new(locale).login;
+        match *self {
+            PasswordRejection::Mismatch => dict.err_password_mismatch(),
+            PasswordRejection::TooShort => dict.err_password_too_short(config::PASSWORD_MIN_LEN as u64),
+            PasswordRejection::TooSimple => dict.err_password_too_simple(),
+            PasswordRejection::ContainsUsername => dict.err_password_contains_username(),
+        }
+    }
+}
+
+/// Checks whether the given new password (and its confirmation) is good
+/// enough for the user with the given username.
+pub fn check_new_password(
+    username: &str,
+    plain_pw: &str,
+    confirmation: &str,
+) -> StdResult<(), PasswordRejection> {
+    if plain_pw != confirmation {
+        return Err(PasswordRejection::Mismatch);
+    }
+
+    if plain_pw.chars().count() < config::PASSWORD_MIN_LEN {
+        return Err(PasswordRejection::TooShort);
+    }
+
+    // Rejects things like "aaaaaaaaaaaa" or "121212121212".
+    let mut chars = plain_pw.chars().collect::<Vec<_>>();
+    chars.sort();
+    chars.dedup();
+    if chars.len() < config::PASSWORD_MIN_DISTINCT_CHARS {
+        return Err(PasswordRejection::TooSimple);
+    }
+
+    if plain_pw.to_lowercase().contains(&username.to_lowercase()) {
+        return Err(PasswordRejection::ContainsUsername);
+    }
+
+    Ok(())
+}
+
+
+/// One-time tokens which allow setting a new password without knowing the
+/// old one. They are created by admins and passed to the user as link.
+///
+/// Only the SHA-256 hash of a token is stored in the database.
+pub struct PasswordResetToken;
+
+impl PasswordResetToken {
+    /// Creates a new token for the given user. Returns the token and the point
+    /// in time at which it expires.
+    pub fn create_for(user: &User, db: &Db) -> Result<(String, DateTime<Utc>)> {
+        #[derive(Insertable)]
+        #[table_name = "password_reset_tokens"]
+        struct NewToken {
+            hash: Vec<u8>,
+            user_id: i64,
+            expires: DateTime<Utc>,
+        }
+
+        let mut bytes = [0u8; config::PASSWORD_RESET_TOKEN_LEN];
+        let mut rng = rand::os::OsRng::new()
+            .chain_err(|| "Unable to use system RNG")?;
+        rng.fill_bytes(&mut bytes);
+        let token = hex::encode(&bytes);
+
+        let new = NewToken {
+            hash: Self::hash(&token),
+            user_id: user.id,
+            expires: Utc::now() + Duration::hours(config::PASSWORD_RESET_TOKEN_VALIDITY),
+        };
+        diesel::insert(&new)
+            .into(password_reset_tokens::table)
+            .execute(&*db.conn()?)
+            .chain_err(|| "Error inserting a password reset token")?;
+
+        Ok((token, new.expires))
+    }
+
+    /// Returns the user the given token belongs to, if the token exists and
+    /// is not expired yet.
+    pub fn user_of(token: &str, db: &Db) -> Result<Option<User>> {
+        let user_id = password_reset_tokens::table
+            .find(Self::hash(token))
+            .filter(password_reset_tokens::expires.gt(Utc::now()))
+            .select(password_reset_tokens::user_id)
+            .first::<i64>(&*db.conn()?)
+            .optional()?;
+
+        match user_id {
+            Some(id) => User::load_by_id(id, db),
+            None => Ok(None),
+        }
+    }
+
+    /// Removes all tokens of the given user (e.g. after one of them was used)
+    /// and all expired tokens of any user.
+    pub fn remove_all_of(user: &User, db: &Db) -> Result<()> {
+        diesel::delete(
+            password_reset_tokens::table
+                .filter(password_reset_tokens::user_id.eq(user.id)
+                    .or(password_reset_tokens::expires.le(Utc::now())))
+        ).execute(&*db.conn()?)?;
+
+        Ok(())
+    }
+
+    fn hash(token: &str) -> Vec<u8> {
+        digest::digest(&digest::SHA256, token.as_bytes()).as_ref().to_vec()
+    }
 }
 
 /// Authenticating users via passwords stored by this application.
@@ -85,6 +251,12 @@ impl login::Provider for Provider {
             .ok_or(LoginError::ProviderNotUsable)?;
 
         if pw.verify(secret) {
+            // This is the only time we know the plain password, so this is
+            // our chance to upgrade old hashes.
+            if pw.needs_rehash() {
+                Password::set_for(&user, secret, db)?;
+            }
+
             Ok(user)
         } else {
             bail!(LoginError::SecretIncorrect)
@@ -1,16 +1,24 @@
+use std::collections::HashMap;
+
 use rocket::config::{Config, Environment};
 use rocket::http::{Cookies, Status};
-use rocket::request::Form;
-use rocket::response::{Flash, Redirect};
+use rocket::request::{Form, FormItems, FromForm};
+use rocket::response::{content, Flash, Redirect};
 use rocket::State;
 
 use config;
 use db::Db;
 use dict::{self, Locale};
 use errors::*;
-use super::{html, login};
+use super::{
+    html, login, saml, finish_first_step, start_session, take_initial_request_path, ClientInfo,
+    LoginError, LoginStep, ProviderEntry, RedirectProvider, Session,
+};
+use super::impersonation::Impersonation;
+use super::password::{self, Password, PasswordResetToken};
+use super::totp::{self, CodeCheck, PendingLogin, TotpSecret};
 use template::Page;
-use user::AuthUser;
+use user::{AuthUser, User};
 
 
 /// Shows a login form.
@@ -31,14 +39,9 @@ fn login_form(
         // Otherwise we show the login form.
         None => {
             // If we're in production, filter out the providers that are tagged
-            // `dev_only`.
-            // TODO: we want to give developers the ability to use `dev_only`
-            // providers in production. Maybe with a magic `i_am_a_dev` cookie
-            // or something stupid like that.
+            // `dev_only`, unless there are developers who may use them.
             let providers = config::LOGIN_PROVIDERS.iter()
-                .filter(|prov| !(
-                    prov.dev_only && config.inner().environment == Environment::Production
-                ))
+                .filter(|prov| prov.is_offered(config.environment))
                 .collect::<Vec<_>>();
 
             Page::empty()
@@ -53,32 +56,353 @@ fn login_form(
 /// Handles post data from a login action.
 ///
 /// Tries to login with the given data. Creates a login-session if the login-
-/// attempt was successful.
+/// attempt was successful. In that case, the user is redirected to the page
+/// they initially requested (if any).
 #[post("/login", data = "<form>")]
 fn validate_data(
-    cookies: Cookies,
+    mut cookies: Cookies,
+    client: ClientInfo,
     form: Form<LoginForm>,
+    config: State<Config>,
     db: State<Db>,
     locale: Locale,
 ) -> Result<Flash<Redirect>> {
     let form = form.into_inner();
 
-    // Find the login provider the user chose. If there is no such provider,
-    // we respond with "400 Bad Request".
+    // Find the login provider the user chose. If there is no such provider
+    // (or it's not offered in this environment), we respond with "400 Bad
+    // Request".
     let login_provider = config::LOGIN_PROVIDERS.iter()
-        .find(|prov| prov.id == form.login_provider)
+        .find(|prov| prov.id == form.login_provider && prov.is_offered(config.environment))
         .ok_or(ErrorKind::BadHttp(Status::BadRequest))?;
 
-    let res = login(&form.id, &form.secret, &*login_provider.imp, cookies, &db);
+    // Only developers may use `dev_only` providers in production. This is
+    // checked before authenticating to not give anyone else the chance to
+    // guess passwords.
+    if !login_provider.is_usable_by(&form.id, config.environment) {
+        let res = Err(LoginError::ProviderNotUsable.into());
+        return login_response(res, &mut cookies, locale);
+    }
+
+    let res = login(
+        &form.id,
+        &form.secret,
+        &*login_provider.imp,
+        &client,
+        &mut cookies,
+        &db,
+    );
+    login_response(res, &mut cookies, locale)
+}
+
+/// Starts a login with a redirect provider (e.g. OpenID Connect) by
+/// redirecting the user to the external identity provider.
+#[get("/login/<provider>")]
+fn start_redirect_login(
+    provider: String,
+    auth_user: Option<AuthUser>,
+    mut cookies: Cookies,
+    config: State<Config>,
+) -> Result<Redirect> {
+    // Same as with the login form: logged in users don't need to log in.
+    if auth_user.is_some() {
+        return Ok(Redirect::to("/"));
+    }
+
+    let (_, provider) = find_redirect_provider(&provider, config.environment)?;
+    let url = provider.start(&mut cookies)?;
+    Ok(Redirect::to(&url))
+}
+
+/// The external identity provider sends the user back to this route after
+/// they logged in there. If the response is valid, a login-session is
+/// created, just like in `validate_data()`.
+#[get("/login/<provider>/callback?<params>")]
+fn redirect_callback(
+    provider: String,
+    params: CallbackParams,
+    cookies: Cookies,
+    client: ClientInfo,
+    config: State<Config>,
+    db: State<Db>,
+    locale: Locale,
+) -> Result<Flash<Redirect>> {
+    finish_redirect_login(&provider, params, cookies, client, config.environment, &db, locale)
+}
+
+/// Like `redirect_callback()`, but for identity providers which send their
+/// response as form data (e.g. SAML with the HTTP-POST binding).
+#[post("/login/<provider>/callback", data = "<form>")]
+fn redirect_callback_post(
+    provider: String,
+    form: Form<CallbackParams>,
+    cookies: Cookies,
+    client: ClientInfo,
+    config: State<Config>,
+    db: State<Db>,
+    locale: Locale,
+) -> Result<Flash<Redirect>> {
+    let params = form.into_inner();
+    finish_redirect_login(&provider, params, cookies, client, config.environment, &db, locale)
+}
+
+/// Serves the metadata of luten as SAML service provider. If the SAML provider
+/// is not configured, we respond with "404 Not Found".
+#[get("/login/saml/metadata")]
+fn saml_metadata() -> Option<content::Xml<String>> {
+    config::FILE.saml.as_ref()
+        .map(|conf| content::Xml(saml::metadata(conf)))
+}
+
+fn finish_redirect_login(
+    provider: &str,
+    params: CallbackParams,
+    mut cookies: Cookies,
+    client: ClientInfo,
+    env: Environment,
+    db: &Db,
+    locale: Locale,
+) -> Result<Flash<Redirect>> {
+    let (entry, provider) = find_redirect_provider(provider, env)?;
+
+    // With redirect providers, we only know who the user is after they
+    // logged in at the identity provider. So the `dev_only` check has to
+    // happen afterwards.
+    let res = match provider.finish(&params.0, &mut cookies, db) {
+        Ok(ref user) if !entry.is_usable_by(user.username(), env) => {
+            Err(LoginError::ProviderNotUsable.into())
+        }
+        Ok(user) => finish_first_step(user, &client, &mut cookies, db),
+        Err(e) => Err(e),
+    };
+    login_response(res, &mut cookies, locale)
+}
+
+/// The second login step for users with two-factor authentification: asks
+/// for a TOTP code. Users who have to use a second factor, but haven't set one
+/// up yet, do that here.
+#[get("/login/two_factor")]
+fn two_factor_form(
+    mut cookies: Cookies,
+    db: State<Db>,
+    locale: Locale,
+) -> Result<StdResult<Page, Redirect>> {
+    // Without a pending login, there is nothing to do here.
+    let (mut pending, user) = match load_pending_login(&mut cookies, &db)? {
+        Some(v) => v,
+        None => return Ok(Err(Redirect::to("/login"))),
+    };
+
+    let content = if TotpSecret::load(&user, &db)?.is_some() {
+        html::two_factor_page(locale)
+    } else {
+        // The secret is generated once per pending login, so that reloading
+        // the page doesn't invalidate an already scanned QR code.
+        let secret = match pending.setup_secret.clone() {
+            Some(secret) => secret,
+            None => {
+                let secret = totp::generate_secret()?;
+                pending.setup_secret = Some(secret.clone());
+                pending.save(&mut cookies);
+                secret
+            }
+        };
+
+        html::two_factor_setup_page(&secret, &user, "/login/two_factor", locale)?
+    };
+
+    Ok(Ok(
+        Page::empty()
+            .with_title(dict::new(locale).login.two_factor_title())
+            .with_content(content)
+    ))
+}
+
+/// Checks the code from the second login step. On success, a login-session is
+/// created.
+#[post("/login/two_factor", data = "<form>")]
+fn two_factor_validate(
+    form: Form<TwoFactorForm>,
+    mut cookies: Cookies,
+    client: ClientInfo,
+    db: State<Db>,
+    locale: Locale,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).login;
+
+    let (pending, user) = match load_pending_login(&mut cookies, &db)? {
+        Some(v) => v,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/login"),
+                dict.flash_err_two_factor_expired(),
+            ));
+        }
+    };
+    let code = totp::normalize_code(&form.into_inner().code);
+
+    // Either the user already has a second factor or they just set one up.
+    let (check, was_setup) = match TotpSecret::load(&user, &db)? {
+        Some(mut secret) => (secret.check(&code, &db)?, false),
+        None => {
+            let step = pending.setup_secret.as_ref()
+                .and_then(|secret| totp::verify_code(secret, &code, 0));
+            match (pending.setup_secret, step) {
+                (Some(secret), Some(step)) => {
+                    TotpSecret::create(&user, secret, step, &db)?;
+                    (CodeCheck::Valid, true)
+                }
+                _ => (CodeCheck::Invalid, true),
+            }
+        }
+    };
+
+    match check {
+        CodeCheck::Valid => {
+            PendingLogin::remove(&mut cookies);
+            let auth_user = start_session(user, &client, &mut cookies, &db)?;
+
+            // Freshly set up users should generate recovery codes right away.
+            if was_setup {
+                Ok(Flash::warning(
+                    Redirect::to("/settings"),
+                    dict.flash_two_factor_set_up(),
+                ))
+            } else {
+                login_response(Ok(LoginStep::Done(auth_user)), &mut cookies, locale)
+            }
+        }
+        CodeCheck::Invalid => {
+            Ok(Flash::error(
+                Redirect::to("/login/two_factor"),
+                dict.flash_err_two_factor_incorrect(),
+            ))
+        }
+        // The user can still enter a recovery code.
+        CodeCheck::Locked => {
+            Ok(Flash::error(
+                Redirect::to("/login/two_factor"),
+                dict.flash_err_two_factor_locked(),
+            ))
+        }
+    }
+}
+
+/// Shows a form to set a new password with a reset token created by an admin.
+#[get("/password_reset/<token>")]
+fn password_reset_form(token: String, db: State<Db>, locale: Locale) -> Result<Page> {
+    let dict = dict::new(locale).login;
+
+    let page = match PasswordResetToken::user_of(&token, &db)? {
+        Some(user) => {
+            Page::empty()
+                .with_title(dict.password_reset_title())
+                .with_content(html::password_reset_page(&user, &token, locale))
+        }
+        None => Page::error(dict.flash_err_reset_token_invalid()),
+    };
+
+    Ok(page)
+}
+
+/// Sets a new password with a reset token. All sessions of the user are
+/// ended, as someone else might have known the old password.
+#[post("/password_reset/<token>", data = "<form>")]
+fn password_reset(
+    token: String,
+    form: Form<NewPasswordForm>,
+    db: State<Db>,
+    locale: Locale,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).login;
+    let form = form.into_inner();
+
+    let user = match PasswordResetToken::user_of(&token, &db)? {
+        Some(user) => user,
+        None => {
+            return Ok(Flash::error(
+                Redirect::to("/login"),
+                dict.flash_err_reset_token_invalid(),
+            ));
+        }
+    };
+
+    if let Err(rejection) = password::check_new_password(
+        user.username(),
+        &form.new_password,
+        &form.new_password_confirm,
+    ) {
+        return Ok(Flash::error(
+            Redirect::to(&format!("/password_reset/{}", token)),
+            rejection.msg(locale),
+        ));
+    }
+
+    Password::set_for(&user, &form.new_password, &db)?;
+    PasswordResetToken::remove_all_of(&user, &db)?;
+    Session::destroy_all_of_user(&user, None, &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/login"),
+        dict.flash_success_password_reset(),
+    ))
+}
+
+/// Returns the pending login of this browser together with its user.
+fn load_pending_login(cookies: &mut Cookies, db: &Db) -> Result<Option<(PendingLogin, User)>> {
+    let pending = match PendingLogin::load(cookies) {
+        Some(pending) => pending,
+        None => return Ok(None),
+    };
+
+    Ok(User::load_by_id(pending.user_id, db)?.map(|user| (pending, user)))
+}
+
+/// Finds the redirect provider with the given id. If there is no such
+/// provider (or it's not offered in this environment), we respond with "400
+/// Bad Request".
+fn find_redirect_provider(
+    id: &str,
+    env: Environment,
+) -> Result<(&'static ProviderEntry, &'static RedirectProvider)> {
+    config::LOGIN_PROVIDERS.iter()
+        .find(|prov| prov.id == id && prov.is_offered(env))
+        .and_then(|prov| prov.imp.as_redirect().map(|imp| (prov, imp)))
+        .ok_or(ErrorKind::BadHttp(Status::BadRequest).into())
+}
+
+/// Turns the result of a login attempt into a response.
+///
+/// On success, the user is sent to the page they initially requested (if
+/// any). Login errors are shown on the login page, all other errors are
+/// passed on.
+fn login_response(
+    res: Result<LoginStep>,
+    cookies: &mut Cookies,
+    locale: Locale,
+) -> Result<Flash<Redirect>> {
     match res {
-        Ok(_) => {
-            // TODO: redirect to the original request path
+        Ok(LoginStep::Done(_)) => {
+            // If the user was forwarded to the login page, we send them back
+            // to the page they wanted to see. The cookie is only removed on
+            // success, so that it survives failed login attempts.
+            let target = take_initial_request_path(cookies)
+                .unwrap_or_else(|| "/".into());
+
             let flash = Flash::success(
-                Redirect::to("/"),
+                Redirect::to(&target),
                 dict::new(locale).login.successful_login(),
             );
             Ok(flash)
         }
+        Ok(LoginStep::SecondFactor) => {
+            let flash = Flash::new(
+                Redirect::to("/login/two_factor"),
+                "info",
+                dict::new(locale).login.two_factor_needed(),
+            );
+            Ok(flash)
+        }
         Err(Error(ErrorKind::LoginError(e), _)) => {
             let flash = Flash::error(
                 Redirect::to("/login"),
@@ -90,6 +414,27 @@ fn validate_data(
     }
 }
 
+/// Ends an impersonation (see `impersonation`) and logs the admin back in.
+#[post("/impersonation/end")]
+fn end_impersonation(
+    auth_user: AuthUser,
+    mut cookies: Cookies,
+    client: ClientInfo,
+    db: State<Db>,
+    locale: Locale,
+) -> Result<StdResult<Flash<Redirect>, Redirect>> {
+    match Impersonation::end(auth_user, &client, &mut cookies, &db)? {
+        Some(_) => Ok(Flash::success(
+            Redirect::to("/admin_panel/impersonations"),
+            dict::new(locale).login.flash_success_impersonation_ended(),
+        )),
+
+        // This wasn't an impersonation session or the admin doesn't exist
+        // anymore.
+        None => Err(Redirect::to("/")),
+    }.make_ok()
+}
+
 /// Handler to logout the user. If there is no login present, nothing happens.
 #[delete("/logout")]
 fn logout(
@@ -99,6 +444,8 @@ fn logout(
     locale: Locale,
 ) -> Result<Flash<Redirect>> {
     if let Some(auth_user) = auth_user {
+        // Logging out also ends an impersonation.
+        Impersonation::finish(auth_user.session(), &db)?;
         auth_user.destroy_session(cookies, &db)?;
     }
     Ok(Flash::success(
@@ -113,3 +460,33 @@ struct LoginForm {
     secret: String,
     login_provider: String,
 }
+
+#[derive(FromForm)]
+struct NewPasswordForm {
+    new_password: String,
+    new_password_confirm: String,
+}
+
+#[derive(FromForm)]
+struct TwoFactorForm {
+    code: String,
+}
+
+/// All query parameters of the callback request. Which parameters are sent
+/// depends on the identity provider and on the outcome of the login.
+struct CallbackParams(HashMap<String, String>);
+
+impl<'f> FromForm<'f> for CallbackParams {
+    type Error = ();
+
+    fn from_form(items: &mut FormItems<'f>, _: bool) -> StdResult<Self, Self::Error> {
+        items.into_iter()
+            .map(|(key, value)| {
+                let key = key.url_decode().map_err(|_| ())?;
+                let value = value.url_decode().map_err(|_| ())?;
+                Ok((key, value))
+            })
+            .collect::<StdResult<_, _>>()
+            .map(CallbackParams)
+    }
+}
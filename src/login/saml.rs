@@ -0,0 +1,677 @@
+//! SAML 2.0 authentification (e.g. via Shibboleth).
+//!
+//! Universities which are part of a federation like DFN-AAI usually run a
+//! Shibboleth identity provider (IdP) speaking SAML 2.0. With this provider,
+//! luten acts as a SAML service provider (SP):
+//!
+//! - The user is redirected to the IdP with an `AuthnRequest` (HTTP-Redirect
+//!   binding).
+//! - After logging in, the IdP makes the user's browser POST a `Response` to
+//!   `/login/saml/callback` (HTTP-POST binding, our "assertion consumer
+//!   service").
+//! - We verify the signature of the response with the IdP's certificate and
+//!   check that it answers our request, is meant for us and is still valid.
+//!   Then we find (or create) the user from the attributes in the assertion.
+//!
+//! The metadata of our SP, which has to be registered at the IdP, is served at
+//! `/login/saml/metadata`.
+//!
+//! Verifying XML signatures correctly is hard, so we don't do it ourselves:
+//! we call the `xmlsec1` command line tool. Encrypted assertions are not
+//! supported; the IdP has to be configured to not encrypt assertions for
+//! luten.
+//!
+//! The provider is configured in the `[saml]` section of the configuration
+//! file (see `Config`).
+
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::process::Command;
+
+use base64;
+use chrono::{DateTime, Duration};
+use chrono::offset::Utc;
+use elementtree::Element;
+use flate2::Compression;
+use flate2::write::DeflateEncoder;
+use hex;
+use rand::{self, Rng};
+use rocket::http::{Cookie, Cookies};
+use url::Url;
+
+use db::Db;
+use dict::Locale;
+use errors::*;
+use login::{
+    self, ExternalAccount, ExternalProfile, LinkExisting, LoginError, RedirectProvider, RoleMapping,
+};
+use user::User;
+
+
+/// The ID of this provider (see `login::ProviderEntry`).
+pub const PROVIDER_ID: &str = "saml";
+
+/// The name of the cookie which stores the ID of our `AuthnRequest` until the
+/// IdP responds.
+///
+/// The response is POSTed to us from the IdP's site. Browsers don't send
+/// `SameSite` cookies with such a request, so this cookie can't be a private
+/// cookie (Rocket makes those `SameSite=Strict`). This is fine, since the
+/// request ID is not secret: it is only used to check that the response
+/// belongs to a login started in the same browser.
+const REQUEST_COOKIE_NAME: &str = "saml_login";
+
+/// How many seconds the clocks of the IdP and this server may differ when
+/// checking the validity period of an assertion.
+const ALLOWED_CLOCK_SKEW: i64 = 60;
+
+const NS_PROTOCOL: &str = "urn:oasis:names:tc:SAML:2.0:protocol";
+const NS_ASSERTION: &str = "urn:oasis:names:tc:SAML:2.0:assertion";
+const NS_DSIG: &str = "http://www.w3.org/2000/09/xmldsig#";
+
+const BINDING_HTTP_POST: &str = "urn:oasis:names:tc:SAML:2.0:bindings:HTTP-POST";
+const STATUS_SUCCESS: &str = "urn:oasis:names:tc:SAML:2.0:status:Success";
+const CONFIRMATION_BEARER: &str = "urn:oasis:names:tc:SAML:2.0:cm:bearer";
+
+/// Configuration of the SAML provider.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The name of this provider shown on the login page, e.g. `Uni-SSO`.
+    pub display_name: String,
+
+    /// The entity ID of luten as service provider, usually the URL of the
+    /// metadata, e.g. `https://luten.uni-osnabrueck.de/login/saml/metadata`.
+    pub entity_id: String,
+
+    /// The absolute URL of `/login/saml/callback`.
+    pub acs_url: String,
+
+    /// The entity ID of the IdP, e.g.
+    /// `https://sso.uni-osnabrueck.de/idp/shibboleth`.
+    pub idp_entity_id: String,
+
+    /// The URL of the IdP's single sign-on service supporting the
+    /// HTTP-Redirect binding, e.g.
+    /// `https://sso.uni-osnabrueck.de/idp/profile/SAML2/Redirect/SSO`.
+    pub idp_sso_url: String,
+
+    /// Path to the IdP's signing certificate (PEM).
+    pub idp_certificate: String,
+
+    /// The `xmlsec1` binary used to verify signatures.
+    #[serde(default = "default_xmlsec1")]
+    pub xmlsec1: String,
+
+    /// The names of the SAML attributes we are interested in.
+    #[serde(default)]
+    pub attributes: Attributes,
+
+    /// How to derive the role of a user from the `groups` attribute. If this
+    /// is missing, the IdP doesn't influence the roles of users.
+    pub roles: Option<RoleMapping>,
+
+    /// If this is `true`, existing users (who are not admins and have no
+    /// internal password) are linked to the IdP on their first login with
+    /// the same username. Otherwise, a username which is already taken is
+    /// refused.
+    #[serde(default)]
+    pub link_existing_users: bool,
+}
+
+fn default_xmlsec1() -> String {
+    "xmlsec1".into()
+}
+
+/// Names of the SAML attributes which contain information about the user.
+/// The defaults are the URNs of the usual eduPerson/LDAP attributes.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Attributes {
+    /// The username in luten (default: `uid`). Users are identified by this
+    /// attribute, so it has to be unique and must not change.
+    pub username: String,
+
+    /// The user's real name (default: `displayName`).
+    pub name: String,
+
+    /// The user's email address (default: `mail`).
+    pub email: String,
+
+    /// The groups the user is a member of (default: `isMemberOf`).
+    pub groups: String,
+}
+
+impl Default for Attributes {
+    fn default() -> Self {
+        Self {
+            username: "urn:oid:0.9.2342.19200300.100.1.1".into(),
+            name: "urn:oid:2.16.840.1.113730.3.1.241".into(),
+            email: "urn:oid:0.9.2342.19200300.100.1.3".into(),
+            groups: "urn:oid:1.3.6.1.4.1.5923.1.5.1.1".into(),
+        }
+    }
+}
+
+
+/// Authenticating users via a SAML 2.0 identity provider.
+pub struct Provider {
+    config: Config,
+}
+
+impl Provider {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Verifies the given (decoded) SAML response and returns the attributes
+    /// of its assertion.
+    fn verify_response(&self, xml: &str, request_id: &str) -> Result<HashMap<String, Vec<String>>> {
+        // We don't want anything to do with DTDs (think of XXE or billion
+        // laughs). SAML messages never contain one.
+        if xml.to_lowercase().contains("<!doctype") {
+            return login_failed("SAML response contains a DTD");
+        }
+
+        let response = Element::from_reader(xml.as_bytes())
+            .or_else(|_| login_failed("SAML response is not valid XML"))?;
+        if !has_tag(&response, NS_PROTOCOL, "Response")
+            || count_descendants(&response, NS_PROTOCOL, "Response") != 1
+        {
+            return login_failed("root element is not the only SAML response");
+        }
+
+        // The IdP has to answer our request...
+        if response.get_attr("InResponseTo") != Some(request_id) {
+            return login_failed("SAML response doesn't answer our request");
+        }
+        if let Some(destination) = response.get_attr("Destination") {
+            if destination != self.config.acs_url {
+                return login_failed("SAML response is meant for someone else");
+            }
+        }
+
+        // ... and it has to be successful. This is not the case if, for
+        // example, the user cancelled the login.
+        let status = child(&response, NS_PROTOCOL, "Status")
+            .and_then(|status| child(status, NS_PROTOCOL, "StatusCode"))
+            .and_then(|code| code.get_attr("Value"));
+        if status != Some(STATUS_SUCCESS) {
+            return login_failed(&format!("SAML response has status {:?}", status));
+        }
+
+        // We only accept exactly one assertion, which has to be a direct
+        // child of the response. Together with verifying a signature at a
+        // fixed position, this protects against signature wrapping attacks.
+        if count_descendants(&response, NS_ASSERTION, "Assertion") != 1
+            || count_descendants(&response, NS_ASSERTION, "EncryptedAssertion") != 0
+        {
+            return login_failed("SAML response doesn't contain exactly one plain assertion");
+        }
+        let assertion = match child(&response, NS_ASSERTION, "Assertion") {
+            Some(assertion) => assertion,
+            None => return login_failed("assertion is not a child of the SAML response"),
+        };
+
+        // Either the assertion or the whole response has to be signed by the
+        // IdP. We prefer the signature of the assertion if there are both.
+        let signed = if child(assertion, NS_DSIG, "Signature").is_some() {
+            ("Assertion", NS_ASSERTION, assertion)
+        } else if child(&response, NS_DSIG, "Signature").is_some() {
+            ("Response", NS_PROTOCOL, &response)
+        } else {
+            return login_failed("SAML response is not signed");
+        };
+        self.verify_signature(xml, &response, signed.0, signed.1, signed.2)?;
+
+        // Now we know the assertion is genuine. Check if it's meant for us
+        // and still valid.
+        let issuer = child(assertion, NS_ASSERTION, "Issuer").map(|issuer| issuer.text().trim());
+        if issuer != Some(self.config.idp_entity_id.as_str()) {
+            return login_failed("assertion was issued by someone else");
+        }
+
+        let now = Utc::now();
+        let skew = Duration::seconds(ALLOWED_CLOCK_SKEW);
+
+        // There has to be a bearer confirmation for our request.
+        let confirmed = child(assertion, NS_ASSERTION, "Subject")
+            .map(|subject| {
+                children(subject, NS_ASSERTION, "SubjectConfirmation")
+                    .into_iter()
+                    .filter(|conf| conf.get_attr("Method") == Some(CONFIRMATION_BEARER))
+                    .filter_map(|conf| child(conf, NS_ASSERTION, "SubjectConfirmationData"))
+                    .any(|data| {
+                        data.get_attr("Recipient") == Some(self.config.acs_url.as_str())
+                            && data.get_attr("InResponseTo") == Some(request_id)
+                            && parse_time(data.get_attr("NotOnOrAfter"))
+                                .map_or(false, |t| now < t + skew)
+                    })
+            })
+            .unwrap_or(false);
+        if !confirmed {
+            return login_failed("assertion has no valid subject confirmation");
+        }
+
+        // The web browser SSO profile requires an audience restriction, so
+        // the conditions have to be present.
+        let conditions = match child(assertion, NS_ASSERTION, "Conditions") {
+            Some(conditions) => conditions,
+            None => return login_failed("assertion has no conditions"),
+        };
+        if let Some(not_before) = conditions.get_attr("NotBefore") {
+            if parse_time(Some(not_before)).map_or(true, |t| now + skew < t) {
+                return login_failed("assertion is not valid yet");
+            }
+        }
+        if let Some(not_on_or_after) = conditions.get_attr("NotOnOrAfter") {
+            if parse_time(Some(not_on_or_after)).map_or(true, |t| now >= t + skew) {
+                return login_failed("assertion is expired");
+            }
+        }
+
+        // There has to be an audience restriction and every one of them
+        // has to include us.
+        let restrictions = children(conditions, NS_ASSERTION, "AudienceRestriction");
+        let audience_ok = !restrictions.is_empty() && restrictions.into_iter()
+            .all(|restriction| {
+                children(restriction, NS_ASSERTION, "Audience")
+                    .into_iter()
+                    .any(|audience| audience.text().trim() == self.config.entity_id)
+            });
+        if !audience_ok {
+            return login_failed("assertion is meant for someone else");
+        }
+
+        // Finally collect all attributes.
+        let mut attributes = HashMap::new();
+        for statement in children(assertion, NS_ASSERTION, "AttributeStatement") {
+            for attr in children(statement, NS_ASSERTION, "Attribute") {
+                if let Some(name) = attr.get_attr("Name") {
+                    attributes.entry(name.to_owned())
+                        .or_insert_with(Vec::new)
+                        .extend(
+                            children(attr, NS_ASSERTION, "AttributeValue")
+                                .into_iter()
+                                .map(|value| value.text().trim().to_owned())
+                        );
+                }
+            }
+        }
+
+        Ok(attributes)
+    }
+
+    /// Verifies the signature which is a direct child of the given element
+    /// (`tag` in namespace `ns`) with `xmlsec1`. `root` is the whole
+    /// response.
+    fn verify_signature(
+        &self,
+        xml: &str,
+        root: &Element,
+        tag: &str,
+        ns: &str,
+        signed: &Element,
+    ) -> Result<()> {
+        // `xmlsec1` verifies the first signature at the path given below,
+        // we check the same one.
+        let signatures = children(signed, NS_DSIG, "Signature");
+        if signatures.len() != 1 {
+            return login_failed("signed element doesn't have exactly one signature");
+        }
+
+        // The signature has to reference the element it is in. Otherwise it
+        // could sign some other part of the document. `xmlsec1` resolves the
+        // reference by ID, so no other element may have the same ID: then
+        // the element it verifies is the one we read the attributes from.
+        let id = match signed.get_attr("ID") {
+            Some(id) => id,
+            None => return login_failed("signed element has no ID"),
+        };
+        if count_with_id(root, id) != 1 {
+            return login_failed("ID of signed element is not unique");
+        }
+        let expected_uri = format!("#{}", id);
+        let references = child(signatures[0], NS_DSIG, "SignedInfo")
+            .map(|info| children(info, NS_DSIG, "Reference"))
+            .unwrap_or_default();
+        let reference_ok = references.len() == 1
+            && references[0].get_attr("URI") == Some(expected_uri.as_str());
+        if !reference_ok {
+            return login_failed("signature doesn't reference the signed element");
+        }
+
+        // `xmlsec1` needs a file.
+        let path = env::temp_dir().join(format!("luten-saml-{}.xml", random_id()?));
+        File::create(&path)
+            .and_then(|mut f| f.write_all(xml.as_bytes()))
+            .chain_err(|| "failed to write SAML response to temporary file")?;
+
+        // Tell `xmlsec1` exactly which signature to verify.
+        let mut xpath = format!("/{}", xpath_step("Response", NS_PROTOCOL));
+        if tag != "Response" {
+            xpath += &format!("/{}", xpath_step(tag, ns));
+        }
+        xpath += &format!("/{}", xpath_step("Signature", NS_DSIG));
+
+        // Keys (or certificates) in the `KeyInfo` of the signature are
+        // ignored: only key names are read, which can only refer to the
+        // IdP's certificate we load. Otherwise, anyone could sign a response
+        // with their own key and put it into the signature.
+        let output = Command::new(&self.config.xmlsec1)
+            .arg("--verify")
+            .arg("--enabled-key-data").arg("key-name")
+            .arg("--pubkey-cert-pem").arg(&self.config.idp_certificate)
+            .arg("--id-attr:ID").arg(format!("{}:Response", NS_PROTOCOL))
+            .arg("--id-attr:ID").arg(format!("{}:Assertion", NS_ASSERTION))
+            .arg("--enabled-reference-uris").arg("same-doc")
+            .arg("--node-xpath").arg(xpath)
+            .arg(&path)
+            .output();
+        let _ = fs::remove_file(&path);
+
+        let output = output.chain_err(|| format!("failed to run '{}'", self.config.xmlsec1))?;
+        if !output.status.success() {
+            return login_failed(&format!(
+                "invalid signature: {}",
+                String::from_utf8_lossy(&output.stderr).trim(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl login::Provider for Provider {
+    fn name(&self, _: Locale) -> String {
+        self.config.display_name.clone()
+    }
+
+    fn as_redirect(&self) -> Option<&RedirectProvider> {
+        Some(self)
+    }
+}
+
+impl RedirectProvider for Provider {
+    fn start(&self, cookies: &mut Cookies) -> Result<String> {
+        let id = random_id()?;
+        let request = format!(
+            "<samlp:AuthnRequest xmlns:samlp=\"{}\" xmlns:saml=\"{}\" ID=\"{}\" Version=\"2.0\" \
+                IssueInstant=\"{}\" Destination=\"{}\" AssertionConsumerServiceURL=\"{}\" \
+                ProtocolBinding=\"{}\">\
+                <saml:Issuer>{}</saml:Issuer>\
+            </samlp:AuthnRequest>",
+            NS_PROTOCOL,
+            NS_ASSERTION,
+            id,
+            Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+            escape_xml(&self.config.idp_sso_url),
+            escape_xml(&self.config.acs_url),
+            BINDING_HTTP_POST,
+            escape_xml(&self.config.entity_id),
+        );
+
+        // HTTP-Redirect binding: the request is deflated and base64 encoded.
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::Default);
+        encoder.write_all(request.as_bytes())
+            .chain_err(|| "failed to deflate SAML request")?;
+        let deflated = encoder.finish()
+            .chain_err(|| "failed to deflate SAML request")?;
+
+        let url = Url::parse_with_params(
+            &self.config.idp_sso_url,
+            &[("SAMLRequest", base64::encode(&deflated))],
+        ).chain_err(|| "invalid IdP single sign-on URL")?;
+
+        let cookie = Cookie::build(REQUEST_COOKIE_NAME, id)
+            .path("/login")
+            .http_only(true)
+            .finish();
+        cookies.add(cookie);
+
+        Ok(url.into_string())
+    }
+
+    fn finish(
+        &self,
+        params: &HashMap<String, String>,
+        cookies: &mut Cookies,
+        db: &Db,
+    ) -> Result<User> {
+        // A request ID can only be used once.
+        let request_id = cookies.get(REQUEST_COOKIE_NAME)
+            .map(|cookie| cookie.value().to_owned());
+        cookies.remove(Cookie::build(REQUEST_COOKIE_NAME, "").path("/login").finish());
+
+        let request_id = match request_id {
+            Some(id) => id,
+            None => return login_failed("no SAML login in progress"),
+        };
+
+        // Base64 in form data may be wrapped.
+        let encoded: String = match params.get("SAMLResponse") {
+            Some(encoded) => encoded.chars().filter(|c| !c.is_whitespace()).collect(),
+            None => return login_failed("no SAML response in request"),
+        };
+        let xml = base64::decode(&encoded)
+            .ok()
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+        let xml = match xml {
+            Some(xml) => xml,
+            None => return login_failed("SAML response is not valid base64 encoded UTF-8"),
+        };
+
+        let mut attributes = self.verify_response(&xml, &request_id)?;
+
+        let names = &self.config.attributes;
+        let username = match attributes.remove(&names.username).and_then(|mut v| v.pop()) {
+            Some(username) => username,
+            None => bail!("SAML assertion doesn't contain the attribute '{}'", names.username),
+        };
+        let profile = ExternalProfile {
+            name: attributes.remove(&names.name).and_then(|mut v| v.pop()),
+            email: attributes.remove(&names.email).and_then(|mut v| v.pop()),
+            groups: attributes.remove(&names.groups).unwrap_or_default(),
+            ..ExternalProfile::default()
+        };
+
+        let account = ExternalAccount { provider: PROVIDER_ID, id: &username };
+        let link = if self.config.link_existing_users {
+            LinkExisting::NonAdmins
+        } else {
+            LinkExisting::Never
+        };
+        login::sync_user(account, &username, link, profile, self.config.roles.as_ref(), db)
+    }
+}
+
+/// Returns the metadata of luten as service provider, which has to be
+/// registered at the IdP.
+pub fn metadata(config: &Config) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <md:EntityDescriptor xmlns:md=\"urn:oasis:names:tc:SAML:2.0:metadata\" entityID=\"{}\">\n  \
+            <md:SPSSODescriptor AuthnRequestsSigned=\"false\" WantAssertionsSigned=\"true\" \
+                protocolSupportEnumeration=\"{}\">\n    \
+                <md:AssertionConsumerService Binding=\"{}\" Location=\"{}\" index=\"0\"/>\n  \
+            </md:SPSSODescriptor>\n\
+        </md:EntityDescriptor>\n",
+        escape_xml(&config.entity_id),
+        NS_PROTOCOL,
+        BINDING_HTTP_POST,
+        escape_xml(&config.acs_url),
+    )
+}
+
+
+/// Returns an error that is shown to the user as `ExternalLoginFailed`. The
+/// given reason is kept in the error chain.
+fn login_failed<T>(reason: &str) -> Result<T> {
+    Err(Error::from(reason))
+        .chain_err(|| ErrorKind::LoginError(LoginError::ExternalLoginFailed))
+}
+
+/// Returns a random ID for a SAML request. IDs must not start with a digit,
+/// hence the underscore.
+fn random_id() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    let mut rng = rand::os::OsRng::new()
+        .chain_err(|| "Unable to use system RNG")?;
+    rng.fill_bytes(&mut bytes);
+
+    Ok(format!("_{}", hex::encode(&bytes)))
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Parses an `xs:dateTime` as used in SAML.
+fn parse_time(s: Option<&str>) -> Option<DateTime<Utc>> {
+    s.and_then(|s| s.parse().ok())
+}
+
+fn has_tag(elem: &Element, ns: &str, name: &str) -> bool {
+    elem.tag().ns() == Some(ns) && elem.tag().name() == name
+}
+
+/// Returns the first direct child with the given tag.
+fn child<'a>(elem: &'a Element, ns: &str, name: &str) -> Option<&'a Element> {
+    elem.children().find(|c| has_tag(c, ns, name))
+}
+
+/// Returns all direct children with the given tag.
+fn children<'a>(elem: &'a Element, ns: &str, name: &str) -> Vec<&'a Element> {
+    elem.children().filter(|c| has_tag(c, ns, name)).collect()
+}
+
+/// Counts all elements with the given tag in the subtree of `elem`
+/// (including `elem` itself).
+fn count_descendants(elem: &Element, ns: &str, name: &str) -> usize {
+    let own = if has_tag(elem, ns, name) { 1 } else { 0 };
+    own + elem.children().map(|c| count_descendants(c, ns, name)).sum::<usize>()
+}
+
+/// Counts all elements with the given `ID` attribute in the subtree of `elem`
+/// (including `elem` itself).
+fn count_with_id(elem: &Element, id: &str) -> usize {
+    let own = if elem.get_attr("ID") == Some(id) { 1 } else { 0 };
+    own + elem.children().map(|c| count_with_id(c, id)).sum::<usize>()
+}
+
+/// An XPath step selecting the element with the given tag.
+fn xpath_step(name: &str, ns: &str) -> String {
+    format!("*[local-name()='{}' and namespace-uri()='{}']", name, ns)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REQUEST_ID: &str = "_0a8d2cbf1e5d4a8f9c3b7e6d5a4f3e2d";
+    const USERNAME_ATTR: &str = "urn:oid:0.9.2342.19200300.100.1.1";
+
+    // `response.xml` is a response with an assertion signed by the key of
+    // `idp.crt`. `response_other_key.xml` is the same response, but signed
+    // by the key of `other.crt` (which is also included in the signature).
+    const RESPONSE: &str = include_str!("testdata/saml/response.xml");
+    const RESPONSE_OTHER_KEY: &str = include_str!("testdata/saml/response_other_key.xml");
+
+    /// Returns a provider trusting the given certificate from `testdata`, or
+    /// `None` if `xmlsec1` is not installed.
+    fn provider(certificate: &str) -> Option<Provider> {
+        if Command::new("xmlsec1").arg("--version").output().is_err() {
+            println!("xmlsec1 is not installed, skipping test");
+            return None;
+        }
+
+        Some(Provider::new(Config {
+            display_name: "Shibboleth".into(),
+            entity_id: "https://luten.example.org/login/saml/metadata".into(),
+            acs_url: "https://luten.example.org/login/saml/callback".into(),
+            idp_entity_id: "https://idp.example.org/idp/shibboleth".into(),
+            idp_sso_url: "https://idp.example.org/idp/profile/SAML2/Redirect/SSO".into(),
+            idp_certificate: format!(
+                "{}/src/login/testdata/saml/{}",
+                env!("CARGO_MANIFEST_DIR"),
+                certificate,
+            ),
+            xmlsec1: default_xmlsec1(),
+            attributes: Attributes::default(),
+            roles: None,
+            link_existing_users: false,
+        }))
+    }
+
+    fn assert_rejected(res: Result<HashMap<String, Vec<String>>>) {
+        match res {
+            Err(Error(ErrorKind::LoginError(LoginError::ExternalLoginFailed), _)) => {}
+            other => panic!("SAML response was not rejected: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn valid_response() {
+        if let Some(provider) = provider("idp.crt") {
+            let attributes = provider.verify_response(RESPONSE, REQUEST_ID).unwrap();
+            assert_eq!(attributes[USERNAME_ATTR], vec!["alice"]);
+        }
+    }
+
+    #[test]
+    fn tampered_response() {
+        if let Some(provider) = provider("idp.crt") {
+            let tampered = RESPONSE.replace(">alice<", ">admin<");
+            assert_rejected(provider.verify_response(&tampered, REQUEST_ID));
+        }
+    }
+
+    #[test]
+    fn unsigned_response() {
+        if let Some(provider) = provider("idp.crt") {
+            let start = RESPONSE.find("<ds:Signature ").unwrap();
+            let end = RESPONSE.find("</ds:Signature>").unwrap() + "</ds:Signature>".len();
+            let unsigned = format!("{}{}", &RESPONSE[..start], &RESPONSE[end..]);
+
+            assert_rejected(provider.verify_response(&unsigned, REQUEST_ID));
+        }
+    }
+
+    #[test]
+    fn signed_with_other_key() {
+        // The certificate in the signature must not be used.
+        if let Some(provider) = provider("idp.crt") {
+            assert_rejected(provider.verify_response(RESPONSE_OTHER_KEY, REQUEST_ID));
+        }
+        if let Some(provider) = provider("other.crt") {
+            assert_rejected(provider.verify_response(RESPONSE, REQUEST_ID));
+        }
+    }
+
+    #[test]
+    fn duplicate_id() {
+        // A second element with the ID of the signed assertion could be
+        // verified instead of the assertion we read.
+        if let Some(provider) = provider("idp.crt") {
+            let duplicated = RESPONSE.replace(
+                "<samlp:Status>",
+                "<samlp:Extensions><x:Fake xmlns:x=\"urn:x\" ID=\"_a7c1e0b2d3f4\"></x:Fake>\
+                    </samlp:Extensions><samlp:Status>",
+            );
+            assert_rejected(provider.verify_response(&duplicated, REQUEST_ID));
+        }
+    }
+
+    #[test]
+    fn other_request() {
+        if let Some(provider) = provider("idp.crt") {
+            assert_rejected(provider.verify_response(RESPONSE, "_other"));
+        }
+    }
+}
@@ -0,0 +1,559 @@
+//! TOTP two-factor authentification (RFC 6238).
+//!
+//! Users can protect their account with a second factor: after logging in
+//! with a login provider, they have to enter a six digit code generated by an
+//! authenticator app on their phone. If they lose their phone, they can use
+//! one of their recovery codes instead (each code works only once).
+//!
+//! The second factor is optional, but can be enforced for certain roles in
+//! the `[two_factor]` section of the configuration file. Users with one of
+//! those roles who haven't set up a second factor yet have to do so during
+//! their next login.
+
+use std::cmp;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use diesel;
+use diesel::prelude::*;
+use hex;
+use rand::{self, Rng};
+use ring::{digest, hmac};
+use rocket::http::{Cookie, Cookies, SameSite};
+use url::percent_encoding::{utf8_percent_encode, DEFAULT_ENCODE_SET};
+
+use config;
+use course::Course;
+use db::Db;
+use db::schema::{recovery_codes, totp_secrets};
+use errors::*;
+use user::{Role, User};
+
+
+/// Length of one time step in seconds.
+const TIME_STEP: i64 = 30;
+
+/// Number of digits of a code.
+const DIGITS: usize = 6;
+
+/// Number of time steps a code may be off (in both directions). This accounts
+/// for clock drift and for users who need a few seconds to type the code.
+const ALLOWED_STEP_DRIFT: i64 = 1;
+
+/// Length of a shared secret in bytes (160 bit, as recommended by RFC 4226).
+const SECRET_LEN: usize = 20;
+
+/// Number of random bytes of a recovery code.
+const RECOVERY_CODE_LEN: usize = 5;
+
+/// The name of the private cookie which remembers a user between the first
+/// login step and entering their code.
+const PENDING_COOKIE_NAME: &str = "two_factor_pending";
+
+/// Two-factor settings from the configuration file.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    /// Users with one of these roles (`admin`, `tutor` or `student`) in any
+    /// of their courses have to use a second factor.
+    #[serde(default)]
+    pub required_for: Vec<Role>,
+}
+
+/// Returns whether users with the given role have to use a second factor.
+pub fn is_required_for_role(role: Role) -> bool {
+    config::FILE.two_factor.required_for.contains(&role)
+}
+
+/// Returns whether the given user has to use a second factor. That's the
+/// case if their global role or their role in any of their courses requires
+/// it; the login happens before a course is chosen.
+pub fn is_required_for(user: &User, db: &Db) -> Result<bool> {
+    if is_required_for_role(user.role()) {
+        return Ok(true);
+    }
+
+    Ok(Course::load_all_of_user(user, db)?
+        .iter()
+        .any(|&(_, role)| is_required_for_role(role)))
+}
+
+/// Returns whether the given user has to enter a code after logging in, or
+/// has to set up a second factor first.
+pub fn is_needed_for(user: &User, db: &Db) -> Result<bool> {
+    Ok(is_required_for(user, db)? || TotpSecret::load(user, db)?.is_some())
+}
+
+
+/// The second factor of a user.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable, Associations, Insertable)]
+#[table_name = "totp_secrets"]
+#[primary_key(user_id)]
+pub struct TotpSecret {
+    user_id: i64,
+    secret: Vec<u8>,
+    last_used_step: i64,
+    failed_attempts: i32,
+    locked_until: Option<DateTime<Utc>>,
+}
+
+/// The result of `TotpSecret::check()`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CodeCheck {
+    /// The TOTP code or recovery code was correct.
+    Valid,
+    Invalid,
+
+    /// Too many wrong codes were entered recently; TOTP codes are rejected
+    /// until the lock expires. Recovery codes are still accepted.
+    Locked,
+}
+
+impl TotpSecret {
+    /// Loads the second factor of the given user. Returns `None` if the user
+    /// hasn't set one up.
+    pub fn load(user: &User, db: &Db) -> Result<Option<Self>> {
+        totp_secrets::table
+            .find(user.id())
+            .first::<Self>(&*db.conn()?)
+            .optional()?
+            .make_ok()
+    }
+
+    /// Stores the given secret as second factor of the given user. Call this
+    /// only after the user entered a correct code for this secret, to make
+    /// sure they stored it in their app.
+    ///
+    /// An existing second factor and all recovery codes are removed.
+    ///
+    /// `used_step` is the time step of the code the user entered, so that the
+    /// code cannot be used again.
+    pub fn create(user: &User, secret: Vec<u8>, used_step: i64, db: &Db) -> Result<Self> {
+        let new = Self {
+            user_id: user.id(),
+            secret,
+            last_used_step: used_step,
+            failed_attempts: 0,
+            locked_until: None,
+        };
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user.id())))
+                .execute(conn)?;
+            diesel::delete(totp_secrets::table.find(user.id()))
+                .execute(conn)?;
+
+            diesel::insert(&new)
+                .into(totp_secrets::table)
+                .get_result::<Self>(conn)
+                .chain_err(|| "failed to insert TOTP secret")
+        })
+    }
+
+    /// Removes the second factor and all recovery codes of the given user.
+    /// Returns `false` if the user didn't have a second factor.
+    pub fn remove_for(user: &User, db: &Db) -> Result<bool> {
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user.id())))
+                .execute(conn)?;
+            diesel::delete(totp_secrets::table.find(user.id()))
+                .execute(conn)
+                .map(|deleted| deleted == 1)
+                .chain_err(|| "failed to delete TOTP secret")
+        })
+    }
+
+    /// Returns whether TOTP codes are currently rejected because of too many
+    /// wrong codes.
+    pub fn is_locked(&self) -> bool {
+        self.locked_until.map_or(false, |until| until > Utc::now())
+    }
+
+    /// Checks the code the user entered, which is either a TOTP code or a
+    /// recovery code. A recovery code is removed once it was used.
+    ///
+    /// Recovery codes are checked even while TOTP codes are locked, so that
+    /// nobody can lock a user out by entering wrong codes. They are too long
+    /// to be guessed anyway.
+    pub fn check(&mut self, input: &str, db: &Db) -> Result<CodeCheck> {
+        let input = normalize_code(input);
+        let is_totp_code = input.len() == DIGITS;
+
+        if is_totp_code && self.is_locked() {
+            return Ok(CodeCheck::Locked);
+        }
+
+        let valid = if is_totp_code {
+            match verify_code(&self.secret, &input, self.last_used_step) {
+                Some(step) => {
+                    self.last_used_step = step;
+                    true
+                }
+                None => false,
+            }
+        } else {
+            let hash = hash_recovery_code(&input);
+            let deleted = diesel::delete(
+                recovery_codes::table
+                    .filter(recovery_codes::user_id.eq(self.user_id))
+                    .filter(recovery_codes::hash.eq(&hash))
+            ).execute(&*db.conn()?)?;
+
+            deleted == 1
+        };
+
+        if valid {
+            self.failed_attempts = 0;
+            self.locked_until = None;
+        } else {
+            self.failed_attempts += 1;
+            self.locked_until = lock_duration(self.failed_attempts)
+                .map(|secs| Utc::now() + Duration::seconds(secs));
+        }
+
+        diesel::update(totp_secrets::table.find(self.user_id))
+            .set((
+                totp_secrets::last_used_step.eq(self.last_used_step),
+                totp_secrets::failed_attempts.eq(self.failed_attempts),
+                totp_secrets::locked_until.eq(self.locked_until),
+            ))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to update TOTP secret")?;
+
+        Ok(if valid { CodeCheck::Valid } else { CodeCheck::Invalid })
+    }
+}
+
+
+/// Returns for how many seconds TOTP codes are rejected after the given
+/// number of wrong codes in a row, or `None` if they aren't rejected at all.
+fn lock_duration(failed_attempts: i32) -> Option<i64> {
+    if failed_attempts < config::TOTP_MAX_FAILED_ATTEMPTS {
+        return None;
+    }
+
+    // Limit the shift to avoid an overflow; the result is capped anyway.
+    let doublings = cmp::min(failed_attempts - config::TOTP_MAX_FAILED_ATTEMPTS, 16) as u32;
+    Some(cmp::min(config::TOTP_LOCK_DURATION << doublings, config::TOTP_MAX_LOCK_DURATION))
+}
+
+/// Removes characters users might enter in addition to the code (e.g.
+/// "123 456" or "ABCDE-12345").
+pub fn normalize_code(input: &str) -> String {
+    input.chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Generates a new random shared secret.
+pub fn generate_secret() -> Result<Vec<u8>> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    let mut rng = rand::os::OsRng::new()
+        .chain_err(|| "Unable to use system RNG")?;
+    rng.fill_bytes(&mut secret);
+
+    Ok(secret)
+}
+
+/// Encodes a secret as base32 (RFC 4648, without padding). This is the format
+/// authenticator apps expect if the secret is typed in manually.
+pub fn encode_secret(secret: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut out = String::new();
+    for chunk in secret.chunks(5) {
+        // Pad the chunk to 40 bit and output as many 5 bit groups as are
+        // needed for the bytes of the chunk.
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = buf.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+
+        let groups = (chunk.len() * 8 + 4) / 5;
+        for i in 0..groups {
+            let index = (bits >> (35 - i * 5)) & 0x1f;
+            out.push(ALPHABET[index as usize] as char);
+        }
+    }
+
+    out
+}
+
+/// Returns the `otpauth://` URI for the given secret, which is shown as QR
+/// code during the setup.
+pub fn provisioning_uri(secret: &[u8], username: &str) -> String {
+    let issuer = utf8_percent_encode(config::WEBSITE_TITLE, DEFAULT_ENCODE_SET).to_string();
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&digits={}&period={}",
+        issuer,
+        utf8_percent_encode(username, DEFAULT_ENCODE_SET),
+        encode_secret(secret),
+        issuer,
+        DIGITS,
+        TIME_STEP,
+    )
+}
+
+/// Returns the code for the given time step (RFC 4226, section 5.3).
+fn code_at(secret: &[u8], step: i64) -> String {
+    let mut counter = [0u8; 8];
+    for (i, byte) in counter.iter_mut().enumerate() {
+        *byte = (step >> (56 - i * 8)) as u8;
+    }
+
+    let key = hmac::SigningKey::new(&digest::SHA1, secret);
+    let mac = hmac::sign(&key, &counter);
+    let mac = mac.as_ref();
+
+    // Dynamic truncation
+    let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+    let value = ((mac[offset] as u32 & 0x7f) << 24)
+        | ((mac[offset + 1] as u32) << 16)
+        | ((mac[offset + 2] as u32) << 8)
+        | (mac[offset + 3] as u32);
+
+    format!("{:01$}", value % 10u32.pow(DIGITS as u32), DIGITS)
+}
+
+/// Checks the given code against the secret. If it is valid for a time step
+/// later than `after`, that time step is returned.
+pub fn verify_code(secret: &[u8], code: &str, after: i64) -> Option<i64> {
+    verify_code_at(secret, code, after, Utc::now().timestamp())
+}
+
+/// Like `verify_code()`, but for the given Unix timestamp instead of now.
+fn verify_code_at(secret: &[u8], code: &str, after: i64, now: i64) -> Option<i64> {
+    let current = now / TIME_STEP;
+
+    (current - ALLOWED_STEP_DRIFT..current + ALLOWED_STEP_DRIFT + 1)
+        .filter(|&step| step > after)
+        .find(|&step| code_at(secret, step) == code)
+}
+
+/// Generates new recovery codes for the given user and returns them. All old
+/// recovery codes of the user are removed.
+pub fn generate_recovery_codes(user: &User, db: &Db) -> Result<Vec<String>> {
+    #[derive(Insertable)]
+    #[table_name = "recovery_codes"]
+    struct NewRecoveryCode {
+        user_id: i64,
+        hash: Vec<u8>,
+    }
+
+    let mut rng = rand::os::OsRng::new()
+        .chain_err(|| "Unable to use system RNG")?;
+    let codes = (0..config::TOTP_RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; RECOVERY_CODE_LEN];
+            rng.fill_bytes(&mut bytes);
+            hex::encode(&bytes)
+        })
+        .collect::<Vec<_>>();
+
+    let new = codes.iter()
+        .map(|code| NewRecoveryCode {
+            user_id: user.id(),
+            hash: hash_recovery_code(code),
+        })
+        .collect::<Vec<_>>();
+
+    let conn = &*db.conn()?;
+    conn.transaction::<_, Error, _>(|| {
+        diesel::delete(recovery_codes::table.filter(recovery_codes::user_id.eq(user.id())))
+            .execute(conn)?;
+        diesel::insert(&new)
+            .into(recovery_codes::table)
+            .execute(conn)
+            .chain_err(|| "failed to insert recovery codes")
+    })?;
+
+    // Split the codes in two halves to make them easier to read.
+    codes.into_iter()
+        .map(|code| format!("{}-{}", &code[..5], &code[5..]))
+        .collect::<Vec<_>>()
+        .make_ok()
+}
+
+/// Returns the number of unused recovery codes of the given user.
+pub fn count_recovery_codes(user: &User, db: &Db) -> Result<u64> {
+    recovery_codes::table
+        .filter(recovery_codes::user_id.eq(user.id()))
+        .count()
+        .get_result::<i64>(&*db.conn()?)
+        .chain_err(|| "failed to count recovery codes")
+        .map(|count| count as u64)
+}
+
+fn hash_recovery_code(code: &str) -> Vec<u8> {
+    digest::digest(&digest::SHA256, code.as_bytes()).as_ref().to_vec()
+}
+
+
+/// A login which passed the first step (the login provider), but still needs
+/// the second factor.
+///
+/// This is stored in a private cookie until the user entered their code.
+#[derive(Debug, Clone)]
+pub struct PendingLogin {
+    pub user_id: i64,
+
+    /// Unix timestamp of the first login step.
+    started: i64,
+
+    /// If the user has to set up a second factor during login, this is the
+    /// secret shown to them.
+    pub setup_secret: Option<Vec<u8>>,
+}
+
+impl PendingLogin {
+    /// Remembers the given user as having passed the first login step.
+    pub fn start(user: &User, cookies: &mut Cookies) {
+        let pending = Self {
+            user_id: user.id(),
+            started: Utc::now().timestamp(),
+            setup_secret: None,
+        };
+        pending.save(cookies);
+    }
+
+    /// Returns the pending login of this browser, if there is one and it
+    /// isn't expired.
+    pub fn load(cookies: &mut Cookies) -> Option<Self> {
+        let value = match cookies.get_private(PENDING_COOKIE_NAME) {
+            Some(cookie) => cookie.value().to_owned(),
+            None => return None,
+        };
+
+        let parts: Vec<_> = value.split(' ').collect();
+        if parts.len() != 3 {
+            return None;
+        }
+        let setup_secret = match parts[2] {
+            "-" => None,
+            encoded => match hex::decode(encoded) {
+                Ok(secret) => Some(secret),
+                Err(_) => return None,
+            },
+        };
+        let pending = match (parts[0].parse(), parts[1].parse()) {
+            (Ok(user_id), Ok(started)) => Self { user_id, started, setup_secret },
+            _ => return None,
+        };
+
+        let age = Utc::now().signed_duration_since(Utc.timestamp(pending.started, 0));
+        if age.num_seconds() > config::TOTP_PENDING_LOGIN_TIMEOUT {
+            return None;
+        }
+
+        Some(pending)
+    }
+
+    /// Stores this pending login (e.g. after a setup secret was added).
+    pub fn save(&self, cookies: &mut Cookies) {
+        let secret = self.setup_secret.as_ref()
+            .map(|s| hex::encode(s))
+            .unwrap_or_else(|| "-".into());
+
+        // The cookie has to be sent after a redirect from an external
+        // identity provider, so it can't be `SameSite=Strict`.
+        let cookie = Cookie::build(
+            PENDING_COOKIE_NAME,
+            format!("{} {} {}", self.user_id, self.started, secret),
+        )
+            .path("/login")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .finish();
+        cookies.add_private(cookie);
+    }
+
+    /// Ends the pending login.
+    pub fn remove(cookies: &mut Cookies) {
+        cookies.remove_private(Cookie::build(PENDING_COOKIE_NAME, "").path("/login").finish());
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The secret of the test vectors in RFC 4226 and RFC 6238.
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn code_at_hotp_vectors() {
+        // RFC 4226, appendix D
+        let codes = [
+            "755224", "287082", "359152", "969429", "338314",
+            "254676", "287922", "162583", "399871", "520489",
+        ];
+        for (step, &code) in codes.iter().enumerate() {
+            assert_eq!(code_at(RFC_SECRET, step as i64), code, "step {}", step);
+        }
+    }
+
+    #[test]
+    fn code_at_totp_vectors() {
+        // RFC 6238, appendix B (SHA-1). The RFC uses eight digits, so only the
+        // last six digits are compared.
+        let codes = [
+            (59, "94287082"),
+            (1111111109, "07081804"),
+            (1111111111, "14050471"),
+            (1234567890, "89005924"),
+            (2000000000, "69279037"),
+            (20000000000, "65353130"),
+        ];
+        for &(time, code) in &codes {
+            assert_eq!(code_at(RFC_SECRET, time / TIME_STEP), &code[2..], "time {}", time);
+        }
+    }
+
+    #[test]
+    fn verify_code_window() {
+        let now = 1111111111;
+        let current = now / TIME_STEP;
+        let verify = |step, after| verify_code_at(RFC_SECRET, &code_at(RFC_SECRET, step), after, now);
+
+        // Codes of the neighbouring time steps are accepted, older or newer
+        // ones are not.
+        assert_eq!(verify(current, 0), Some(current));
+        assert_eq!(verify(current - 1, 0), Some(current - 1));
+        assert_eq!(verify(current + 1, 0), Some(current + 1));
+        assert_eq!(verify(current - 2, 0), None);
+        assert_eq!(verify(current + 2, 0), None);
+        assert_eq!(verify_code_at(RFC_SECRET, "050471", 0, now), Some(current));
+        assert_eq!(verify_code_at(RFC_SECRET, "050472", 0, now), None);
+
+        // Codes can't be used twice.
+        assert_eq!(verify(current, current), None);
+        assert_eq!(verify(current - 1, current), None);
+        assert_eq!(verify(current + 1, current), Some(current + 1));
+    }
+
+    #[test]
+    fn encode_secret_vectors() {
+        // RFC 4648, section 10 (without padding)
+        assert_eq!(encode_secret(b""), "");
+        assert_eq!(encode_secret(b"f"), "MY");
+        assert_eq!(encode_secret(b"fo"), "MZXQ");
+        assert_eq!(encode_secret(b"foo"), "MZXW6");
+        assert_eq!(encode_secret(b"foob"), "MZXW6YQ");
+        assert_eq!(encode_secret(b"fooba"), "MZXW6YTB");
+        assert_eq!(encode_secret(b"foobar"), "MZXW6YTBOI");
+    }
+
+    #[test]
+    fn lock_duration_backs_off() {
+        let max = config::TOTP_MAX_FAILED_ATTEMPTS;
+
+        assert_eq!(lock_duration(0), None);
+        assert_eq!(lock_duration(max - 1), None);
+        assert_eq!(lock_duration(max), Some(config::TOTP_LOCK_DURATION));
+        assert_eq!(lock_duration(max + 1), Some(2 * config::TOTP_LOCK_DURATION));
+        assert_eq!(lock_duration(max + 2), Some(4 * config::TOTP_LOCK_DURATION));
+        assert_eq!(lock_duration(max + 100), Some(config::TOTP_MAX_LOCK_DURATION));
+        assert_eq!(lock_duration(i32::max_value()), Some(config::TOTP_MAX_LOCK_DURATION));
+    }
+}
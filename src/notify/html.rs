@@ -0,0 +1,58 @@
+use maud::{html, Markup};
+
+use dict::{self, Locale};
+use super::inbox::InboxEntry;
+
+
+pub fn inbox(locale: Locale, entries: &[InboxEntry]) -> Markup {
+    let dict = dict::new(locale).notify;
+
+    html! {
+        h1 (dict.inbox_title())
+
+        @if entries.is_empty() {
+            p { i (dict.inbox_empty()) }
+        } @else {
+            table class="c-table" {
+                thead class="c-table__head" {
+                    tr class="c-table__row c-table__row--heading" {
+                        th class="c-table__cell" (dict.inbox_created())
+                        th class="c-table__cell" (dict.inbox_message())
+                        th class="c-table__cell" {}
+                    }
+                }
+                tbody class="c-table__body" {
+                    @for entry in entries {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" {
+                                (entry.created.format("%d.%m.%Y %H:%M"))
+                                @if !entry.read {
+                                    " "
+                                    span class="c-badge c-badge--info" (dict.inbox_new())
+                                }
+                            }
+                            td class="c-table__cell" {
+                                a href=(entry.notification.link()) (entry.notification.text(locale))
+                            }
+                            td class="c-table__cell" {
+                                form action={"/inbox/" (entry.id) "/dismiss"} method="post" {
+                                    input
+                                        type="submit"
+                                        class="c-button c-button--ghost u-xsmall"
+                                        value=(dict.inbox_dismiss());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            form action="/inbox/dismiss_all" method="post" class="u-letter-box--small" {
+                input
+                    type="submit"
+                    class="c-button c-button--error"
+                    value=(dict.inbox_dismiss_all());
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+//! The inbox: notifications stored in the database until the user dismisses
+//! them.
+//!
+//! Unlike Rocket flashes, which are shown exactly once, notifications in the
+//! inbox survive until they are dismissed. The number of unread
+//! notifications is shown in the nav bar.
+
+use chrono::{DateTime, Utc};
+use diesel;
+use diesel::expression::dsl::count_star;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use db::Db;
+use db::schema::notifications;
+use errors::*;
+use user::User;
+use super::{Notification, NotificationKind};
+
+
+/// A notification in the inbox of a user.
+#[derive(Debug, Clone)]
+pub struct InboxEntry {
+    pub id: i64,
+    pub notification: Notification,
+    pub created: DateTime<Utc>,
+    pub read: bool,
+}
+
+/// A row of the `notifications` table.
+#[derive(Queryable)]
+struct Row {
+    id: i64,
+    _user_id: i64,
+    kind: NotificationKind,
+    argument: Option<String>,
+    created: DateTime<Utc>,
+    read: bool,
+}
+
+impl InboxEntry {
+    /// Loads all notifications of the given user, newest first.
+    pub fn load_all_of_user(user: &User, db: &Db) -> Result<Vec<Self>> {
+        let rows = notifications::table
+            .filter(notifications::user_id.eq(user.id()))
+            .order(notifications::created.desc())
+            .load::<Row>(&*db.conn()?)?;
+
+        rows.into_iter()
+            .map(|row| -> Result<Self> {
+                let notification = Notification::from_parts(row.kind, row.argument)
+                    .ok_or_else(|| format!("invalid notification with id {} in database", row.id))?;
+
+                Ok(Self {
+                    id: row.id,
+                    notification,
+                    created: row.created,
+                    read: row.read,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of unread notifications of the given user.
+    pub fn count_unread(user: &User, db: &Db) -> Result<i64> {
+        notifications::table
+            .filter(notifications::user_id.eq(user.id()))
+            .filter(notifications::read.eq(false))
+            .select(count_star())
+            .first(&*db.conn()?)
+            .chain_err(|| "failed to count unread notifications")
+    }
+
+    /// Marks all notifications of the given user as read.
+    pub fn mark_all_read(user: &User, db: &Db) -> Result<()> {
+        diesel::update(
+            notifications::table
+                .filter(notifications::user_id.eq(user.id()))
+                .filter(notifications::read.eq(false))
+        ).set(notifications::read.eq(true))
+            .execute(&*db.conn()?)?;
+
+        Ok(())
+    }
+
+    /// Removes the notification with the given id from the inbox of the given
+    /// user. Returns `false` if the user has no such notification.
+    pub fn dismiss(id: i64, user: &User, db: &Db) -> Result<bool> {
+        diesel::delete(
+            notifications::table
+                .find(id)
+                .filter(notifications::user_id.eq(user.id()))
+        ).execute(&*db.conn()?)
+            .map(|count| count > 0)
+            .chain_err(|| "failed to dismiss notification")
+    }
+
+    /// Removes all notifications from the inbox of the given user.
+    pub fn dismiss_all(user: &User, db: &Db) -> Result<()> {
+        diesel::delete(notifications::table.filter(notifications::user_id.eq(user.id())))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to dismiss notifications")?;
+
+        Ok(())
+    }
+}
+
+/// Adds the given notification to the inbox of all given users.
+pub fn insert_all(users: &[&User], notification: &Notification, conn: &PgConnection) -> Result<()> {
+    #[derive(Insertable)]
+    #[table_name = "notifications"]
+    struct NewEntry<'a> {
+        user_id: i64,
+        kind: NotificationKind,
+        argument: Option<&'a str>,
+    }
+
+    let new_entries = users.iter()
+        .map(|user| NewEntry {
+            user_id: user.id(),
+            kind: notification.kind(),
+            argument: notification.argument(),
+        })
+        .collect::<Vec<_>>();
+
+    if !new_entries.is_empty() {
+        diesel::insert(&new_entries)
+            .into(notifications::table)
+            .execute(conn)
+            .chain_err(|| "failed to insert notifications")?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+// ===========================================================================
+// General parts of all emails
+// ===========================================================================
+unit greeting(name: &str) {
+    De => "Hallo {name},",
+    En => "Hello {name},",
+}
+unit footer(base_url: &str) {
+    De => "Diese E-Mail wurde automatisch von {base_url} versendet. Bitte \
+           antworte nicht darauf.",
+    En => "This email was sent automatically by {base_url}. Please do not \
+           reply to it.",
+}
+
+
+// ===========================================================================
+// Notifications
+// ===========================================================================
+unit preparation_started_subject {
+    De => "Die Terminfindung hat begonnen",
+    En => "The preparation phase has started",
+}
+unit preparation_started_body(base_url: &str) {
+    De => "die Terminfindung für die Testate hat begonnen. Bitte melde dich \
+           unter {base_url}/prep an und gib an, zu welchen Zeiten du kannst.",
+    En => "the preparation phase for the testats has started. Please log in \
+           at {base_url}/prep and tell us which time slots suit you.",
+}
+
+unit schedule_ready_subject {
+    De => "Deine Testattermine stehen fest",
+    En => "Your testat schedule is ready",
+}
+unit schedule_ready_body(base_url: &str) {
+    De => "die Termine für die Testate wurden verteilt. Du findest deinen \
+           Termin unter {base_url}.",
+    En => "the testat time slots have been assigned. You can find yours at \
+           {base_url}.",
+}
+
+unit partner_chosen_subject {
+    De => "Du wurdest als Testatpartner gewählt",
+    En => "You were chosen as testat partner",
+}
+unit partner_chosen_body(by: &str, base_url: &str) {
+    De => "'{by}' möchte die Testate mit dir zusammen machen. Falls du das \
+           auch möchtest, wähle '{by}' unter {base_url}/prep ebenfalls als \
+           Partner aus.",
+    En => "'{by}' wants to do the testats together with you. If you want \
+           that, too, choose '{by}' as your partner at {base_url}/prep.",
+}
+
+unit partner_accepted_subject {
+    De => "Ihr seid Testatpartner",
+    En => "You are testat partners",
+}
+unit partner_accepted_body(by: &str, base_url: &str) {
+    De => "'{by}' hat dich ebenfalls als Partner gewählt. Ihr macht die Testate \
+           also zusammen. Deine Einstellungen findest du unter {base_url}/prep.",
+    En => "'{by}' chose you as partner, too. So you will do the testats \
+           together. You can find your settings at {base_url}/prep.",
+}
+
+
+// ===========================================================================
+// Inbox
+// ===========================================================================
+unit inbox_title {
+    De => "Benachrichtigungen",
+    En => "Notifications",
+}
+unit inbox_empty {
+    De => "Du hast keine Benachrichtigungen.",
+    En => "You don't have any notifications.",
+}
+unit inbox_created {
+    De => "Datum",
+    En => "Date",
+}
+unit inbox_message {
+    De => "Nachricht",
+    En => "Message",
+}
+unit inbox_new {
+    De => "Neu",
+    En => "New",
+}
+unit inbox_dismiss {
+    De => "Entfernen",
+    En => "Dismiss",
+}
+unit inbox_dismiss_all {
+    De => "Alle entfernen",
+    En => "Dismiss all",
+}
+unit flash_dismissed {
+    De => "Die Benachrichtigung wurde entfernt.",
+    En => "The notification was dismissed.",
+}
+unit flash_all_dismissed {
+    De => "Alle Benachrichtigungen wurden entfernt.",
+    En => "All notifications were dismissed.",
+}
+
+unit preparation_started_text {
+    De => "Die Terminfindung hat begonnen. Bitte gib an, zu welchen Zeiten du kannst.",
+    En => "The preparation phase has started. Please tell us which time slots suit you.",
+}
+unit schedule_ready_text {
+    De => "Die Testattermine wurden verteilt.",
+    En => "The testat time slots have been assigned.",
+}
+unit partner_chosen_text(by: &str) {
+    De => "'{by}' hat dich als Testatpartner gewählt.",
+    En => "'{by}' chose you as testat partner.",
+}
+unit partner_accepted_text(by: &str) {
+    De => "'{by}' hat dich ebenfalls als Partner gewählt. Ihr seid Testatpartner.",
+    En => "'{by}' chose you as partner, too. You are testat partners now.",
+}
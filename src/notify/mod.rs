@@ -0,0 +1,341 @@
+//! Notifications about important events, like the start of the preparation
+//! phase. **Has routes.**
+//!
+//! Each notification is shown in the inbox of the user (see `inbox`) and, if
+//! possible, sent via email.
+//!
+//! Emails are not sent right away: `notify()` only writes them into the
+//! `outbox` table. A background thread (see `start_worker()`) regularly sends
+//! all pending emails via the configured `Transport`. If sending fails, it is
+//! retried later with an increasing delay, up to `config::OUTBOX_MAX_ATTEMPTS`
+//! times. This way, a broken mail server never makes a request fail and no
+//! email is lost on a restart. Emails are only sent if the `[email]` section
+//! is present in the configuration file and only to users with an email
+//! address.
+
+use std::iter;
+use std::thread;
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+
+use config;
+use db::Db;
+use db::schema::{outbox, prep_student_preferences};
+use dict::{self, Locale};
+use errors::*;
+use user::User;
+
+mod html;
+pub mod inbox;
+pub mod routes;
+mod smtp;
+mod transport;
+
+pub use self::transport::{FileTransport, Mail, StdoutTransport, Transport};
+pub use self::smtp::SmtpTransport;
+
+
+/// Email settings, the `[email]` section of the configuration file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The sender of all emails, e.g. `Luten <luten@example.com>`.
+    pub from: String,
+
+    /// The public URL of this website (without trailing slash). Used for
+    /// links in emails.
+    pub base_url: String,
+
+    pub transport: transport::Config,
+}
+
+/// Something a user is notified about.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Notification {
+    /// The preparation phase started: students and tutors should enter their
+    /// preferences.
+    PreparationStarted,
+
+    /// The schedule has been calculated and the app is running.
+    ScheduleReady,
+
+    /// Another student chose the user as their partner.
+    PartnerChosen {
+        /// The username of the student who chose the partner.
+        by: String,
+    },
+
+    /// A student chose the user as their partner after the user had already
+    /// chosen them: they are partners now.
+    PartnerAccepted {
+        /// The username of the student who accepted.
+        by: String,
+    },
+}
+
+/// The kind of a notification, without any data. This is what is stored in
+/// the database.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum NotificationKind {
+    PreparationStarted,
+    ScheduleReady,
+    PartnerChosen,
+    PartnerAccepted,
+}
+
+impl Notification {
+    pub fn kind(&self) -> NotificationKind {
+        match *self {
+            Notification::PreparationStarted => NotificationKind::PreparationStarted,
+            Notification::ScheduleReady => NotificationKind::ScheduleReady,
+            Notification::PartnerChosen { .. } => NotificationKind::PartnerChosen,
+            Notification::PartnerAccepted { .. } => NotificationKind::PartnerAccepted,
+        }
+    }
+
+    /// The data of this notification which is stored in the database next
+    /// to the kind.
+    pub fn argument(&self) -> Option<&str> {
+        match *self {
+            Notification::PreparationStarted | Notification::ScheduleReady => None,
+            Notification::PartnerChosen { ref by } | Notification::PartnerAccepted { ref by } => {
+                Some(by.as_str())
+            }
+        }
+    }
+
+    /// Creates a notification from the values stored in the database.
+    /// Returns `None` if the argument doesn't fit the kind.
+    pub fn from_parts(kind: NotificationKind, argument: Option<String>) -> Option<Self> {
+        match (kind, argument) {
+            (NotificationKind::PreparationStarted, None) => Some(Notification::PreparationStarted),
+            (NotificationKind::ScheduleReady, None) => Some(Notification::ScheduleReady),
+            (NotificationKind::PartnerChosen, Some(by)) => Some(Notification::PartnerChosen { by }),
+            (NotificationKind::PartnerAccepted, Some(by)) => {
+                Some(Notification::PartnerAccepted { by })
+            }
+            _ => None,
+        }
+    }
+
+    /// The page on which the user can react to this notification.
+    pub fn link(&self) -> &'static str {
+        match *self {
+            Notification::PreparationStarted
+                | Notification::PartnerChosen { .. }
+                | Notification::PartnerAccepted { .. } => "/prep",
+            Notification::ScheduleReady => "/",
+        }
+    }
+
+    /// A short text shown in the inbox.
+    pub fn text(&self, locale: Locale) -> String {
+        let dict = dict::new(locale).notify;
+
+        match *self {
+            Notification::PreparationStarted => dict.preparation_started_text(),
+            Notification::ScheduleReady => dict.schedule_ready_text(),
+            Notification::PartnerChosen { ref by } => dict.partner_chosen_text(by),
+            Notification::PartnerAccepted { ref by } => dict.partner_accepted_text(by),
+        }
+    }
+
+    fn subject(&self, locale: Locale) -> String {
+        let dict = dict::new(locale).notify;
+
+        match *self {
+            Notification::PreparationStarted => dict.preparation_started_subject(),
+            Notification::ScheduleReady => dict.schedule_ready_subject(),
+            Notification::PartnerChosen { .. } => dict.partner_chosen_subject(),
+            Notification::PartnerAccepted { .. } => dict.partner_accepted_subject(),
+        }
+    }
+
+    fn body(&self, user: &User, base_url: &str, locale: Locale) -> String {
+        let dict = dict::new(locale).notify;
+        let name = user.name().unwrap_or(user.username());
+
+        let text = match *self {
+            Notification::PreparationStarted => dict.preparation_started_body(base_url),
+            Notification::ScheduleReady => dict.schedule_ready_body(base_url),
+            Notification::PartnerChosen { ref by } => dict.partner_chosen_body(by, base_url),
+            Notification::PartnerAccepted { ref by } => dict.partner_accepted_body(by, base_url),
+        };
+
+        format!("{}\n\n{}\n\n{}\n", dict.greeting(name), text, dict.footer(base_url))
+    }
+}
+
+/// Returns the locale in which emails and other texts which are not part of
+/// a request (like calendar feeds) are written to the given user.
+///
+/// Students choose their language in the preparation phase of each of their
+/// courses; English is used if they chose it in any course. Everyone else
+/// gets German texts.
+pub fn locale_of(user: &User, conn: &PgConnection) -> Result<Locale> {
+    let prefers_english = prep_student_preferences::table
+        .filter(prep_student_preferences::user_id.eq(user.id()))
+        .select(prep_student_preferences::prefers_english)
+        .load::<bool>(conn)?
+        .into_iter()
+        .any(|english| english);
+
+    if prefers_english {
+        Ok(Locale::En)
+    } else {
+        Ok(Locale::De)
+    }
+}
+
+/// Notifies the given user: the notification is added to their inbox and an
+/// email is put into the outbox.
+pub fn notify(user: &User, notification: &Notification, db: &Db) -> Result<()> {
+    notify_all(iter::once(user), notification, db)
+}
+
+/// Like `notify()`, but for many users at once.
+pub fn notify_all<'a, I>(users: I, notification: &Notification, db: &Db) -> Result<()>
+    where I: IntoIterator<Item = &'a User>
+{
+    let users = users.into_iter().collect::<Vec<_>>();
+    let conn = &*db.conn()?;
+
+    conn.transaction::<_, Error, _>(|| {
+        inbox::insert_all(&users, notification, conn)?;
+        queue_emails(&users, notification, conn)
+    })
+}
+
+/// Puts emails with the given notification into the outbox. Users without an
+/// email address are skipped. Does nothing if emails are disabled.
+fn queue_emails(users: &[&User], notification: &Notification, conn: &PgConnection) -> Result<()> {
+    #[derive(Insertable)]
+    #[table_name = "outbox"]
+    struct NewMail<'a> {
+        user_id: i64,
+        recipient: &'a str,
+        subject: String,
+        body: String,
+    }
+
+    let config = match config::FILE.email {
+        Some(ref config) => config,
+        None => return Ok(()),
+    };
+
+    let mut new_mails = Vec::new();
+    for user in users {
+        if let Some(email) = user.email() {
+            let locale = locale_of(user, conn)?;
+            new_mails.push(NewMail {
+                user_id: user.id(),
+                recipient: email,
+                subject: notification.subject(locale),
+                body: notification.body(user, &config.base_url, locale),
+            });
+        }
+    }
+
+    if !new_mails.is_empty() {
+        diesel::insert(&new_mails)
+            .into(outbox::table)
+            .execute(conn)
+            .chain_err(|| "failed to insert emails into outbox")?;
+    }
+
+    Ok(())
+}
+
+/// An email in the outbox. Emails which failed `config::OUTBOX_MAX_ATTEMPTS`
+/// times stay unsent; they can be inspected with `manage_db list outbox`.
+#[derive(Debug, Clone, Identifiable, Queryable)]
+#[table_name = "outbox"]
+pub struct OutboxMail {
+    pub id: i64,
+    pub user_id: i64,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub created: DateTime<Utc>,
+    pub attempts: i32,
+    pub next_attempt: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub sent: Option<DateTime<Utc>>,
+}
+
+/// Sends all emails in the outbox which are due. Returns the number of sent
+/// and failed emails.
+///
+/// Errors while sending a single email are stored in the outbox, so only
+/// database errors are returned.
+pub fn process_outbox(from: &str, transport: &Transport, db: &Db) -> Result<(usize, usize)> {
+    let conn = &*db.conn()?;
+    let now = Utc::now();
+    let due = outbox::table
+        .filter(outbox::sent.is_null())
+        .filter(outbox::attempts.lt(config::OUTBOX_MAX_ATTEMPTS))
+        .filter(outbox::next_attempt.le(now))
+        .order(outbox::next_attempt.asc())
+        .limit(config::OUTBOX_BATCH_SIZE)
+        .load::<OutboxMail>(conn)
+        .chain_err(|| "failed to load emails from outbox")?;
+
+    let (mut sent, mut failed) = (0, 0);
+    for entry in due {
+        let mail = Mail {
+            from: from.to_owned(),
+            to: entry.recipient.clone(),
+            subject: entry.subject.clone(),
+            body: entry.body.clone(),
+        };
+
+        match transport.send(&mail) {
+            Ok(()) => {
+                diesel::update(&entry)
+                    .set(outbox::sent.eq(Utc::now()))
+                    .execute(conn)?;
+                sent += 1;
+            }
+            Err(e) => {
+                // The delay doubles with every failed attempt.
+                let delay = config::OUTBOX_RETRY_DELAY * (1 << entry.attempts);
+                let error = e.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": ");
+                diesel::update(&entry)
+                    .set((
+                        outbox::attempts.eq(entry.attempts + 1),
+                        outbox::next_attempt.eq(Utc::now() + Duration::seconds(delay)),
+                        outbox::last_error.eq(error),
+                    ))
+                    .execute(conn)?;
+                failed += 1;
+            }
+        }
+    }
+
+    Ok((sent, failed))
+}
+
+/// Starts a background thread which sends the emails in the outbox every
+/// `config::OUTBOX_INTERVAL` seconds. Does nothing if email notifications
+/// are disabled.
+pub fn start_worker() {
+    let config = match config::FILE.email {
+        Some(ref config) => config,
+        None => return,
+    };
+
+    let transport = config.transport.build();
+    let db = Db::open_connection();
+
+    thread::spawn(move || {
+        loop {
+            if let Err(e) = process_outbox(&config.from, &*transport, &db) {
+                eprintln!("failed to process outbox: {}", e);
+            }
+            thread::sleep(StdDuration::from_secs(config::OUTBOX_INTERVAL));
+        }
+    });
+}
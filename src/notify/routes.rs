@@ -0,0 +1,44 @@
+use rocket::State;
+use rocket::response::{Flash, Redirect};
+
+use db::Db;
+use dict::{self, Locale};
+use errors::*;
+use super::html;
+use super::inbox::InboxEntry;
+use template::Page;
+use user::AuthUser;
+
+
+/// Shows all notifications of the user. Opening this page marks them as read.
+#[get("/inbox")]
+pub fn inbox(auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<Page> {
+    let entries = InboxEntry::load_all_of_user(&auth_user, &db)?;
+
+    // Admins looking around as another user shouldn't change anything.
+    if !auth_user.session().is_impersonation() {
+        InboxEntry::mark_all_read(&auth_user, &db)?;
+    }
+
+    Page::empty()
+        .with_title(dict::new(locale).notify.inbox_title())
+        .with_active_nav_route("/inbox")
+        .with_content(html::inbox(locale, &entries))
+        .make_ok()
+}
+
+#[post("/inbox/<id>/dismiss")]
+pub fn dismiss(id: i64, auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<Flash<Redirect>> {
+    if !InboxEntry::dismiss(id, &auth_user, &db)? {
+        return Ok(Flash::error(Redirect::to("/inbox"), bad_request(locale)));
+    }
+
+    Ok(Flash::success(Redirect::to("/inbox"), dict::new(locale).notify.flash_dismissed()))
+}
+
+#[post("/inbox/dismiss_all")]
+pub fn dismiss_all(auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<Flash<Redirect>> {
+    InboxEntry::dismiss_all(&auth_user, &db)?;
+
+    Ok(Flash::success(Redirect::to("/inbox"), dict::new(locale).notify.flash_all_dismissed()))
+}
@@ -0,0 +1,250 @@
+//! A small SMTP client (RFC 5321), just enough to hand our emails to a mail
+//! server.
+//!
+//! For each email, a new connection is opened. That's not the most efficient
+//! way, but we don't send many emails and it avoids dealing with timed out
+//! connections.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use base64;
+use native_tls::{TlsConnector, TlsStream};
+
+use errors::*;
+use super::transport::{Mail, Transport};
+
+
+/// Configuration of the SMTP transport.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    pub host: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    /// How the connection is encrypted.
+    #[serde(default)]
+    pub security: Security,
+
+    /// Credentials for `AUTH PLAIN`. If no username is given, we don't
+    /// authenticate.
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// Timeout in seconds for each read and write.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_port() -> u16 {
+    587
+}
+
+fn default_timeout() -> u64 {
+    10
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Security {
+    /// Unencrypted. Only use this for a server on the same machine!
+    None,
+
+    /// Upgrade the connection with `STARTTLS` (usually port 587).
+    Starttls,
+
+    /// TLS from the start (usually port 465).
+    Tls,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Security::Starttls
+    }
+}
+
+/// The connection to the server, possibly encrypted.
+enum Stream {
+    Plain(TcpStream),
+    Tls(TlsStream<TcpStream>),
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Plain(ref mut s) => s.read(buf),
+            Stream::Tls(ref mut s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match *self {
+            Stream::Plain(ref mut s) => s.write(buf),
+            Stream::Tls(ref mut s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match *self {
+            Stream::Plain(ref mut s) => s.flush(),
+            Stream::Tls(ref mut s) => s.flush(),
+        }
+    }
+}
+
+/// An SMTP session with a server.
+struct Session {
+    stream: BufReader<Stream>,
+}
+
+impl Session {
+    /// Sends a command (without the trailing CRLF) and checks the reply
+    /// code. Returns the text of the reply.
+    fn command(&mut self, command: &str, expected: u16) -> Result<String> {
+        self.stream.get_mut().write_all(format!("{}\r\n", command).as_bytes())?;
+        self.stream.get_mut().flush()?;
+
+        // Don't put passwords into error messages.
+        let shown = if command.starts_with("AUTH") { "AUTH" } else { command };
+        self.reply(expected).chain_err(|| format!("SMTP command '{}' failed", shown))
+    }
+
+    /// Sends the message after a `DATA` command. The message has to be
+    /// terminated by a line containing only a dot.
+    fn data(&mut self, message: &str) -> Result<()> {
+        self.stream.get_mut().write_all(message.as_bytes())?;
+        self.stream.get_mut().flush()?;
+        self.reply(250).chain_err(|| "SMTP server rejected the message")?;
+
+        Ok(())
+    }
+
+    /// Reads a (possibly multiline) reply and checks its code.
+    fn reply(&mut self, expected: u16) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                bail!("SMTP server closed the connection");
+            }
+
+            let line = line.trim_right();
+            if line.len() < 3 {
+                bail!("invalid SMTP reply '{}'", line);
+            }
+            let code = line[..3].parse::<u16>()
+                .chain_err(|| format!("invalid SMTP reply '{}'", line))?;
+            if line.len() > 4 {
+                text.push_str(&line[4..]);
+            }
+            text.push('\n');
+
+            // A dash after the code means that more lines follow.
+            if line.as_bytes().get(3) != Some(&b'-') {
+                if code != expected {
+                    bail!("unexpected SMTP reply: {}", line);
+                }
+                return Ok(text);
+            }
+        }
+    }
+}
+
+/// Sends emails via SMTP.
+pub struct SmtpTransport {
+    config: Config,
+}
+
+impl SmtpTransport {
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    fn connect(&self) -> Result<Session> {
+        let config = &self.config;
+        let tcp = TcpStream::connect((config.host.as_str(), config.port))
+            .chain_err(|| format!("failed to connect to '{}:{}'", config.host, config.port))?;
+        tcp.set_read_timeout(Some(Duration::from_secs(config.timeout)))?;
+        tcp.set_write_timeout(Some(Duration::from_secs(config.timeout)))?;
+
+        let stream = match config.security {
+            Security::Tls => Stream::Tls(self.tls(tcp)?),
+            _ => Stream::Plain(tcp),
+        };
+        let mut session = Session { stream: BufReader::new(stream) };
+        session.reply(220)?;
+        session.command("EHLO localhost", 250)?;
+
+        if config.security == Security::Starttls {
+            session.command("STARTTLS", 220)?;
+            let tcp = match session.stream.into_inner() {
+                Stream::Plain(tcp) => tcp,
+                Stream::Tls(_) => unreachable!(),
+            };
+            session = Session { stream: BufReader::new(Stream::Tls(self.tls(tcp)?)) };
+            session.command("EHLO localhost", 250)?;
+        }
+
+        if let Some(ref username) = config.username {
+            let password = config.password.as_ref().map(|p| p.as_str()).unwrap_or("");
+            let credentials = format!("\0{}\0{}", username, password);
+            session.command(&format!("AUTH PLAIN {}", base64::encode(credentials.as_bytes())), 235)?;
+        }
+
+        Ok(session)
+    }
+
+    fn tls(&self, tcp: TcpStream) -> Result<TlsStream<TcpStream>> {
+        let connector = TlsConnector::builder()
+            .and_then(|builder| builder.build())
+            .chain_err(|| "failed to create TLS connector")?;
+
+        connector.connect(&self.config.host, tcp)
+            .map_err(|e| format!("TLS handshake with SMTP server failed: {}", e).into())
+    }
+}
+
+impl Transport for SmtpTransport {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        // This also rejects addresses containing line breaks, which would
+        // end up in the SMTP commands below.
+        let message = mail.format()?;
+
+        let mut session = self.connect()?;
+
+        session.command(&format!("MAIL FROM:<{}>", address(&mail.from)), 250)?;
+        session.command(&format!("RCPT TO:<{}>", address(&mail.to)), 250)?;
+        session.command("DATA", 354)?;
+
+        // Lines starting with a dot have to be escaped by another dot
+        // ("dot-stuffing"). The message is terminated by a line containing
+        // only a dot.
+        let mut data = String::new();
+        for line in message.lines() {
+            if line.starts_with('.') {
+                data.push('.');
+            }
+            data.push_str(line);
+            data.push_str("\r\n");
+        }
+        data.push_str(".\r\n");
+        session.data(&data)?;
+
+        // The email is accepted at this point, so errors don't matter.
+        let _ = session.command("QUIT", 221);
+
+        Ok(())
+    }
+}
+
+/// Extracts the address from a mailbox like `Luten <luten@example.com>`.
+fn address(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &mailbox[start + 1..end],
+        _ => mailbox.trim(),
+    }
+}
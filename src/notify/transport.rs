@@ -0,0 +1,182 @@
+//! Ways to actually deliver emails.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use base64;
+use chrono::Utc;
+
+use errors::*;
+use super::smtp::{self, SmtpTransport};
+
+
+/// Configuration of the transport, the `[email.transport]` section of the
+/// configuration file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Config {
+    /// Sends emails via an SMTP server.
+    Smtp(smtp::Config),
+
+    /// Appends all emails to the given file. Useful for development and
+    /// testing.
+    File {
+        path: String,
+    },
+
+    /// Prints all emails to stdout. Useful for development and testing.
+    Stdout,
+}
+
+impl Config {
+    /// Creates the transport described by this configuration.
+    pub fn build(&self) -> Box<Transport + Send> {
+        match *self {
+            Config::Smtp(ref config) => Box::new(SmtpTransport::new(config.clone())),
+            Config::File { ref path } => Box::new(FileTransport { path: path.clone() }),
+            Config::Stdout => Box::new(StdoutTransport),
+        }
+    }
+}
+
+/// A single email.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Mail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Mail {
+    /// Checks that the addresses and the subject don't contain line breaks.
+    /// Otherwise, they could be used to inject SMTP commands or headers.
+    pub fn check(&self) -> Result<()> {
+        let fields = [("sender", &self.from), ("recipient", &self.to), ("subject", &self.subject)];
+        for &(name, value) in &fields {
+            if value.contains(|c| c == '\r' || c == '\n') {
+                bail!("{} of email contains a line break", name);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Formats this email as message as defined in RFC 5322 (with MIME
+    /// headers). Lines are terminated by CRLF. Fails if `check()` fails.
+    ///
+    /// Subject and body are base64 encoded, so that we don't have to care
+    /// about non-ASCII characters or long lines.
+    pub fn format(&self) -> Result<String> {
+        self.check()?;
+
+        let subject = format!("=?utf-8?B?{}?=", base64::encode(self.subject.as_bytes()));
+        let headers = [
+            ("Date", Utc::now().to_rfc2822()),
+            ("From", self.from.clone()),
+            ("To", self.to.clone()),
+            ("Subject", subject),
+            ("MIME-Version", "1.0".into()),
+            ("Content-Type", "text/plain; charset=utf-8".into()),
+            ("Content-Transfer-Encoding", "base64".into()),
+            ("Auto-Submitted", "auto-generated".into()),
+        ];
+
+        let mut out = String::new();
+        for &(ref name, ref value) in &headers {
+            out.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        out.push_str("\r\n");
+        let body = base64::encode(self.body.replace('\n', "\r\n").as_bytes());
+        for line in body.as_bytes().chunks(76) {
+            // Base64 is ASCII only.
+            out.push_str(&String::from_utf8_lossy(line));
+            out.push_str("\r\n");
+        }
+
+        Ok(out)
+    }
+}
+
+/// Something that can deliver emails.
+pub trait Transport {
+    /// Sends the given email. An error means that the email was not sent and
+    /// sending should be retried later.
+    fn send(&self, mail: &Mail) -> Result<()>;
+}
+
+/// Appends all emails to a file.
+pub struct FileTransport {
+    path: String,
+}
+
+impl Transport for FileTransport {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .chain_err(|| format!("failed to open '{}'", self.path))?;
+
+        writeln!(file, "========== To: {} | Subject: {}", mail.to, mail.subject)?;
+        writeln!(file, "{}", mail.body)?;
+        writeln!(file, "")?;
+
+        Ok(())
+    }
+}
+
+/// Prints all emails to stdout.
+pub struct StdoutTransport;
+
+impl Transport for StdoutTransport {
+    fn send(&self, mail: &Mail) -> Result<()> {
+        println!("========== To: {} | Subject: {}", mail.to, mail.subject);
+        println!("{}", mail.body);
+        println!("");
+
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mail() -> Mail {
+        Mail {
+            from: "Luten <luten@example.com>".into(),
+            to: "alice@example.com".into(),
+            subject: "Dein Testat".into(),
+            body: "Hallo!\n.\nBis bald".into(),
+        }
+    }
+
+    #[test]
+    fn format_encodes_subject_and_body() {
+        let formatted = mail().format().unwrap();
+        let (headers, body) = formatted.split_at(formatted.find("\r\n\r\n").unwrap());
+
+        assert!(headers.contains("\r\nFrom: Luten <luten@example.com>\r\n"));
+        assert!(headers.contains("\r\nTo: alice@example.com\r\n"));
+        assert!(headers.contains("\r\nSubject: =?utf-8?B?RGVpbiBUZXN0YXQ=?=\r\n"));
+        assert_eq!(body, "\r\n\r\nSGFsbG8hDQouDQpCaXMgYmFsZA==\r\n");
+    }
+
+    #[test]
+    fn line_breaks_in_headers_are_rejected() {
+        let injected = [
+            Mail { from: "luten@example.com\r\nRCPT TO:<eve@example.com>".into(), ..mail() },
+            Mail { to: "alice@example.com\nBcc: eve@example.com".into(), ..mail() },
+            Mail { to: "alice@example.com\r".into(), ..mail() },
+            Mail { subject: "Hallo\r\nBcc: eve@example.com".into(), ..mail() },
+        ];
+
+        assert!(mail().check().is_ok());
+        for mail in &injected {
+            assert!(mail.check().is_err(), "{:?}", mail);
+            assert!(mail.format().is_err(), "{:?}", mail);
+        }
+    }
+}
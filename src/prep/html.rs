@@ -343,13 +343,16 @@ pub fn timeslot_list<F, D>(slots: &[(TimeSlot, D)], locale: Locale, mut slot_for
     for v in days.values_mut().filter(|v| !v.is_empty()) {
         v.sort_by_key(|e| e.unwrap().0);
 
-        let mut last_time = v[0].unwrap().0.time();
+        // Insert a gap between two timeslots if the second doesn't start
+        // directly after the first one ends. Timeslots can have different
+        // lengths, so each gap is shown as one empty slot.
         let mut i = 1;
         while i < v.len() {
-            if v[i].unwrap().0.time().prev() != last_time {
+            let prev_end = v[i - 1].unwrap().0.end();
+            if v[i].unwrap().0.time() != prev_end {
                 v.insert(i, None);
+                i += 1;
             }
-            last_time = last_time.next();
 
             i += 1;
         }
@@ -2,6 +2,7 @@
 //! routes.**
 
 use diesel;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
 
 
@@ -9,6 +10,7 @@ mod html;
 pub mod routes;
 
 
+use course::Course;
 use db::Db;
 use db::schema::{prep_student_preferences, timeslots, timeslot_ratings};
 use errors::*;
@@ -16,24 +18,26 @@ use timeslot::{Rating, TimeSlot};
 use user::{Student, User};
 
 
-/// Preferences by a student, set by the student during the preparation state.
+/// Preferences by a student in one course, set by the student during the
+/// preparation state.
 #[derive(Debug, Clone, Identifiable, Insertable, Queryable)]
 #[table_name = "prep_student_preferences"]
-#[primary_key(user_id)]
+#[primary_key(user_id, course_id)]
 pub struct StudentPreferences {
     user_id: i64,
     pub partner: Option<String>,
     pub prefers_english: bool,
+    course_id: i16,
 }
 
 impl StudentPreferences {
-    /// Loads the preferences of the given student.
+    /// Loads the preferences of the given student in the given course.
     ///
     /// This assumes that a row for the given student already exists. As such
     /// an error is returned when it doesn't.
-    pub fn load_for(user: &Student, db: &Db) -> Result<Self> {
+    pub fn load_for(user: &Student, course: &Course, db: &Db) -> Result<Self> {
         prep_student_preferences::table
-            .find(user.id())
+            .find((user.id(), course.id()))
             .first::<Self>(&*db.conn()?)
             .chain_err(|| "student with non-existing preferences found")
     }
@@ -42,19 +46,33 @@ impl StudentPreferences {
     /// into the database. The inserted object is returned.
     pub fn create(
         user: &Student,
+        course: &Course,
         partner: Option<String>,
         prefers_english: bool,
         db: &Db,
+    ) -> Result<Self> {
+        Self::create_on(user, course, partner, prefers_english, &*db.conn()?)
+    }
+
+    /// Like `create()`, but uses the given connection, e.g. one with an open
+    /// transaction.
+    pub fn create_on(
+        user: &Student,
+        course: &Course,
+        partner: Option<String>,
+        prefers_english: bool,
+        conn: &PgConnection,
     ) -> Result<Self> {
         let new_entry = Self {
             user_id: user.id(),
             partner,
             prefers_english,
+            course_id: course.id(),
         };
 
         diesel::insert(&new_entry)
             .into(prep_student_preferences::table)
-            .get_result::<Self>(&*db.conn()?)
+            .get_result::<Self>(conn)
             .chain_err(|| "failed to insert new StudentPreferences")
     }
 
@@ -62,13 +80,40 @@ impl StudentPreferences {
     ///
     /// Note that the default values are hardcoded here for now. Later, in the
     /// bright future, we will make preferences much more customizable.
-    pub fn create_default(user: &Student, db: &Db) -> Result<Self> {
-        Self::create(user, None, false, db)
+    pub fn create_default(user: &Student, course: &Course, db: &Db) -> Result<Self> {
+        Self::create_default_on(user, course, &*db.conn()?)
+    }
+
+    /// Like `create_default()`, but uses the given connection.
+    pub fn create_default_on(user: &Student, course: &Course, conn: &PgConnection) -> Result<Self> {
+        Self::create_on(user, course, None, false, conn)
+    }
+
+    /// Creates default preferences for the given student in the given
+    /// course if they don't have any preferences there yet. Returns `true`
+    /// if preferences were created.
+    ///
+    /// This is necessary when a user becomes a student in a course.
+    pub fn create_default_if_missing_on(
+        user: &Student,
+        course: &Course,
+        conn: &PgConnection,
+    ) -> Result<bool> {
+        let existing = prep_student_preferences::table
+            .find((user.id(), course.id()))
+            .first::<Self>(conn)
+            .optional()?;
+
+        if existing.is_none() {
+            Self::create_default_on(user, course, conn)?;
+        }
+
+        Ok(existing.is_none())
     }
 
     /// Updates the database with this value.
     pub fn update(&self, db: &Db) -> Result<()> {
-        diesel::update(prep_student_preferences::table.find(self.user_id))
+        diesel::update(prep_student_preferences::table.find((self.user_id, self.course_id)))
             .set((
                 prep_student_preferences::columns::partner.eq(&self.partner),
                 prep_student_preferences::columns::prefers_english.eq(&self.prefers_english),
@@ -106,11 +151,12 @@ impl TimeSlotRating {
             .map(|opt| opt.map(|(r, slot)| (slot, r.rating)))
     }
 
-    /// Loads all ratings of the given user.
-    pub fn load_all_of_user(user: &User, db: &Db) -> Result<Vec<(TimeSlot, Rating)>> {
+    /// Loads all ratings of the given user for timeslots of the given course.
+    pub fn load_all_of_user(user: &User, course: &Course, db: &Db) -> Result<Vec<(TimeSlot, Rating)>> {
         let ratings: Vec<_> = timeslot_ratings::table
             .filter(timeslot_ratings::columns::user_id.eq(user.id()))
             .inner_join(timeslots::table)
+            .filter(timeslots::course_id.eq(course.id()))
             .get_results::<(Self, TimeSlot)>(&*db.conn()?)?
             .into_iter()
             .map(|(r, slot)| (slot, r.rating))
@@ -121,22 +167,22 @@ impl TimeSlotRating {
         //  this shouldn't be necessary: one user creation, default entries are
         //  created. The following code is only useful if timeslots are added
         //  after users are created.
-        if ratings.len() as u64 != TimeSlot::count(&db)? {
-            Self::create_defaults_for_user(&user, &db)?;
-            TimeSlotRating::load_all_of_user(&user, &db)
+        if ratings.len() as u64 != TimeSlot::count(course, &db)? {
+            Self::create_defaults_for_user(&user, course, &db)?;
+            TimeSlotRating::load_all_of_user(&user, course, &db)
         } else {
             Ok(ratings)
         }
     }
 
-    /// Inserts a "Bad" rating for all existing timeslots for which the given
-    /// user has no rating yet. Returns `true` if at least one rating was
-    /// inserted, `false` otherwise.
-    pub fn create_defaults_for_user(user: &User, db: &Db) -> Result<bool> {
+    /// Inserts a "Bad" rating for all timeslots of the given course for which
+    /// the given user has no rating yet. Returns `true` if at least one
+    /// rating was inserted, `false` otherwise.
+    pub fn create_defaults_for_user(user: &User, course: &Course, db: &Db) -> Result<bool> {
         // First, find all timeslots for which the given user has no rating by
         // finding all timeslots and the slots for which the user has a
         // rating...
-        let all_timeslots = TimeSlot::load_all(db)?;
+        let all_timeslots = TimeSlot::load_all(course, db)?;
         let my_ratings = timeslot_ratings::table
             .filter(timeslot_ratings::columns::user_id.eq(user.id()))
             .select(timeslot_ratings::columns::timeslot_id)
@@ -163,17 +209,29 @@ impl TimeSlotRating {
             .chain_err(|| "failed to insert timeslot ratings into DB")
     }
 
-    /// Updates all given timeslots with the given ratings.
-    pub fn update_all(user: &User, ratings: &[(i16, Rating)], db: &Db) -> Result<()> {
-        // Yeah, we execute one query per time slot here... Maybe we should
-        // change this.
+    /// Updates the ratings of the given user for the given timeslots of the
+    /// given course. If one of the timeslots doesn't belong to the course,
+    /// nothing is changed and `false` is returned.
+    pub fn update_all(user: &User, course: &Course, ratings: &[(i16, Rating)], db: &Db) -> Result<bool> {
         let conn = &*db.conn()?;
-        for &(slot_id, rating) in ratings {
-            diesel::update(timeslot_ratings::table.find((user.id(), slot_id)))
-                .set(timeslot_ratings::columns::rating.eq(rating))
-                .execute(conn)?;
-        }
-
-        Ok(())
+        conn.transaction::<_, Error, _>(|| {
+            let slot_ids = timeslots::table
+                .filter(timeslots::course_id.eq(course.id()))
+                .select(timeslots::id)
+                .load::<i16>(conn)?;
+            if ratings.iter().any(|&(slot_id, _)| !slot_ids.contains(&slot_id)) {
+                return Ok(false);
+            }
+
+            // Yeah, we execute one query per time slot here... Maybe we
+            // should change this.
+            for &(slot_id, rating) in ratings {
+                diesel::update(timeslot_ratings::table.find((user.id(), slot_id)))
+                    .set(timeslot_ratings::columns::rating.eq(rating))
+                    .execute(conn)?;
+            }
+
+            Ok(true)
+        })
     }
 }
@@ -5,12 +5,14 @@ use option_filter::OptionFilterExt;
 
 use super::{html, StudentPreferences, TimeSlotRating};
 use config;
+use course::Course;
 use db::Db;
 use dict::{self, Locale};
 use errors::*;
+use notify::{self, Notification};
 use state::PreparationState;
 use template::{NavItem, Page};
-use user::{AuthUser, Role, User};
+use user::{AuthStaff, AuthStudent, AuthTutor, Role, User};
 use timeslot::Rating;
 
 
@@ -24,118 +26,144 @@ fn nav_items(locale: Locale) -> Vec<NavItem> {
     ]
 }
 
+/// The overview page for students: their general preferences.
 #[get("/prep")]
-pub fn overview(
-    auth_user: AuthUser,
+pub fn overview_student(
+    auth_student: AuthStudent,
     locale: Locale,
     db: State<Db>,
     _state: PreparationState,
 ) -> Result<Page> {
     let dict = dict::new(locale).prep;
 
-    match auth_user.role() {
-        // ===== Student ======================================================
-        Role::Student => {
-            let student = auth_user.into_user().into_student().unwrap();
-            let pref = StudentPreferences::load_for(&student, &db)?;
-
-            let partner = pref.partner.as_ref()
-                .map_or(Ok(None), |name| User::load_by_username(name, &db))?
-                .and_then(|u| u.into_student().ok())
-                .filter(|s| s.id() != student.id());
-
-            Page::empty()
-                .with_title(dict.overview_title())
-                .add_nav_items(nav_items(locale))
-                .with_active_nav_route("/prep")
-                .with_content(html::student_overview(
-                    locale,
-                    &pref,
-                    &partner,
-                ))
+    let course = auth_student.course().clone();
+    let student = auth_student.into_inner();
+    let pref = StudentPreferences::load_for(&student, &course, &db)?;
+
+    let partner = match pref.partner.as_ref() {
+        Some(name) => match User::load_by_username(name, &db)? {
+            Some(user) => user.into_student_of(&course, &db)?.ok(),
+            None => None,
+        },
+        None => None,
+    }.filter(|s| s.id() != student.id());
+
+    Page::empty()
+        .with_title(dict.overview_title())
+        .add_nav_items(nav_items(locale))
+        .with_active_nav_route("/prep")
+        .with_content(html::student_overview(
+            locale,
+            &pref,
+            &partner,
+        ))
+        .make_ok()
+}
+
+/// The overview page for tutors and admins: statistics about the ratings.
+#[get("/prep", rank = 2)]
+pub fn overview_staff(
+    auth_staff: AuthStaff,
+    locale: Locale,
+    db: State<Db>,
+    _state: PreparationState,
+) -> Result<Page> {
+    use std::collections::HashMap;
+    use diesel::prelude::*;
+    use diesel::expression::sql;
+    use db::schema::{course_members, timeslot_ratings, timeslots, users};
+
+    let dict = dict::new(locale).prep;
+
+    // Only students and tutors of the current course and ratings of its
+    // timeslots are taken into account.
+    let course_id = auth_staff.course().map(|c| c.id()).unwrap_or(0);
+    let conn = &*db.conn()?;
+    let member_ids = |role: Role| {
+        course_members::table
+            .filter(course_members::course_id.eq(course_id))
+            .filter(course_members::role.eq(role))
+            .select(course_members::user_id)
+            .load::<i64>(conn)
+    };
+    let student_ids = member_ids(Role::Student)?;
+    let tutor_ids = member_ids(Role::Tutor)?;
+    let slot_ids = timeslots::table
+        .filter(timeslots::course_id.eq(course_id))
+        .select(timeslots::id)
+        .load::<i16>(conn)?;
+
+    let stats = {
+        let ratings = timeslot_ratings::table
+            .filter(timeslot_ratings::user_id.eq_any(&student_ids))
+            .filter(timeslot_ratings::timeslot_id.eq_any(&slot_ids))
+            .select((timeslot_ratings::user_id, timeslot_ratings::rating))
+            .load::<(i64, Rating)>(conn)?;
+
+        // Number of good and of good or tolerable ratings per student
+        let mut counts = HashMap::new();
+        for (user_id, rating) in ratings {
+            let count = counts.entry(user_id).or_insert((0u64, 0u64));
+            if rating == Rating::Good {
+                count.0 += 1;
+            }
+            if rating != Rating::Bad {
+                count.1 += 1;
+            }
         }
 
-        // ===== Tutor or admin ===============================================
-        Role::Tutor | Role::Admin => {
-            use diesel::prelude::*;
-            use diesel::expression::sql;
-            use db::schema::{timeslot_ratings, users};
-
-            let conn = &*db.conn()?;
-            let stats = {
-                let num_students = users::table
-                    .filter(sql("role = 'student'"))
-                    .count()
-                    .get_result::<i64>(conn)?;
-
-                let num_students_with_slots = users::table
-                    .inner_join(timeslot_ratings::table)
-                    .filter(sql("rating <> 'bad' AND role = 'student'"))
-                    .select(sql("count(distinct user_id) as count"))
-                    .get_result::<i64>(conn)?;
-
-
-                let avg_good_rating_per_student = sql("
-                    select cast(avg(count) as float) from (
-                        select count(*) as count, user_id
-                        from timeslot_ratings
-                        inner join users
-                            on users.id = user_id
-                        where rating = 'good' and role = 'student'
-                        group by user_id
-                    ) as counts
-                ").get_result::<f64>(conn)?;
-
-                let avg_ok_rating_per_student = sql("
-                    select cast(avg(count) as float) from (
-                        select count(*) as count, user_id
-                        from timeslot_ratings
-                        inner join users
-                            on users.id = user_id
-                        where rating <> 'bad' and role = 'student'
-                        group by user_id
-                    ) as counts
-                ").get_result::<f64>(conn)?;
-
-                html::TutorAdminStats {
-                    num_students: num_students as u64,
-                    num_students_with_slots: num_students_with_slots as u64,
-                    avg_good_rating_per_student,
-                    avg_ok_rating_per_student,
-                }
-            };
+        // The averages only take students into account who gave at least
+        // one such rating.
+        let average = |counts: Vec<u64>| {
+            let counts: Vec<_> = counts.into_iter().filter(|&c| c > 0).collect();
+            if counts.is_empty() {
+                0.0
+            } else {
+                counts.iter().sum::<u64>() as f64 / counts.len() as f64
+            }
+        };
 
-            let tutors = users::table
-                .inner_join(timeslot_ratings::table)
-                .filter(sql("role = 'tutor'"))
-                .group_by(users::columns::id)
-                .select(sql("
-                    username,
-                    name,
-                    sum(case when rating='good' then 1 else 0 end) as num_good,
-                    sum(case when rating<>'bad' then 1 else 0 end) as num_ok
-                "))
-                .load::<(String, Option<String>, i64, i64)>(conn)?;
-
-            let content = html::tutor_admin_overview(
-                locale,
-                auth_user.is_tutor(),
-                stats,
-                &tutors,
-            );
-
-            Page::empty()
-                .with_title(dict.overview_title())
-                .add_nav_items(nav_items(locale))
-                .with_active_nav_route("/prep")
-                .with_content(content)
+        html::TutorAdminStats {
+            num_students: student_ids.len() as u64,
+            num_students_with_slots: counts.values().filter(|&&(_, ok)| ok > 0).count() as u64,
+            avg_good_rating_per_student: average(counts.values().map(|&(good, _)| good).collect()),
+            avg_ok_rating_per_student: average(counts.values().map(|&(_, ok)| ok).collect()),
         }
-    }.make_ok()
+    };
+
+    let tutors = users::table
+        .inner_join(timeslot_ratings::table)
+        .filter(users::id.eq_any(&tutor_ids))
+        .filter(timeslot_ratings::timeslot_id.eq_any(&slot_ids))
+        .group_by(users::columns::id)
+        .select(sql("
+            username,
+            name,
+            sum(case when rating='good' then 1 else 0 end) as num_good,
+            sum(case when rating<>'bad' then 1 else 0 end) as num_ok
+        "))
+        .load::<(String, Option<String>, i64, i64)>(conn)?;
+
+    let content = html::tutor_admin_overview(
+        locale,
+        auth_staff.course_role() == Role::Tutor,
+        stats,
+        &tutors,
+    );
+
+    Page::empty()
+        .with_title(dict.overview_title())
+        .add_nav_items(nav_items(locale))
+        .with_active_nav_route("/prep")
+        .with_content(content)
+        .make_ok()
 }
 
+/// Only students have general preferences. Tutors and admins should not be
+/// sent to this route; for them, the request is forwarded.
 #[post("/prep_student_settings", data = "<form>")]
 pub fn set_general_settings(
-    auth_user: AuthUser,
+    auth_student: AuthStudent,
     form: Form<GeneralStudentSettings>,
     db: State<Db>,
     _state: PreparationState,
@@ -146,28 +174,30 @@ pub fn set_general_settings(
     }
 
     let dict = dict::new(locale).prep;
+    let course = auth_student.course().clone();
+    let student = auth_student.into_inner();
 
-    // The auth_user needs to be a student. Tutors and admins should not be
-    // forwarded to this route.
-    let student = match auth_user.into_user().into_student() {
-        Ok(s) => s,
-        Err(_) => {
-            return err(bad_request(locale));
-        }
-    };
-
-    let mut pref = StudentPreferences::load_for(&student, &db)?;
+    let mut pref = StudentPreferences::load_for(&student, &course, &db)?;
     let form = form.into_inner();
 
-    // Set partner
+    // Set partner. If a new partner is chosen, they are notified.
+    let mut new_partner = None;
     match form.partner.as_ref() {
         "random" => {
             pref.partner = None;
         }
         "chosen" => {
             if let Some(id) = form.partner_id {
-                match User::load_by_username(&id, &db)? {
-                    Some(ref u) if u.is_student() => {
+                let user = User::load_by_username(&id, &db)?;
+                let is_student = match user {
+                    Some(ref u) => u.role_in(&course, &db)? == Some(Role::Student),
+                    None => false,
+                };
+                match user {
+                    Some(ref u) if is_student => {
+                        if pref.partner.as_ref() != Some(&id) {
+                            new_partner = Some(u.clone());
+                        }
                         pref.partner = Some(id);
                     }
                     Some(ref u) => {
@@ -200,6 +230,25 @@ pub fn set_general_settings(
     // Finally, store the changes in the database.
     pref.update(&db)?;
 
+    if let Some(partner) = new_partner {
+        // If the partner already chose this student, they are partners now.
+        let chosen_back = match partner.clone().into_student_of(&course, &db)? {
+            Ok(partner) => {
+                let partner_pref = StudentPreferences::load_for(&partner, &course, &db)?;
+                partner_pref.partner.as_ref().map(|p| p.as_str()) == Some(student.username())
+            }
+            Err(_) => false,
+        };
+
+        let by = student.username().to_owned();
+        let notification = if chosen_back {
+            Notification::PartnerAccepted { by }
+        } else {
+            Notification::PartnerChosen { by }
+        };
+        notify::notify(&partner, &notification, &db)?;
+    }
+
     Ok(Flash::success(Redirect::to("/prep"), dict.flash_success_storing_preferences()))
 }
 
@@ -211,52 +260,81 @@ pub struct GeneralStudentSettings {
 }
 
 #[get("/prep/timeslots")]
-pub fn timeslots(
-    auth_user: AuthUser,
+pub fn timeslots_student(
+    auth_student: AuthStudent,
     locale: Locale,
     db: State<Db>,
     _state: PreparationState,
 ) -> Result<Page> {
     let dict = dict::new(locale).prep;
 
-    // Load all ratings of the user.
-    let ratings = TimeSlotRating::load_all_of_user(&auth_user, &db)?;
-
-    match auth_user.role() {
-        Role::Student | Role::Tutor => {
-            let (explanation, min_good, min_ok) = match auth_user.role() {
-                Role::Student => (
-                    dict.timeslots_student_explanation(),
-                    config::MIN_GOOD_SLOTS_STUDENT,
-                    config::MIN_OK_SLOTS_STUDENT,
-                ),
-                Role::Tutor => (
-                    dict.timeslots_tutor_explanation(),
-                    config::MIN_GOOD_SLOTS_TUTOR,
-                    config::MIN_OK_SLOTS_TUTOR,
-                ),
-                _ => unreachable!(),
-            };
+    timeslots_page(
+        auth_student.as_user(),
+        auth_student.course(),
+        &dict.timeslots_student_explanation(),
+        config::MIN_GOOD_SLOTS_STUDENT,
+        config::MIN_OK_SLOTS_STUDENT,
+        locale,
+        &db,
+    )
+}
 
-            let content = html::timeslots(
-                &explanation,
-                min_good,
-                min_ok,
-                &ratings,
-                locale,
-            );
-
-            Page::empty()
-                .with_title(dict.timeslots_title())
-                .add_nav_items(nav_items(locale))
-                .with_active_nav_route("/prep/timeslots")
-                .with_content(content)
-                .make_ok()
-        }
-        Role::Admin => {
-            Page::unimplemented().make_ok()
-        }
-    }
+#[get("/prep/timeslots", rank = 2)]
+pub fn timeslots_tutor(
+    auth_tutor: AuthTutor,
+    locale: Locale,
+    db: State<Db>,
+    _state: PreparationState,
+) -> Result<Page> {
+    let dict = dict::new(locale).prep;
+
+    timeslots_page(
+        auth_tutor.as_user(),
+        auth_tutor.course(),
+        &dict.timeslots_tutor_explanation(),
+        config::MIN_GOOD_SLOTS_TUTOR,
+        config::MIN_OK_SLOTS_TUTOR,
+        locale,
+        &db,
+    )
+}
+
+/// Tutors are handled by `timeslots_tutor()`, so only admins end up here.
+#[get("/prep/timeslots", rank = 3)]
+pub fn timeslots_admin(_staff: AuthStaff, _state: PreparationState) -> Page {
+    Page::unimplemented()
+}
+
+/// Shows the timeslot ratings of the given user (student or tutor) in the
+/// given course.
+fn timeslots_page(
+    user: &User,
+    course: &Course,
+    explanation: &str,
+    min_good: u64,
+    min_ok: u64,
+    locale: Locale,
+    db: &Db,
+) -> Result<Page> {
+    let dict = dict::new(locale).prep;
+
+    // Load all ratings of the user.
+    let ratings = TimeSlotRating::load_all_of_user(user, course, db)?;
+
+    let content = html::timeslots(
+        explanation,
+        min_good,
+        min_ok,
+        &ratings,
+        locale,
+    );
+
+    Page::empty()
+        .with_title(dict.timeslots_title())
+        .add_nav_items(nav_items(locale))
+        .with_active_nav_route("/prep/timeslots")
+        .with_content(content)
+        .make_ok()
 }
 
 /// Stores a list of (timeslot_id, rating).
@@ -302,15 +380,39 @@ pub enum TimeSlotFormError {
 
 
 #[post("/prep/update_timeslots", data = "<form>")]
-fn update_timeslots(
-    auth_user: AuthUser,
+pub fn update_timeslots_student(
+    auth_student: AuthStudent,
     form: Form<TimeSlotForm>,
     locale: Locale,
     db: State<Db>,
     _state: PreparationState,
 ) -> Result<Flash<Redirect>> {
-    let form = form.into_inner();
-    TimeSlotRating::update_all(&auth_user, &form.slots, &db)?;
+    update_timeslots(auth_student.as_user(), auth_student.course(), form.into_inner(), locale, &db)
+}
+
+#[post("/prep/update_timeslots", data = "<form>", rank = 2)]
+pub fn update_timeslots_tutor(
+    auth_tutor: AuthTutor,
+    form: Form<TimeSlotForm>,
+    locale: Locale,
+    db: State<Db>,
+    _state: PreparationState,
+) -> Result<Flash<Redirect>> {
+    update_timeslots(auth_tutor.as_user(), auth_tutor.course(), form.into_inner(), locale, &db)
+}
+
+/// Stores the timeslot ratings of the given user (student or tutor) in the
+/// given course.
+fn update_timeslots(
+    user: &User,
+    course: &Course,
+    form: TimeSlotForm,
+    locale: Locale,
+    db: &Db,
+) -> Result<Flash<Redirect>> {
+    if !TimeSlotRating::update_all(user, course, &form.slots, db)? {
+        return Ok(Flash::error(Redirect::to("/prep/timeslots"), bad_request(locale)));
+    }
 
     Ok(Flash::success(
         Redirect::to("/prep/timeslots"),
@@ -0,0 +1,173 @@
+//! Rooms in which testats take place.
+//!
+//! Each course has its own rooms. A room has a limited number of seats and
+//! can only be used in some timeslots. Both are constraints for the schedule:
+//! every testat in a room needs one seat per student and one for the tutor.
+//! Rooms are managed in the admin panel.
+
+use diesel;
+use diesel::prelude::*;
+
+use course::Course;
+use db::Db;
+use db::schema::{room_availability, rooms, testats, timeslots};
+use errors::*;
+use timeslot::TimeSlot;
+
+
+/// A room, as stored in the database.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable)]
+#[table_name = "rooms"]
+pub struct Room {
+    id: i16,
+    course_id: i16,
+    pub name: String,
+
+    /// Number of seats.
+    pub capacity: i16,
+}
+
+impl Room {
+    /// Loads all rooms of the given course, ordered by name.
+    pub fn load_all(course: &Course, db: &Db) -> Result<Vec<Self>> {
+        rooms::table
+            .filter(rooms::course_id.eq(course.id()))
+            .order(rooms::name.asc())
+            .load(&*db.conn()?)
+            .chain_err(|| "failed to load rooms")
+    }
+
+    /// Loads the room with the given id, if it belongs to the given course.
+    pub fn load_by_id(course: &Course, id: i16, db: &Db) -> Result<Option<Self>> {
+        rooms::table
+            .find(id)
+            .filter(rooms::course_id.eq(course.id()))
+            .first(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to load room")
+    }
+
+    /// Creates a new room. It is available in all timeslots of the course.
+    pub fn create(course: &Course, name: &str, capacity: i16, db: &Db) -> Result<Self> {
+        #[derive(Insertable)]
+        #[table_name = "rooms"]
+        struct NewRoom<'a> {
+            course_id: i16,
+            name: &'a str,
+            capacity: i16,
+        }
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            let room = diesel::insert(&NewRoom { course_id: course.id(), name, capacity })
+                .into(rooms::table)
+                .get_result::<Self>(conn)?;
+
+            let slot_ids = timeslots::table
+                .filter(timeslots::course_id.eq(course.id()))
+                .select(timeslots::id)
+                .load::<i16>(conn)?;
+            room.set_availability_on(&slot_ids, conn)?;
+
+            Ok(room)
+        })
+    }
+
+    /// Changes name and capacity of this room and makes it available in
+    /// exactly the given timeslots. Either everything or nothing is changed.
+    pub fn update(&mut self, name: &str, capacity: i16, timeslot_ids: &[i16], db: &Db) -> Result<()> {
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            // The error isn't chained, so that callers can detect a name
+            // which is already taken.
+            diesel::update(rooms::table.find(self.id))
+                .set((rooms::name.eq(name), rooms::capacity.eq(capacity)))
+                .execute(conn)?;
+            self.set_availability_on(timeslot_ids, conn)
+        })?;
+        self.name = name.to_owned();
+        self.capacity = capacity;
+
+        Ok(())
+    }
+
+    /// Deletes this room. Testats in this room have no room afterwards.
+    pub fn delete(self, db: &Db) -> Result<()> {
+        diesel::delete(rooms::table.find(self.id))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to delete room")?;
+
+        Ok(())
+    }
+
+    /// Returns the ids of all timeslots in which this room is available.
+    pub fn available_timeslots(&self, db: &Db) -> Result<Vec<i16>> {
+        room_availability::table
+            .filter(room_availability::room_id.eq(self.id))
+            .select(room_availability::timeslot_id)
+            .load(&*db.conn()?)
+            .chain_err(|| "failed to load availability of room")
+    }
+
+    pub fn is_available(&self, timeslot: &TimeSlot, db: &Db) -> Result<bool> {
+        room_availability::table
+            .find((self.id, timeslot.id()))
+            .count()
+            .get_result::<i64>(&*db.conn()?)
+            .map(|count| count > 0)
+            .chain_err(|| "failed to load availability of room")
+    }
+
+    /// Makes this room available in exactly the given timeslots. Ids of
+    /// timeslots of other courses are ignored.
+    fn set_availability_on(&self, timeslot_ids: &[i16], conn: &PgConnection) -> Result<()> {
+        #[derive(Insertable)]
+        #[table_name = "room_availability"]
+        struct Availability {
+            room_id: i16,
+            timeslot_id: i16,
+        }
+
+        let valid_ids = timeslots::table
+            .filter(timeslots::course_id.eq(self.course_id))
+            .filter(timeslots::id.eq_any(timeslot_ids))
+            .select(timeslots::id)
+            .load::<i16>(conn)?;
+        let new: Vec<_> = valid_ids.into_iter()
+            .map(|timeslot_id| Availability { room_id: self.id, timeslot_id })
+            .collect();
+
+        diesel::delete(room_availability::table.filter(room_availability::room_id.eq(self.id)))
+            .execute(conn)?;
+        diesel::insert(&new)
+            .into(room_availability::table)
+            .execute(conn)
+            .chain_err(|| "failed to insert availability of room")?;
+
+        Ok(())
+    }
+
+    /// Returns the number of seats taken by testats in the given timeslot:
+    /// one per student and one per tutor.
+    pub fn seats_taken(&self, timeslot: &TimeSlot, db: &Db) -> Result<u64> {
+        let tutors = testats::table
+            .filter(testats::room_id.eq(self.id))
+            .filter(testats::timeslot_id.eq(timeslot.id()))
+            .select(testats::tutor_id)
+            .load::<i64>(&*db.conn()?)?;
+
+        let mut distinct = tutors.clone();
+        distinct.sort();
+        distinct.dedup();
+
+        Ok((tutors.len() + distinct.len()) as u64)
+    }
+
+    pub fn id(&self) -> i16 {
+        self.id
+    }
+
+    pub fn course_id(&self) -> i16 {
+        self.course_id
+    }
+}
@@ -0,0 +1,173 @@
+//! The semester calendar: the lecture period and the days off within it.
+//!
+//! Timeslots only consist of a day of the week and a time. Together with the
+//! calendar, the concrete dates of all testats can be computed: testats take
+//! place every week in the lecture period, except on days off (holidays,
+//! breaks). The calendar is edited in the admin panel.
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+use diesel;
+use diesel::prelude::*;
+
+use db::Db;
+use db::schema::{semester, semester_days_off};
+use errors::*;
+use timeslot::{DayOfWeek, TimeSlot};
+
+
+/// The current semester, as stored in the database.
+#[derive(Debug, Clone, Eq, PartialEq, Queryable)]
+pub struct Semester {
+    id: bool,
+    pub name: String,
+
+    /// The first and last day on which testats take place.
+    pub lecture_start: NaiveDate,
+    pub lecture_end: NaiveDate,
+}
+
+impl Semester {
+    /// Loads the current semester. Returns `None` if no semester was set up
+    /// yet.
+    pub fn load(db: &Db) -> Result<Option<Self>> {
+        semester::table
+            .first::<Self>(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to load semester")
+    }
+
+    /// Replaces the current semester. Days off are kept.
+    pub fn set(name: &str, lecture_start: NaiveDate, lecture_end: NaiveDate, db: &Db) -> Result<Self> {
+        #[derive(Insertable)]
+        #[table_name = "semester"]
+        struct NewSemester<'a> {
+            name: &'a str,
+            lecture_start: NaiveDate,
+            lecture_end: NaiveDate,
+        }
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            diesel::delete(semester::table).execute(conn)?;
+            diesel::insert(&NewSemester { name, lecture_start, lecture_end })
+                .into(semester::table)
+                .get_result(conn)
+                .chain_err(|| "failed to insert semester")
+        })
+    }
+}
+
+/// A period without testats. Both days are included.
+#[derive(Debug, Clone, Eq, PartialEq, Identifiable, Queryable)]
+#[table_name = "semester_days_off"]
+pub struct DayOff {
+    id: i32,
+    pub first_day: NaiveDate,
+    pub last_day: NaiveDate,
+    pub description: String,
+}
+
+impl DayOff {
+    /// Loads all days off, ordered by date.
+    pub fn load_all(db: &Db) -> Result<Vec<Self>> {
+        semester_days_off::table
+            .order(semester_days_off::first_day.asc())
+            .load(&*db.conn()?)
+            .chain_err(|| "failed to load days off")
+    }
+
+    pub fn create(first_day: NaiveDate, last_day: NaiveDate, description: &str, db: &Db) -> Result<Self> {
+        #[derive(Insertable)]
+        #[table_name = "semester_days_off"]
+        struct NewDayOff<'a> {
+            first_day: NaiveDate,
+            last_day: NaiveDate,
+            description: &'a str,
+        }
+
+        diesel::insert(&NewDayOff { first_day, last_day, description })
+            .into(semester_days_off::table)
+            .get_result(&*db.conn()?)
+            .chain_err(|| "failed to insert day off")
+    }
+
+    /// Deletes the day off with the given id. Returns `false` if it doesn't
+    /// exist.
+    pub fn delete(id: i32, db: &Db) -> Result<bool> {
+        diesel::delete(semester_days_off::table.find(id))
+            .execute(&*db.conn()?)
+            .map(|count| count > 0)
+            .chain_err(|| "failed to delete day off")
+    }
+
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.first_day <= date && date <= self.last_day
+    }
+}
+
+/// The semester together with its days off.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    pub semester: Semester,
+    pub days_off: Vec<DayOff>,
+}
+
+impl Calendar {
+    /// Loads the calendar. Returns `None` if no semester was set up yet.
+    pub fn load(db: &Db) -> Result<Option<Self>> {
+        match Semester::load(db)? {
+            Some(semester) => Ok(Some(Calendar {
+                semester,
+                days_off: DayOff::load_all(db)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the day off the given date lies in, if any.
+    pub fn day_off(&self, date: NaiveDate) -> Option<&DayOff> {
+        self.days_off.iter().find(|d| d.contains(date))
+    }
+
+    /// Returns all dates in the lecture period which are the given day of
+    /// the week, including days off.
+    pub fn all_on(&self, day: DayOfWeek) -> Vec<NaiveDate> {
+        let weekday: Weekday = day.into();
+        let mut date = self.semester.lecture_start;
+        while date.weekday() != weekday {
+            date = date.succ();
+        }
+
+        let mut out = Vec::new();
+        while date <= self.semester.lecture_end {
+            out.push(date);
+            date = date + Duration::weeks(1);
+        }
+
+        out
+    }
+
+    /// Returns the dates and times of all testats in the given timeslot,
+    /// without those on days off.
+    pub fn dates_of(&self, timeslot: &TimeSlot) -> Vec<NaiveDateTime> {
+        let time = timeslot.time().as_naive_time();
+
+        self.all_on(timeslot.day())
+            .into_iter()
+            .filter(|date| self.day_off(*date).is_none())
+            .map(|date| date.and_time(time))
+            .collect()
+    }
+
+    /// Returns the first testat in the given timeslot starting at or after
+    /// `now`. Returns `None` if there are no more testats this semester.
+    pub fn next_date_of(&self, timeslot: &TimeSlot, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.dates_of(timeslot)
+            .into_iter()
+            .find(|date| *date >= now)
+    }
+}
@@ -1,12 +1,13 @@
 use rocket::response::{Flash, Redirect};
-use rocket::Request;
-use rocket::http::Cookie;
+use rocket::{Request, State};
+use rocket::http::{Cookie, Method};
 
 use config;
+use db::Db;
 use dict::{self, Locale};
 use errors::*;
+use login::Session;
 use template::{FlashBubble, Page};
-use user::AuthUser;
 
 
 /// Catcher for 403 Forbidden.
@@ -15,23 +16,39 @@ use user::AuthUser;
 /// otherwise.
 #[error(403)]
 fn unauthorized(req: &Request) -> StdResult<Flash<Redirect>, Page> {
-    if req.guard::<AuthUser>().is_success() {
+    // We don't use the `AuthUser` guard here, since it also fails for
+    // impersonation sessions trying to change something.
+    let db = req.guard::<State<Db>>().expect("cannot retrieve DB connection from request");
+    let auth_user = Session::from_cookies(req.cookies(), &db).ok().and_then(|u| u);
+
+    if let Some(auth_user) = auth_user {
         // In this case a login IS present, but it lacks the permissions to do
         // something. In this case it doesn't make sense to forward to the
         // login page. We will show an error instead.
         let locale = req.guard::<Locale>().unwrap();
+        let dict = dict::new(locale);
+        let flash = if auth_user.session().is_impersonation() {
+            FlashBubble::error(dict.impersonation_read_only_flash())
+        } else {
+            FlashBubble::error(dict.forbidden_flash())
+        };
 
         Page::empty()
-            .add_flash(FlashBubble::error(dict::new(locale).forbidden_flash()))
+            .add_flash(flash)
             .make_err()
     } else {
         // In this case, there is no login present. We will forward to the
-        // login page.
-        let uri = req.uri().as_str().to_owned();
-        let cookie = Cookie::build(config::INITIAL_REQ_COOKIE_NAME, uri)
-            .path("/")
-            .finish();
-        req.cookies().add(cookie);
+        // login page. We remember the requested page in order to redirect
+        // back to it after the login. This only makes sense for `GET`
+        // requests, since the redirect will always be a `GET` request.
+        if req.method() == Method::Get {
+            let uri = req.uri().as_str().to_owned();
+            let cookie = Cookie::build(config::INITIAL_REQ_COOKIE_NAME, uri)
+                .path("/")
+                .http_only(true)
+                .finish();
+            req.cookies().add(cookie);
+        }
 
         Ok(Flash::error(
             Redirect::to("/login"),
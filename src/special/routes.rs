@@ -4,10 +4,12 @@ use rocket::{Config, State};
 use rocket::response::{NamedFile, Redirect};
 
 use db::Db;
+use dict::{self, Locale};
 use errors::*;
 use state::{AppState, CurrentAppState};
-use template::Page;
-use user::{AuthUser, Role};
+use template::{FlashBubble, Page};
+use user::AuthUser;
+use user::permission::Permission;
 
 
 /// The index page.
@@ -15,20 +17,34 @@ use user::{AuthUser, Role};
 /// This handler will always redirect instead of generating a response itself
 /// (except in the error case).
 #[get("/")]
-pub fn index(auth_user: AuthUser, db: State<Db>) -> Result<StdResult<Redirect, Page>> {
-    let app_state = CurrentAppState::load(&db)?;
+pub fn index(auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<StdResult<Redirect, Page>> {
+    let sees_admin_panel = auth_user.has_permission(Permission::ViewAdminPanel, &db)?;
 
-    // Redirect to the correct route depending on user role and app state.
-    match (auth_user.role(), app_state.state) {
+    // Users who aren't a member of any course can't do anything yet.
+    let app_state = match auth_user.course() {
+        Some(course) => CurrentAppState::load(course, &db)?,
+        None if sees_admin_panel => return Ok(Ok(Redirect::to("/admin_panel"))),
+        None => {
+            let msg = dict::new(locale).course.no_course();
+            return Ok(Err(Page::empty().add_flash(FlashBubble::info(msg))));
+        }
+    };
+
+    // Redirect to the correct route depending on user permissions and app
+    // state.
+    match (sees_admin_panel, app_state.state) {
         // Preparation state
         (_, AppState::Preparation) => Ok(Redirect::to("/prep")),
 
         // Frozen state: admins are redirected to the admin panel, all others
         // see a empty page with a flash bubble talking about the state.
-        (Role::Admin, AppState::Frozen) => Ok(Redirect::to("/admin_panel")),
-        (_, AppState::Frozen) => Err(Page::empty()),
+        (true, AppState::Frozen) => Ok(Redirect::to("/admin_panel")),
+        (false, AppState::Frozen) => Err(Page::empty()),
 
-        _ => Err(Page::unimplemented()),
+        // Running state: admins manage the app, everyone else sees their
+        // testats.
+        (true, AppState::Running) => Ok(Redirect::to("/admin_panel")),
+        (false, AppState::Running) => Ok(Redirect::to("/testats")),
     }.make_ok()
 }
 
@@ -8,6 +8,8 @@
 //!   at the beginning of the semester.
 //! - `Running`: when all time slots and partners/tutors are assigned. This is
 //!   active during the semester.
+//!
+//! Each course has its own state.
 
 use chrono::DateTime;
 use chrono::offset::Utc;
@@ -18,8 +20,10 @@ use rocket::{Outcome, State};
 use rocket::request::{self, FromRequest, Request};
 
 
+use course::Course;
 use errors::*;
 use db::Db;
+use user::AuthUser;
 
 /// All possible states of the app
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -33,19 +37,21 @@ pub enum AppState {
 }
 
 
-/// Represents the current application state, as stored in the database.
+/// Represents the current application state of a course, as stored in the
+/// database.
 #[derive(Debug, Clone, Eq, PartialEq, Queryable)]
 pub struct CurrentAppState {
-    id: bool,
     pub state: AppState,
     pub reason: Option<String>,
     pub next_state_switch: Option<DateTime<Utc>>,
+    course_id: i16,
 }
 
 impl CurrentAppState {
-    /// Loads the current app state from the database.
-    pub fn load(db: &Db) -> Result<Self> {
+    /// Loads the current app state of the given course from the database.
+    pub fn load(course: &Course, db: &Db) -> Result<Self> {
         current_app_state::table
+            .find(course.id())
             .first::<Self>(&*db.conn()?)?
             .make_ok()
     }
@@ -54,14 +60,15 @@ impl CurrentAppState {
         self.reason.as_ref().map(AsRef::as_ref)
     }
 
-    /// Sets the current app state to the given values.
+    /// Sets the current app state of the given course to the given values.
     pub fn set(
+        course: &Course,
         state: AppState,
         reason: Option<String>,
         next_state_switch: Option<DateTime<Utc>>,
         db: &Db,
     ) -> Result<Self> {
-        diesel::update(current_app_state::table.find(true))
+        diesel::update(current_app_state::table.find(course.id()))
             .set((
                 current_app_state::columns::state.eq(&state),
                 current_app_state::columns::reason.eq(&reason),
@@ -75,7 +82,8 @@ impl CurrentAppState {
 
 macro_rules! state_req_guard {
     ($name:ident, $variant:ident) => {
-        /// A request guard to ensure the app is in a specific state.
+        /// A request guard to ensure the current course of the user is in a
+        /// specific state.
         pub struct $name(pub CurrentAppState);
 
         impl<'a, 'r> FromRequest<'a, 'r> for $name {
@@ -83,7 +91,16 @@ macro_rules! state_req_guard {
 
             fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
                 let db = req.guard::<State<Db>>().expect("cannot retrieve DB connection from request");
-                let app_state = CurrentAppState::load(&db);
+                let auth_user = match req.guard::<AuthUser>() {
+                    Outcome::Success(auth_user) => auth_user,
+                    _ => return Outcome::Forward(()),
+                };
+                let course = match auth_user.course() {
+                    Some(course) => course,
+                    None => return Outcome::Forward(()),
+                };
+
+                let app_state = CurrentAppState::load(course, &db);
                 if let Ok(app_state) = app_state {
                     if app_state.state == AppState::$variant {
                         return Outcome::Success($name(app_state));
@@ -3,14 +3,18 @@
 use std::borrow::Cow;
 use maud::{html, DOCTYPE, Markup, Render};
 use option_filter::OptionFilterExt;
-use rocket::Request;
+use rocket::{Request, State};
 use rocket::request::FlashMessage;
 use rocket::response::{self, Responder};
 
+use announcement::Announcement;
 use config;
+use db::Db;
 use dict::{self, Locale};
+use notify::inbox::InboxEntry;
 use state::FrozenState;
 use user::AuthUser;
+use user::permission::Permission;
 
 
 /// Builder type, used to render the whole page as HTML.
@@ -142,8 +146,46 @@ impl Page {
             self.flashes.insert(0, flash);
         }
 
+        // Try to create an auth user from the request.
+        let auth_user = req.guard::<AuthUser>().succeeded();
 
+        // Show all announcements for this user which are not dismissed yet.
+        if let Some(ref auth_user) = auth_user {
+            let db = req.guard::<State<Db>>().unwrap();
+            let announcements = Announcement::load_active_for(auth_user, auth_user.course_role(), &db)
+                .unwrap_or_default();
+            for announcement in announcements {
+                self.flashes.push(FlashBubble::info(html! {
+                    (announcement.render())
+                    form
+                        action={"/announcements/" (announcement.id()) "/dismiss"}
+                        method="post"
+                    {
+                        input type="hidden" name="redirect" value=(req.uri().as_str());
+                        input
+                            type="submit"
+                            class="c-button c-button--info u-xsmall"
+                            value=(dict.announcement.dismiss());
+                    }
+                }));
+            }
+        }
+        let show_admin_panel = match auth_user {
+            Some(ref u) => {
+                let db = req.guard::<State<Db>>().unwrap();
+                u.has_permission(Permission::ViewAdminPanel, &db).unwrap_or(false)
+            }
+            None => false,
+        };
 
+        // The number of unread notifications, shown as badge in the nav bar.
+        let unread_notifications = match auth_user {
+            Some(ref u) => {
+                let db = req.guard::<State<Db>>().unwrap();
+                InboxEntry::count_unread(u, &db).unwrap_or(0)
+            }
+            None => 0,
+        };
 
         html! { (DOCTYPE) html {
             // ===============================================================
@@ -174,7 +216,22 @@ impl Page {
             // Start <body>
             // ===============================================================
             body class="c-text" {
-                header (self.render_nav(req, locale))
+                header (self.render_nav(auth_user.as_ref(), show_admin_panel, unread_notifications, locale))
+
+                // While an admin impersonates a user, this is always shown.
+                @if let Some(auth_user) = auth_user.as_ref().filter(|u| u.session().is_impersonation()) {
+                    div class="c-alert c-alert--warning impersonation-banner" {
+                        (dict.impersonation_banner(auth_user.username()))
+                        " "
+                        form action="/impersonation/end" method="post" style="display: inline" {
+                            input
+                                type="submit"
+                                class="c-button c-button--warning u-xsmall"
+                                value=(dict.impersonation_end());
+                        }
+                    }
+                }
+
                 main class="o-container o-container--large u-pillar-box--small" {
                     // Show all flashes
                     div class="u-letter-box--small" {
@@ -197,17 +254,20 @@ impl Page {
         } }
     }
 
-    fn render_nav(&self, req: &Request, locale: Locale) -> Markup {
+    fn render_nav(
+        &self,
+        auth_user: Option<&AuthUser>,
+        show_admin_panel: bool,
+        unread_notifications: i64,
+        locale: Locale,
+    ) -> Markup {
         let (title_fg, title_border) = title_colors();
         let dict = dict::new(locale);
 
-        // Try to create an auth user from the request.
-        let auth_user = req.guard::<AuthUser>().succeeded();
-
-        // Add "Admin Panel" nav item if the user is an admin
+        // Add "Admin Panel" nav item if the user may see it
         // TODO: l10n
         let mut nav_items = self.nav_items.clone();
-        if auth_user.as_ref().filter(|u| u.is_admin()).is_some() {
+        if show_admin_panel {
             nav_items.push(NavItem::new("Admin Panel", "/admin_panel"));
         }
 
@@ -268,6 +328,47 @@ impl Page {
                             }
                         }
                     }
+
+                    // Link to the inbox with the number of unread
+                    // notifications
+                    a
+                        class={
+                            "c-nav__item c-nav__item--right"
+                            @if self.active_nav_route.as_ref().map_or(false, |r| r == "/inbox") {
+                                " c-nav__item--active"
+                            } @else {
+                                ""
+                            }
+                        }
+                        href="/inbox"
+                        title=(dict.nav_inbox())
+                    {
+                        i class="fa fa-bell" {}
+                        @if unread_notifications > 0 {
+                            " "
+                            span class="c-badge c-badge--rounded c-badge--error" (unread_notifications)
+                        }
+                    }
+
+                    // The current course, links to the list of all courses
+                    // to switch to another one.
+                    a
+                        class={
+                            "c-nav__item c-nav__item--right"
+                            @if self.active_nav_route.as_ref().map_or(false, |r| r == "/courses") {
+                                " c-nav__item--active"
+                            } @else {
+                                ""
+                            }
+                        }
+                        href="/courses"
+                        title=(dict.nav_courses())
+                    {
+                        i class="fa fa-book" {}
+                        @if let Some(course) = auth_user.course() {
+                            " " (course.name)
+                        }
+                    }
                 }
             }
         }
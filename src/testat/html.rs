@@ -0,0 +1,106 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use maud::{html, Markup};
+
+use dict::{self, Locale};
+use semester::Calendar;
+use timeslot::DayOfWeek;
+use super::WeeklyTestat;
+
+
+/// The testats of a user, each with its next date.
+pub fn overview(
+    locale: Locale,
+    testats: &[(WeeklyTestat, Option<NaiveDateTime>)],
+    semester: Option<&Calendar>,
+    today: NaiveDate,
+) -> Markup {
+    let dict = dict::new(locale).testat;
+    let format_date = |date: NaiveDateTime| {
+        let day = DayOfWeek::from(date.weekday());
+        format!("{}, {}", day.short_name(locale), date.format("%d.%m.%Y %H:%M"))
+    };
+    let next = testats.iter().filter_map(|&(_, next)| next).min();
+
+    html! {
+        h1 (dict.overview_title())
+
+        @if let Some(semester) = semester {
+            p {
+                (semester.semester.name) ": "
+                (dict.lecture_period(
+                    &semester.semester.lecture_start.format("%d.%m.%Y").to_string(),
+                    &semester.semester.lecture_end.format("%d.%m.%Y").to_string(),
+                ))
+            }
+        } @else {
+            p { i (dict.no_semester()) }
+        }
+
+        @if testats.is_empty() {
+            p { i (dict.no_testats()) }
+        } @else {
+            @if let Some(next) = next {
+                p {
+                    (dict.next_testat()) ": "
+                    strong (format_date(next))
+                }
+            }
+
+            table class="c-table" {
+                thead class="c-table__head" {
+                    tr class="c-table__row c-table__row--heading" {
+                        th class="c-table__cell" (dict.timeslot())
+                        th class="c-table__cell" (dict.tutor())
+                        th class="c-table__cell" (dict.students())
+                        th class="c-table__cell" (dict.room())
+                        th class="c-table__cell" (dict.next_date())
+                    }
+                }
+                tbody class="c-table__body" {
+                    @for &(ref testat, next) in testats {
+                        tr class="c-table__row" {
+                            td class="c-table__cell" {
+                                (testat.timeslot.day().full_name(locale)) ", "
+                                (testat.timeslot.time())
+                            }
+                            td class="c-table__cell" {
+                                (testat.tutor.name().unwrap_or(testat.tutor.username()))
+                            }
+                            td class="c-table__cell" {
+                                (testat.students.iter()
+                                    .map(|s| s.name().unwrap_or(s.username()))
+                                    .collect::<Vec<_>>()
+                                    .join(", "))
+                            }
+                            td class="c-table__cell" {
+                                (testat.room.as_ref().map_or("–", |r| r.name.as_str()))
+                            }
+                            td class="c-table__cell" {
+                                (next.map(&format_date).unwrap_or_else(|| "–".into()))
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        @if let Some(semester) = semester {
+            @if semester.days_off.iter().any(|d| d.last_day >= today) {
+                h2 (dict.days_off_headline())
+                ul {
+                    @for day_off in semester.days_off.iter().filter(|d| d.last_day >= today) {
+                        li {
+                            (day_off.first_day.format("%d.%m.%Y"))
+                            @if day_off.last_day != day_off.first_day {
+                                " – " (day_off.last_day.format("%d.%m.%Y"))
+                            }
+                            ": " (day_off.description)
+                        }
+                    }
+                }
+            }
+        }
+
+        p a href="/settings" (dict.subscribe_hint())
+    }
+}
@@ -0,0 +1,248 @@
+//! Calendar feeds of the testat schedule (iCalendar, RFC 5545).
+//!
+//! Every user can create a secret link to their feed on the settings page and
+//! subscribe to it with a calendar app. The app regularly fetches the feed,
+//! so changes of the schedule show up automatically.
+//!
+//! Each weekly testat is one recurring event from the first testat in the
+//! lecture period until its end; days off are excluded (see `semester`).
+//! Times are "floating" (they have no timezone), so calendar apps show them
+//! in the local time of the device, which is what we want for testats taking
+//! place in one building.
+
+use chrono::{Duration, NaiveDateTime, NaiveTime, Utc};
+use diesel;
+use diesel::prelude::*;
+use hex;
+use rand::{self, Rng};
+
+use config;
+use db::Db;
+use db::schema::calendar_tokens;
+use dict::{self, Locale};
+use errors::*;
+use semester::Calendar;
+use user::User;
+use super::WeeklyTestat;
+
+
+/// Returns the token of the calendar feed of the given user, if they created
+/// one.
+pub fn token_of(user: &User, db: &Db) -> Result<Option<String>> {
+    calendar_tokens::table
+        .find(user.id())
+        .select(calendar_tokens::token)
+        .first(&*db.conn()?)
+        .optional()
+        .chain_err(|| "failed to load calendar token")
+}
+
+/// Creates a new token for the calendar feed of the given user. The old
+/// token (and thus the old link) becomes invalid.
+pub fn create_token(user: &User, db: &Db) -> Result<String> {
+    #[derive(Insertable)]
+    #[table_name = "calendar_tokens"]
+    struct NewToken<'a> {
+        user_id: i64,
+        token: &'a str,
+    }
+
+    let mut bytes = [0u8; config::CALENDAR_TOKEN_LEN];
+    let mut rng = rand::os::OsRng::new()
+        .chain_err(|| "Unable to use system RNG")?;
+    rng.fill_bytes(&mut bytes);
+    let token = hex::encode(&bytes);
+
+    let conn = &*db.conn()?;
+    conn.transaction::<_, Error, _>(|| {
+        diesel::delete(calendar_tokens::table.find(user.id())).execute(conn)?;
+        diesel::insert(&NewToken { user_id: user.id(), token: &token })
+            .into(calendar_tokens::table)
+            .execute(conn)
+            .chain_err(|| "failed to insert calendar token")?;
+
+        Ok(())
+    })?;
+
+    Ok(token)
+}
+
+/// Returns the user the given calendar token belongs to.
+pub fn user_of_token(token: &str, db: &Db) -> Result<Option<User>> {
+    let user_id = calendar_tokens::table
+        .filter(calendar_tokens::token.eq(token))
+        .select(calendar_tokens::user_id)
+        .first::<i64>(&*db.conn()?)
+        .optional()?;
+
+    match user_id {
+        Some(id) => User::load_by_id(id, db),
+        None => Ok(None),
+    }
+}
+
+/// Generates the calendar with all given testats. Without a semester
+/// calendar, it is empty.
+pub fn calendar(testats: &[WeeklyTestat], semester: Option<&Calendar>, locale: Locale) -> String {
+    let dict = dict::new(locale).testat;
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_owned(),
+        "VERSION:2.0".to_owned(),
+        "PRODID:-//luten//testats//EN".to_owned(),
+        "CALSCALE:GREGORIAN".to_owned(),
+        format!("X-WR-CALNAME:{}", escape(config::WEBSITE_TITLE)),
+    ];
+
+    if let Some(semester) = semester {
+        let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        for testat in testats {
+            let day = testat.timeslot.day();
+            let first = match semester.dates_of(&testat.timeslot).first() {
+                Some(date) => *date,
+                None => continue,
+            };
+            let end = first + Duration::minutes(testat.timeslot.duration().into());
+
+            let students = testat.students.iter()
+                .map(|s| s.name().unwrap_or(s.username()))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let description = dict.event_description(
+                testat.tutor.name().unwrap_or(testat.tutor.username()),
+                &students,
+            );
+
+            lines.push("BEGIN:VEVENT".into());
+            lines.push(format!(
+                "UID:testat-{}-{}@luten",
+                testat.timeslot.id(),
+                testat.tutor.id(),
+            ));
+            lines.push(format!("DTSTAMP:{}", now));
+            lines.push(format!("DTSTART:{}", date_time(first)));
+            lines.push(format!("DTEND:{}", date_time(end)));
+            lines.push(format!(
+                "RRULE:FREQ=WEEKLY;UNTIL={}",
+                date_time(semester.semester.lecture_end.and_time(NaiveTime::from_hms(23, 59, 59))),
+            ));
+
+            let excluded = semester.all_on(day).into_iter()
+                .filter(|date| *date > first.date() && semester.day_off(*date).is_some())
+                .map(|date| date_time(date.and_time(first.time())))
+                .collect::<Vec<_>>();
+            if !excluded.is_empty() {
+                lines.push(format!("EXDATE:{}", excluded.join(",")));
+            }
+
+            lines.push(format!("SUMMARY:{}", escape(&dict.event_summary())));
+            if let Some(ref room) = testat.room {
+                lines.push(format!("LOCATION:{}", escape(&room.name)));
+            }
+            lines.push(format!("DESCRIPTION:{}", escape(&description)));
+            lines.push("END:VEVENT".into());
+        }
+    }
+
+    lines.push("END:VCALENDAR".into());
+
+    let mut out = String::new();
+    for line in lines {
+        fold(&line, &mut out);
+    }
+    out
+}
+
+/// Formats a floating date-time value.
+fn date_time(date_time: NaiveDateTime) -> String {
+    date_time.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Escapes a text value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Appends the given content line to `out`, terminated by CRLF. Lines longer
+/// than 75 bytes are folded (continued on the next line after a space),
+/// without splitting UTF-8 characters.
+fn fold(line: &str, out: &mut String) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn folded(line: &str) -> String {
+        let mut out = String::new();
+        fold(line, &mut out);
+        out
+    }
+
+    /// Checks that no line of `out` is longer than 75 octets and that
+    /// unfolding yields `line` again.
+    fn check_folding(line: &str, out: &str) {
+        assert!(out.ends_with("\r\n"));
+        for physical in out[..out.len() - 2].split("\r\n") {
+            assert!(physical.len() <= 75, "{:?}", physical);
+        }
+        assert_eq!(out[..out.len() - 2].replace("\r\n ", ""), line);
+    }
+
+    #[test]
+    fn short_lines_are_not_folded() {
+        assert_eq!(folded("BEGIN:VCALENDAR"), "BEGIN:VCALENDAR\r\n");
+
+        let line = "a".repeat(75);
+        assert_eq!(folded(&line), format!("{}\r\n", line));
+    }
+
+    #[test]
+    fn long_lines_are_folded() {
+        let line = "a".repeat(76);
+        assert_eq!(folded(&line), format!("{}\r\n a\r\n", "a".repeat(75)));
+
+        let line = format!("SUMMARY:{}", "Testat ".repeat(40));
+        let out = folded(&line);
+        assert_eq!(out.matches("\r\n ").count(), 3);
+        check_folding(&line, &out);
+    }
+
+    #[test]
+    fn multibyte_characters_are_not_split() {
+        // "ä" has two and "€" has three octets; neither fits into the 75th
+        // octet of the line.
+        let line = format!("{}ä{}", "a".repeat(74), "b".repeat(10));
+        assert_eq!(folded(&line), format!("{}\r\n ä{}\r\n", "a".repeat(74), "b".repeat(10)));
+
+        let line = format!("{}€", "a".repeat(73));
+        assert_eq!(folded(&line), format!("{}\r\n €\r\n", "a".repeat(73)));
+
+        let line = format!("LOCATION:{}", "Hörsaal für Übungen – ".repeat(10));
+        check_folding(&line, &folded(&line));
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("Testat"), "Testat");
+        assert_eq!(escape("Raum 1, Gebäude 50.34"), "Raum 1\\, Gebäude 50.34");
+        assert_eq!(escape("Tutor: Erika; Raum: -101"), "Tutor: Erika\\; Raum: -101");
+        assert_eq!(escape("Zeile 1\nZeile 2"), "Zeile 1\\nZeile 2");
+        assert_eq!(escape("C:\\Pfad\\n"), "C:\\\\Pfad\\\\n");
+    }
+}
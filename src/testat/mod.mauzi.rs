@@ -0,0 +1,68 @@
+// ===========================================================================
+// Overview
+// ===========================================================================
+unit overview_title {
+    De => "Deine Testate",
+    En => "Your testats",
+}
+unit lecture_period(start: &str, end: &str) {
+    De => "Vorlesungszeit vom {start} bis zum {end}",
+    En => "Lecture period from {start} to {end}",
+}
+unit no_semester {
+    De => "Die Termine des Semesters stehen noch nicht fest.",
+    En => "The dates of the semester haven't been set yet.",
+}
+unit no_testats {
+    De => "Du hast keine Testate.",
+    En => "You don't have any testats.",
+}
+unit next_testat {
+    De => "Nächstes Testat",
+    En => "Next testat",
+}
+unit timeslot {
+    De => "Zeit",
+    En => "Time",
+}
+unit tutor {
+    De => "Tutor",
+    En => "Tutor",
+}
+unit students {
+    De => "Studenten",
+    En => "Students",
+}
+unit room {
+    De => "Raum",
+    En => "Room",
+}
+unit next_date {
+    De => "Nächster Termin",
+    En => "Next date",
+}
+unit days_off_headline {
+    De => "Tage ohne Testate",
+    En => "Days without testats",
+}
+unit subscribe_hint {
+    De => "Du kannst deine Testate in einer Kalender-App abonnieren (siehe Einstellungen).",
+    En => "You can subscribe to your testats with a calendar app (see settings).",
+}
+
+
+// ===========================================================================
+// Calendar feed
+// ===========================================================================
+unit event_summary {
+    De => "Testat",
+    En => "Testat",
+}
+unit event_description(tutor: &str, students: &str) {
+    De => "Tutor: {tutor}\nStudenten: {students}",
+    En => "Tutor: {tutor}\nStudents: {students}",
+}
+unit flash_calendar_link_created {
+    De => "Ein neuer Kalender-Link wurde erstellt. Alte Links funktionieren nicht mehr.",
+    En => "A new calendar link was created. Old links don't work anymore.",
+}
@@ -0,0 +1,147 @@
+//! The schedule of testats. **Has routes.**
+//!
+//! Every student has their testat once a week in one timeslot with one tutor
+//! (and possibly with their partner). Students and tutors can subscribe to
+//! their schedule with a calendar app, see `ical`.
+
+use std::collections::BTreeMap;
+
+use diesel;
+use diesel::prelude::*;
+
+use db::Db;
+use db::schema::{rooms, testats, timeslots, users};
+use errors::*;
+use room::Room;
+use timeslot::TimeSlot;
+use user::{Student, Tutor, User};
+
+mod html;
+pub mod ical;
+pub mod routes;
+
+
+/// The weekly testat of one student.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Identifiable, Insertable, Queryable)]
+#[table_name = "testats"]
+#[primary_key(student_id, course_id)]
+pub struct Testat {
+    pub student_id: i64,
+    pub tutor_id: i64,
+    pub timeslot_id: i16,
+    pub course_id: i16,
+    pub room_id: Option<i16>,
+}
+
+impl Testat {
+    /// Assigns the given student to the given tutor, timeslot and room. A
+    /// previous assignment of the student in the course of the timeslot is
+    /// replaced.
+    ///
+    /// Fails if the room is not available in the timeslot or has not enough
+    /// free seats (see `room`).
+    pub fn assign(
+        student: &Student,
+        tutor: &Tutor,
+        timeslot: &TimeSlot,
+        room: Option<&Room>,
+        db: &Db,
+    ) -> Result<Self> {
+        if let Some(room) = room {
+            if room.course_id() != timeslot.course_id() || !room.is_available(timeslot, db)? {
+                bail!("room '{}' is not available in timeslot #{}", room.name, timeslot.id());
+            }
+
+            // The tutors of all other testats in the room at that time and
+            // the new one.
+            let mut tutors = testats::table
+                .filter(testats::room_id.eq(room.id()))
+                .filter(testats::timeslot_id.eq(timeslot.id()))
+                .filter(testats::student_id.ne(student.id()))
+                .select(testats::tutor_id)
+                .load::<i64>(&*db.conn()?)?;
+            tutors.push(tutor.id());
+            let num_students = tutors.len();
+            tutors.sort();
+            tutors.dedup();
+
+            if num_students + tutors.len() > room.capacity as usize {
+                bail!("room '{}' has no free seats left in timeslot #{}", room.name, timeslot.id());
+            }
+        }
+
+        let new = Testat {
+            student_id: student.id(),
+            tutor_id: tutor.id(),
+            timeslot_id: timeslot.id(),
+            course_id: timeslot.course_id(),
+            room_id: room.map(|r| r.id()),
+        };
+
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            diesel::delete(testats::table.find((student.id(), timeslot.course_id()))).execute(conn)?;
+            diesel::insert(&new)
+                .into(testats::table)
+                .get_result(conn)
+                .chain_err(|| "failed to insert testat")
+        })
+    }
+}
+
+/// A weekly testat as seen by one of its participants: the timeslot, the
+/// tutor and all students.
+#[derive(Debug, Clone)]
+pub struct WeeklyTestat {
+    pub timeslot: TimeSlot,
+    pub tutor: User,
+    pub students: Vec<User>,
+
+    /// `None` if no room was assigned yet.
+    pub room: Option<Room>,
+}
+
+impl WeeklyTestat {
+    /// Loads all testats the given user takes part in, as student or tutor, in
+    /// all courses. Ordered by timeslot.
+    pub fn load_for(user: &User, db: &Db) -> Result<Vec<Self>> {
+        let conn = &*db.conn()?;
+
+        let own = testats::table
+            .filter(testats::student_id.eq(user.id()).or(testats::tutor_id.eq(user.id())))
+            .load::<Testat>(conn)?;
+
+        // Group all students by timeslot and tutor.
+        let mut groups = BTreeMap::new();
+        for testat in own {
+            if groups.contains_key(&(testat.timeslot_id, testat.tutor_id)) {
+                continue;
+            }
+
+            let student_ids = testats::table
+                .filter(testats::timeslot_id.eq(testat.timeslot_id))
+                .filter(testats::tutor_id.eq(testat.tutor_id))
+                .select(testats::student_id)
+                .load::<i64>(conn)?;
+            let students = users::table
+                .filter(users::id.eq_any(student_ids))
+                .order(users::username.asc())
+                .load::<User>(conn)?;
+            groups.insert((testat.timeslot_id, testat.tutor_id), (students, testat.room_id));
+        }
+
+        let mut out = Vec::new();
+        for ((timeslot_id, tutor_id), (students, room_id)) in groups {
+            let timeslot = timeslots::table.find(timeslot_id).first::<TimeSlot>(conn)?;
+            let tutor = users::table.find(tutor_id).first::<User>(conn)?;
+            let room = match room_id {
+                Some(id) => rooms::table.find(id).first::<Room>(conn).optional()?,
+                None => None,
+            };
+            out.push(WeeklyTestat { timeslot, tutor, students, room });
+        }
+        out.sort_by(|a, b| a.timeslot.cmp(&b.timeslot));
+
+        Ok(out)
+    }
+}
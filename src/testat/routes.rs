@@ -0,0 +1,80 @@
+use chrono::Local;
+use rocket::State;
+use rocket::http::ContentType;
+use rocket::response::{Flash, Redirect};
+use rocket::response::content::Content;
+
+use db::Db;
+use dict::{self, Locale};
+use errors::*;
+use notify;
+use semester::Calendar;
+use super::WeeklyTestat;
+use super::{html, ical};
+use template::{NavItem, Page};
+use user::AuthUser;
+
+
+/// The testats of the user with their next dates.
+#[get("/testats")]
+pub fn overview(auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<Page> {
+    let semester = Calendar::load(&db)?;
+    let now = Local::now().naive_local();
+
+    let testats = WeeklyTestat::load_for(&auth_user, &db)?
+        .into_iter()
+        .map(|testat| {
+            let next = semester.as_ref().and_then(|s| s.next_date_of(&testat.timeslot, now));
+            (testat, next)
+        })
+        .collect::<Vec<_>>();
+
+    let title = dict::new(locale).testat.overview_title();
+    Page::empty()
+        .with_title(title.clone())
+        .add_nav_items(vec![NavItem::new(title, "/testats")])
+        .with_active_nav_route("/testats")
+        .with_content(html::overview(locale, &testats, semester.as_ref(), now.date()))
+        .make_ok()
+}
+
+
+/// The calendar feed of a user. The file name is the secret token of the
+/// user with the extension `.ics`, as some calendar apps insist on it.
+#[get("/calendar/<file>")]
+pub fn calendar(file: String, db: State<Db>) -> Result<Option<Content<String>>> {
+    if !file.ends_with(".ics") {
+        return Ok(None);
+    }
+    let token = file.trim_right_matches(".ics");
+
+    let user = match ical::user_of_token(token, &db)? {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+
+    // Calendar apps don't tell us the language of the user.
+    let locale = notify::locale_of(&user, &*db.conn()?)?;
+    let testats = WeeklyTestat::load_for(&user, &db)?;
+    let semester = Calendar::load(&db)?;
+
+    Ok(Some(Content(
+        ContentType::new("text", "calendar"),
+        ical::calendar(&testats, semester.as_ref(), locale),
+    )))
+}
+
+/// Creates a new link to the calendar feed. An existing link becomes invalid.
+#[post("/settings/calendar")]
+pub fn create_calendar_token(
+    auth_user: AuthUser,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    ical::create_token(&auth_user, &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/settings"),
+        dict::new(locale).testat.flash_calendar_link_created(),
+    ))
+}
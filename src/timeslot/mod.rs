@@ -7,6 +7,7 @@ use diesel;
 use diesel::prelude::*;
 
 use config;
+use course::Course;
 use dict::{self, Locale};
 use db::Db;
 use db::schema::timeslots;
@@ -97,22 +98,39 @@ impl fmt::Display for DayOfWeek {
 pub struct NewTimeSlot {
     day: DayOfWeek,
     time: NaiveTime,
+    course_id: i16,
+    duration: i16,
 }
 
 impl NewTimeSlot {
-    pub fn new(day: DayOfWeek, time: Time) -> Self {
+    /// Creates a new timeslot starting at `time` which is `duration` minutes
+    /// long (see `parse_duration()`).
+    pub fn new(course: &Course, day: DayOfWeek, time: Time, duration: u16) -> Self {
         Self {
             day,
             time: time.0,
+            course_id: course.id(),
+            duration: duration as i16,
         }
     }
+
+    /// Returns whether this new timeslot overlaps the given one.
+    pub fn overlaps(&self, other: &TimeSlot) -> bool {
+        let start = Time(self.time);
+        let end = start.next(self.duration as u16);
+
+        self.day == other.day() && start < other.end() && other.time() < end
+    }
 }
 
+/// A weekly timeslot of a course.
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, Queryable)]
 pub struct TimeSlot {
     id: i16,
     day: DayOfWeek,
     time: NaiveTime,
+    course_id: i16,
+    duration: i16,
 }
 
 impl PartialOrd for TimeSlot {
@@ -134,17 +152,18 @@ impl TimeSlot {
             .make_ok()
     }
 
-    /// Returns all timeslots form the database.
-    pub fn load_all(db: &Db) -> Result<Vec<Self>> {
+    /// Returns all timeslots of the given course from the database.
+    pub fn load_all(course: &Course, db: &Db) -> Result<Vec<Self>> {
         timeslots::table
+            .filter(timeslots::course_id.eq(course.id()))
             .load(&*db.conn()?)
             .chain_err(|| "unable to load timeslots from DB")
     }
 
     /// Creates a new timeslot with the given data and stores it in the
     /// database.
-    pub fn create(day: DayOfWeek, time: Time, db: &Db) -> Result<Self> {
-        let new_timeslot = NewTimeSlot { day, time: time.0 };
+    pub fn create(course: &Course, day: DayOfWeek, time: Time, duration: u16, db: &Db) -> Result<Self> {
+        let new_timeslot = NewTimeSlot::new(course, day, time, duration);
 
         diesel::insert(&new_timeslot)
             .into(timeslots::table)
@@ -167,20 +186,21 @@ impl TimeSlot {
             })
     }
 
-    /// Counts the number of timeslots in the database.
-    pub fn count(db: &Db) -> Result<u64> {
+    /// Counts the number of timeslots of the given course in the database.
+    pub fn count(course: &Course, db: &Db) -> Result<u64> {
         timeslots::table
+            .filter(timeslots::course_id.eq(course.id()))
             .count()
             .get_result::<i64>(&*db.conn()?)
             .chain_err(|| "failed to count number of timeslots in DB")
             .map(|count| count as u64)
     }
 
-    /// Deletes the timeslot with the given id from the database. Returns
-    /// `true` if it has been deleted, `false` otherwise (which probably means
-    /// the the id wasn't found).
-    pub fn delete(id: i16, db: &Db) -> Result<bool> {
-        diesel::delete(timeslots::table.find(id))
+    /// Deletes the timeslot with the given id of the given course from the
+    /// database. Returns `true` if it has been deleted, `false` otherwise
+    /// (which probably means the the id wasn't found).
+    pub fn delete(course: &Course, id: i16, db: &Db) -> Result<bool> {
+        diesel::delete(timeslots::table.find(id).filter(timeslots::course_id.eq(course.id())))
             .execute(&*db.conn()?)
             .map(|changes_rows| changes_rows == 1)
             .chain_err(|| "failed to delete timeslot from DB")
@@ -190,13 +210,29 @@ impl TimeSlot {
         self.id
     }
 
+    /// Returns the start of this timeslot.
     pub fn time(&self) -> Time {
         Time(self.time)
     }
 
+    /// Returns the length of this timeslot in minutes.
+    pub fn duration(&self) -> u16 {
+        self.duration as u16
+    }
+
+    /// Returns the end of this timeslot, which is the start of a directly
+    /// following timeslot.
+    pub fn end(&self) -> Time {
+        self.time().next(self.duration())
+    }
+
     pub fn day(&self) -> DayOfWeek {
         self.day
     }
+
+    pub fn course_id(&self) -> i16 {
+        self.course_id
+    }
 }
 
 
@@ -238,12 +274,26 @@ impl Into<chrono::Weekday> for DayOfWeek {
 pub struct Time(NaiveTime);
 
 impl Time {
-    pub fn next(&self) -> Self {
-        Time(self.0 + Duration::minutes(config::TIMESLOT_LEN.into()))
+    /// Returns the time `minutes` later, e.g. the start of the next timeslot
+    /// if this is the start of a timeslot with that length.
+    pub fn next(&self, minutes: u16) -> Self {
+        Time(self.0 + Duration::minutes(minutes.into()))
     }
 
-    pub fn prev(&self) -> Self {
-        Time(self.0 - Duration::minutes(config::TIMESLOT_LEN.into()))
+    /// Returns the time `minutes` earlier.
+    pub fn prev(&self, minutes: u16) -> Self {
+        Time(self.0 - Duration::minutes(minutes.into()))
+    }
+
+    /// Returns the number of minutes since midnight.
+    fn minutes_of_day(&self) -> u32 {
+        use chrono::Timelike;
+
+        self.0.hour() * 60 + self.0.minute()
+    }
+
+    pub fn as_naive_time(&self) -> NaiveTime {
+        self.0
     }
 }
 
@@ -256,29 +306,36 @@ impl fmt::Display for Time {
 impl FromStr for Time {
     type Err = String;
 
-    /// Parses strings of the form "HH:MM" where "MM" is a multiple of
-    /// `config::TIMESLOT_LEN`.
+    /// Parses strings of the form "HH:MM".
     fn from_str(s: &str) -> StdResult<Self, Self::Err> {
-        use chrono::prelude::*;
-
-        let time = NaiveTime::parse_from_str(s, "%H:%M")
-            .map_err(|e| e.to_string())?;
-
-        if time.minute() % (config::TIMESLOT_LEN as u32) != 0 {
-            return Err(format!(
-                "The minutes of a timeslot have to be a multiple of {}!",
-                config::TIMESLOT_LEN,
-            ));
-        }
+        NaiveTime::parse_from_str(s, "%H:%M")
+            .map(Time)
+            .map_err(|e| e.to_string())
+    }
+}
 
-        Ok(Time(time))
+/// Parses the length of a timeslot in minutes. It has to be between 1 and
+/// `config::MAX_TIMESLOT_LEN`.
+pub fn parse_duration(s: &str) -> StdResult<u16, String> {
+    match s.trim().parse::<u16>() {
+        Ok(duration) if duration > 0 && duration <= config::MAX_TIMESLOT_LEN => Ok(duration),
+        _ => Err(format!(
+            "The length of a timeslot has to be a number of minutes between 1 and {}!",
+            config::MAX_TIMESLOT_LEN,
+        )),
     }
 }
 
-pub fn parse_time_interval(s: &str) -> StdResult<Vec<Time>, String> {
+/// Parses either a single time "HH:MM" or an interval "HH:MM-HH:MM" and
+/// returns the starts of all timeslots with the given length in it. The
+/// length of the interval has to be a multiple of `duration` and all
+/// timeslots have to end before midnight (a timeslot ending at 24:00 would
+/// end at 00:00 of the same day).
+pub fn parse_time_interval(s: &str, duration: u16) -> StdResult<Vec<Time>, String> {
     let parts: Vec<_> = s.splitn(2, '-').collect();
-    if parts.len() == 1 {
-        Ok(vec![parts[0].trim().parse()?])
+    let (start, end) = if parts.len() == 1 {
+        let start: Time = parts[0].trim().parse()?;
+        (start, start.minutes_of_day() + u32::from(duration))
     } else {
         let start: Time = parts[0].trim().parse()?;
         let end: Time = parts[1].trim().parse()?;
@@ -286,16 +343,22 @@ pub fn parse_time_interval(s: &str) -> StdResult<Vec<Time>, String> {
         if start >= end {
             return Err("start has to be smaller than the end!".into());
         }
-
-        let mut slot = start;
-        let mut out = Vec::new();
-        while slot < end {
-            out.push(slot);
-            slot.0 = slot.0 + Duration::minutes(config::TIMESLOT_LEN.into());
+        if (end.minutes_of_day() - start.minutes_of_day()) % u32::from(duration) != 0 {
+            return Err(format!(
+                "The length of the interval has to be a multiple of {} minutes!",
+                duration,
+            ));
         }
 
-        Ok(out)
+        (start, end.minutes_of_day())
+    };
+
+    if end >= 24 * 60 {
+        return Err("Timeslots have to end before midnight!".into());
     }
+
+    let count = (end - start.minutes_of_day()) / u32::from(duration);
+    Ok((0..count).map(|i| start.next(i as u16 * duration)).collect())
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -304,3 +367,65 @@ pub enum Rating {
     Tolerable,
     Bad,
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(strs: &[&str]) -> Vec<Time> {
+        strs.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("20"), Ok(20));
+        assert_eq!(parse_duration(" 45 "), Ok(45));
+        assert_eq!(parse_duration(&config::MAX_TIMESLOT_LEN.to_string()), Ok(config::MAX_TIMESLOT_LEN));
+
+        assert!(parse_duration("0").is_err());
+        assert!(parse_duration("-20").is_err());
+        assert!(parse_duration("20min").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration(&(config::MAX_TIMESLOT_LEN + 1).to_string()).is_err());
+    }
+
+    #[test]
+    fn single_time() {
+        assert_eq!(parse_time_interval("08:15", 20), Ok(times(&["08:15"])));
+        assert_eq!(parse_time_interval(" 23:00 ", 45), Ok(times(&["23:00"])));
+        assert_eq!(parse_time_interval("23:39", 20), Ok(times(&["23:39"])));
+
+        assert!(parse_time_interval("8 Uhr", 20).is_err());
+    }
+
+    #[test]
+    fn interval() {
+        assert_eq!(
+            parse_time_interval("08:00-09:00", 20),
+            Ok(times(&["08:00", "08:20", "08:40"])),
+        );
+        assert_eq!(
+            parse_time_interval("10:00 - 12:15", 45),
+            Ok(times(&["10:00", "10:45", "11:30"])),
+        );
+        assert_eq!(parse_time_interval("22:30-23:15", 45), Ok(times(&["22:30"])));
+
+        // Not a multiple of the length
+        assert!(parse_time_interval("08:00-09:00", 45).is_err());
+        assert!(parse_time_interval("08:00-08:50", 20).is_err());
+
+        // Empty or reversed
+        assert!(parse_time_interval("09:00-09:00", 20).is_err());
+        assert!(parse_time_interval("09:00-08:00", 20).is_err());
+    }
+
+    #[test]
+    fn midnight() {
+        // Timeslots ending at midnight would end at 00:00 of the same day.
+        assert!(parse_time_interval("23:40", 20).is_err());
+        assert!(parse_time_interval("23:15", 45).is_err());
+        assert!(parse_time_interval("23:30", 45).is_err());
+        assert!(parse_time_interval("22:30-24:00", 45).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use maud::{html, Markup};
+
+use config;
+use dict::{self, Locale};
+use login::Session;
+use super::profile::{self, Profile, ProfileField};
+
+
+/// Everything the settings page needs to know about the second factor of the
+/// user.
+pub struct TwoFactorState {
+    pub enabled: bool,
+    pub required: bool,
+    pub recovery_codes: u64,
+}
+
+pub fn settings(
+    locale: Locale,
+    current: &Session,
+    sessions: &[Session],
+    two_factor: &TwoFactorState,
+    has_password: bool,
+    profile: &Profile,
+    calendar_token: Option<&str>,
+) -> Markup {
+    let dict = dict::new(locale).user;
+
+    html! {
+        h1 (dict.settings_title())
+
+        h2 (dict.profile_headline())
+        p (dict.profile_explanation())
+        form action="/settings/profile" method="post" style="max-width: 25em" {
+            @for field in ProfileField::all() {
+                div class="o-form-element" {
+                    label class="c-label" for=(field.as_str()) (field.label(locale))
+                    input
+                        id=(field.as_str())
+                        class="c-field"
+                        name=(field.as_str())
+                        value=(profile.field(*field).unwrap_or_default())
+                        disabled?[!profile::is_user_editable(*field)];
+                }
+            }
+            input
+                type="submit"
+                class="c-button c-button--brand"
+                value=(dict.save_profile());
+        }
+
+        @if has_password {
+            h2 (dict.password_headline())
+            form action="/settings/password" method="post" style="max-width: 25em" {
+                div class="o-form-element" {
+                    label class="c-label" for="old_password" (dict.old_password())
+                    input
+                        id="old_password"
+                        type="password"
+                        class="c-field"
+                        name="old_password"
+                        autocomplete="current-password";
+                }
+                div class="o-form-element" {
+                    label class="c-label" for="new_password" (dict.new_password())
+                    input
+                        id="new_password"
+                        type="password"
+                        class="c-field"
+                        name="new_password"
+                        autocomplete="new-password";
+                    div class="c-hint" (dict.new_password_hint(config::PASSWORD_MIN_LEN as u64))
+                }
+                div class="o-form-element" {
+                    label class="c-label" for="new_password_confirm" (dict.new_password_confirm())
+                    input
+                        id="new_password_confirm"
+                        type="password"
+                        class="c-field"
+                        name="new_password_confirm"
+                        autocomplete="new-password";
+                }
+                input
+                    type="submit"
+                    class="c-button c-button--brand"
+                    value=(dict.change_password());
+            }
+        }
+
+        h2 (dict.two_factor_headline())
+        p (dict.two_factor_explanation())
+        @if two_factor.enabled {
+            p {
+                span class="c-badge c-badge--success" (dict.two_factor_enabled())
+                " "
+                (dict.two_factor_recovery_codes_left(two_factor.recovery_codes))
+            }
+            form action="/settings/two_factor/recovery_codes" method="post" class="u-letter-box--small" {
+                input
+                    type="submit"
+                    class="c-button c-button--brand"
+                    value=(dict.generate_recovery_codes());
+            }
+            @if !two_factor.required {
+                form action="/settings/two_factor/disable" method="post" class="u-letter-box--small" {
+                    div class="c-input-group" {
+                        div class="o-field" {
+                            input
+                                type="text"
+                                class="c-field"
+                                name="code"
+                                autocomplete="off"
+                                placeholder=(dict.two_factor_code_placeholder());
+                        }
+                        input
+                            type="submit"
+                            class="c-button c-button--error"
+                            value=(dict.disable_two_factor());
+                    }
+                }
+            }
+        } @else {
+            @if two_factor.required {
+                p (dict.two_factor_required())
+            }
+            a class="c-button c-button--brand" href="/settings/two_factor" (dict.set_up_two_factor())
+        }
+
+        h2 (dict.calendar_headline())
+        p (dict.calendar_explanation())
+        @if let Some(token) = calendar_token {
+            p code (format!("/calendar/{}.ics", token))
+        }
+        form action="/settings/calendar" method="post" class="u-letter-box--small" {
+            input
+                type="submit"
+                class="c-button c-button--brand"
+                value=(if calendar_token.is_some() {
+                    dict.renew_calendar_link()
+                } else {
+                    dict.create_calendar_link()
+                });
+        }
+
+        h2 (dict.sessions_headline())
+        p (dict.sessions_explanation())
+
+        table class="c-table" {
+            thead class="c-table__head" {
+                tr class="c-table__row c-table__row--heading" {
+                    th class="c-table__cell" (dict.session_birth())
+                    th class="c-table__cell" (dict.session_last_use())
+                    th class="c-table__cell" (dict.session_user_agent())
+                    th class="c-table__cell" (dict.session_ip_address())
+                    th class="c-table__cell" {}
+                }
+            }
+            tbody class="c-table__body" {
+                @for session in sessions {
+                    tr class="c-table__row" {
+                        td class="c-table__cell" (session.birth.format("%d.%m.%Y %H:%M"))
+                        td class="c-table__cell" (session.last_use.format("%d.%m.%Y %H:%M"))
+                        td class="c-table__cell" (session.user_agent.as_ref().map_or("–", |s| s.as_str()))
+                        td class="c-table__cell" (session.ip_address.as_ref().map_or("–", |s| s.as_str()))
+                        td class="c-table__cell" {
+                            @if session.id == current.id {
+                                span class="c-badge c-badge--info" (dict.current_session())
+                            } @else {
+                                form action="/settings/revoke_session" method="post" {
+                                    input type="hidden" name="session" value=(session.public_id());
+                                    input
+                                        type="submit"
+                                        class="c-button c-button--error u-xsmall"
+                                        value=(dict.revoke_session());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        @if sessions.len() > 1 {
+            form action="/settings/revoke_other_sessions" method="post" class="u-letter-box--small" {
+                input
+                    type="submit"
+                    class="c-button c-button--error"
+                    value=(dict.revoke_other_sessions());
+            }
+        }
+    }
+}
+
+/// Shows freshly generated recovery codes.
+pub fn recovery_codes(locale: Locale, codes: &[String]) -> Markup {
+    let dict = dict::new(locale).user;
+
+    html! {
+        h1 (dict.recovery_codes_headline())
+        p (dict.recovery_codes_explanation())
+        ul {
+            @for code in codes {
+                li code (code)
+            }
+        }
+        a class="c-button c-button--brand" href="/settings" (dict.back_to_settings())
+    }
+}
@@ -0,0 +1,563 @@
+//! Importing users from CSV files.
+//!
+//! At the start of a term we get an enrollment export (e.g. from Stud.IP)
+//! with all participants of the course. Such a file can be imported in the
+//! admin panel or with `manage_db import users`. Importing happens in two
+//! steps: first, an `ImportPlan` is prepared which shows which users will be
+//! created, which will be updated and which rows conflict with existing data.
+//! After checking this preview, the plan is applied in one transaction.
+//!
+//! The file needs a header line. Columns are recognized by their name (see
+//! `Column::from_header`), unknown columns are ignored. Only the username
+//! column is required. Fields can be separated by `;`, `,` or tabs.
+
+use std::collections::{HashMap, HashSet};
+
+use diesel;
+use diesel::prelude::*;
+
+use db::Db;
+use db::schema::users;
+use errors::*;
+use super::{Role, User};
+use super::profile::{Profile, ProfileError, ProfileField};
+
+
+/// One row of an imported CSV file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ImportRow {
+    /// The line of the record in the file (starting at 1, the header is line
+    /// 1).
+    pub line: usize,
+    pub username: String,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub matriculation_number: Option<String>,
+}
+
+impl ImportRow {
+    /// Returns `base` with the profile fields of this row. Fields missing in
+    /// this row are kept.
+    fn profile(&self, mut base: Profile) -> StdResult<Profile, ProfileError> {
+        if let Some(ref email) = self.email {
+            base.set_field(ProfileField::Email, email)?;
+        }
+        if let Some(ref number) = self.matriculation_number {
+            base.set_field(ProfileField::MatriculationNumber, number)?;
+        }
+
+        Ok(base)
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub enum CsvError {
+        /// The file doesn't contain a single line.
+        Empty {
+            description("the CSV file is empty")
+        }
+
+        /// No column in the header line contains usernames.
+        MissingUsernameColumn {
+            description("the CSV file has no username column")
+        }
+
+        /// A quoted field is not terminated.
+        UnterminatedQuote(line: usize) {
+            description("unterminated quoted field in CSV file")
+            display("unterminated quoted field in line {}", line)
+        }
+
+        /// A record has no username.
+        EmptyUsername(line: usize) {
+            description("empty username in CSV file")
+            display("empty username in line {}", line)
+        }
+    }
+}
+
+/// The columns of an enrollment export we are interested in.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+enum Column {
+    Username,
+    Name,
+    FirstName,
+    LastName,
+    Email,
+    MatriculationNumber,
+}
+
+impl Column {
+    /// Recognizes a column by its name in the header. Case, spaces, `-` and
+    /// `_` are ignored; German and English names are understood.
+    fn from_header(header: &str) -> Option<Self> {
+        let normalized = header.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-' && *c != '_')
+            .flat_map(|c| c.to_lowercase())
+            .collect::<String>();
+
+        match &*normalized {
+            "username" | "nutzername" | "benutzername" | "login" | "kennung" => {
+                Some(Column::Username)
+            }
+            "name" | "fullname" => Some(Column::Name),
+            "vorname" | "firstname" => Some(Column::FirstName),
+            "nachname" | "lastname" => Some(Column::LastName),
+            "email" | "mail" | "emailadresse" => Some(Column::Email),
+            "matrikelnummer" | "matrikelnr" | "matrnr" | "matriculationnumber" => {
+                Some(Column::MatriculationNumber)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parses a CSV file with a header line into import rows.
+pub fn parse_csv(input: &str) -> StdResult<Vec<ImportRow>, CsvError> {
+    // Excel likes to prepend a byte order mark.
+    let input = input.trim_left_matches('\u{feff}');
+
+    let delimiter = {
+        let first_line = input.lines().next().ok_or(CsvError::Empty)?;
+        [';', ',', '\t'].iter()
+            .cloned()
+            .max_by_key(|&d| first_line.matches(d).count())
+            .unwrap()
+    };
+
+    let mut records = split_records(input, delimiter)?.into_iter();
+    let (_, header) = records.next().ok_or(CsvError::Empty)?;
+    let columns = header.iter()
+        .map(|h| Column::from_header(h))
+        .collect::<Vec<_>>();
+    if !columns.contains(&Some(Column::Username)) {
+        return Err(CsvError::MissingUsernameColumn);
+    }
+
+    let mut out = Vec::new();
+    for (line, fields) in records {
+        // Skip completely empty lines
+        if fields.iter().all(|f| f.trim().is_empty()) {
+            continue;
+        }
+
+        let mut values = HashMap::new();
+        for (col, field) in columns.iter().zip(fields) {
+            let field = field.trim();
+            if let Some(col) = *col {
+                if !field.is_empty() {
+                    values.insert(col, field.to_owned());
+                }
+            }
+        }
+
+        let username = values.remove(&Column::Username)
+            .ok_or(CsvError::EmptyUsername(line))?;
+        let name = values.remove(&Column::Name).or_else(|| {
+            let parts = [Column::FirstName, Column::LastName].iter()
+                .filter_map(|c| values.get(c).cloned())
+                .collect::<Vec<_>>();
+            if parts.is_empty() {
+                None
+            } else {
+                Some(parts.join(" "))
+            }
+        });
+
+        out.push(ImportRow {
+            line,
+            username,
+            name,
+            email: values.remove(&Column::Email),
+            matriculation_number: values.remove(&Column::MatriculationNumber),
+        });
+    }
+
+    Ok(out)
+}
+
+/// Splits the input into records of fields. Fields can be quoted with `"`,
+/// quoted fields can contain the delimiter, line breaks and `""` for a
+/// literal quote. A `"` only starts a quoted field at the start of a field
+/// (surrounding spaces are ignored); everywhere else it is taken literally,
+/// e.g. in `Max "Mad" Mustermann`. Each record is returned with the line it
+/// starts in.
+fn split_records(input: &str, delimiter: char) -> StdResult<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut was_quoted = false;
+    let mut line = 1;
+    let mut record_start = 1;
+
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if !was_quoted && field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+                was_quoted = true;
+            }
+            '\n' if !in_quotes => {
+                fields.push(field.trim_right_matches('\r').to_owned());
+                field.clear();
+                was_quoted = false;
+                records.push((record_start, fields));
+                fields = Vec::new();
+                line += 1;
+                record_start = line;
+            }
+            c if c == delimiter && !in_quotes => {
+                fields.push(field.clone());
+                field.clear();
+                was_quoted = false;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(CsvError::UnterminatedQuote(record_start));
+    }
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((record_start, fields));
+    }
+
+    Ok(records)
+}
+
+
+/// Why a row cannot be imported.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConflictKind {
+    /// The username appears in an earlier row of the same file.
+    DuplicateInFile,
+
+    /// A user with this username exists, but has a different role. Roles are
+    /// never changed by an import.
+    RoleMismatch(Role),
+
+    /// The email address or matriculation number is invalid.
+    InvalidProfile(ProfileError),
+
+    /// The matriculation number belongs to another user (or another row of
+    /// the file).
+    MatriculationNumberTaken,
+}
+
+/// A row which is skipped when applying the import.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub row: ImportRow,
+    pub kind: ConflictKind,
+}
+
+/// What importing a list of rows would do. Created with `prepare()` and
+/// executed with `apply()`.
+#[derive(Debug, Clone)]
+pub struct ImportPlan {
+    /// The role of all newly created users.
+    pub role: Role,
+
+    /// Rows for which a new user is created.
+    pub new_users: Vec<ImportRow>,
+
+    /// Existing users whose name or profile will be changed to the one in
+    /// the row.
+    pub updates: Vec<(User, ImportRow)>,
+
+    /// Existing users which are already up to date.
+    pub unchanged: Vec<User>,
+
+    pub conflicts: Vec<Conflict>,
+}
+
+/// The outcome of applying an `ImportPlan`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub skipped: usize,
+}
+
+impl ImportPlan {
+    /// Compares the given rows with the users in the database. Users which
+    /// don't exist yet will be created with the given role.
+    pub fn prepare(rows: Vec<ImportRow>, role: Role, db: &Db) -> Result<Self> {
+        let users = users::table.load::<User>(&*db.conn()?)?;
+        Ok(Self::compare(rows, role, users))
+    }
+
+    /// Like `prepare()`, but compares with the given users instead of the
+    /// ones in the database.
+    fn compare(rows: Vec<ImportRow>, role: Role, users: Vec<User>) -> Self {
+        // Owners of all matriculation numbers. Rows which are imported add
+        // their number, so that duplicates within the file are found, too.
+        let mut numbers = users.iter()
+            .filter_map(|u| u.matriculation_number().map(|n| (n.to_owned(), u.username().to_owned())))
+            .collect::<HashMap<_, _>>();
+        let mut existing = users.into_iter()
+            .map(|u| (u.username().to_owned(), u))
+            .collect::<HashMap<_, _>>();
+
+        let mut plan = ImportPlan {
+            role,
+            new_users: vec![],
+            updates: vec![],
+            unchanged: vec![],
+            conflicts: vec![],
+        };
+
+        let mut seen = HashSet::new();
+        for row in rows {
+            if !seen.insert(row.username.clone()) {
+                plan.conflicts.push(Conflict { row, kind: ConflictKind::DuplicateInFile });
+                continue;
+            }
+
+            let user = existing.remove(&row.username);
+            if let Some(ref user) = user {
+                if user.role() != role {
+                    let kind = ConflictKind::RoleMismatch(user.role());
+                    plan.conflicts.push(Conflict { row, kind });
+                    continue;
+                }
+            }
+
+            let old_profile = user.as_ref().map(|u| u.profile()).unwrap_or_default();
+            let new_profile = match row.profile(old_profile.clone()) {
+                Ok(profile) => profile,
+                Err(e) => {
+                    plan.conflicts.push(Conflict { row, kind: ConflictKind::InvalidProfile(e) });
+                    continue;
+                }
+            };
+
+            if let Some(number) = new_profile.matriculation_number.clone() {
+                if numbers.get(&number).map_or(false, |owner| *owner != row.username) {
+                    let kind = ConflictKind::MatriculationNumberTaken;
+                    plan.conflicts.push(Conflict { row, kind });
+                    continue;
+                }
+                numbers.insert(number, row.username.clone());
+            }
+
+            match user {
+                None => plan.new_users.push(row),
+                Some(user) => {
+                    let name_changed = row.name.is_some()
+                        && user.name() != row.name.as_ref().map(AsRef::as_ref);
+
+                    if name_changed || new_profile != old_profile {
+                        plan.updates.push((user, row));
+                    } else {
+                        plan.unchanged.push(user);
+                    }
+                }
+            }
+        }
+
+        plan
+    }
+
+    /// Returns `true` if applying this plan would not change anything.
+    pub fn is_noop(&self) -> bool {
+        self.new_users.is_empty() && self.updates.is_empty()
+    }
+
+    /// Creates and updates all users of this plan in one transaction.
+    /// Conflicting rows are skipped. If anything fails, nothing is changed.
+    pub fn apply(&self, db: &Db) -> Result<ImportSummary> {
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| {
+            for row in &self.new_users {
+                let user = User::create_on(row.username.clone(), row.name.clone(), self.role, conn)
+                    .chain_err(|| format!("failed to create user '{}'", row.username))?;
+                diesel::update(users::table.find(user.id()))
+                    .set(&row.profile(Profile::default())?)
+                    .execute(conn)
+                    .chain_err(|| format!("failed to set profile of user '{}'", row.username))?;
+            }
+
+            for &(ref user, ref row) in &self.updates {
+                let name = row.name.clone().or_else(|| user.name.clone());
+                diesel::update(users::table.find(user.id()))
+                    .set((users::name.eq(&name), &row.profile(user.profile())?))
+                    .execute(conn)
+                    .chain_err(|| format!("failed to update user '{}'", row.username))?;
+            }
+
+            Ok(ImportSummary {
+                created: self.new_users.len(),
+                updated: self.updates.len(),
+                unchanged: self.unchanged.len(),
+                skipped: self.conflicts.len(),
+            })
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: usize, username: &str) -> ImportRow {
+        ImportRow {
+            line,
+            username: username.into(),
+            name: None,
+            email: None,
+            matriculation_number: None,
+        }
+    }
+
+    fn user(id: i64, username: &str, role: Role, number: Option<&str>) -> User {
+        User {
+            id,
+            username: username.into(),
+            name: Some(format!("User {}", id)),
+            role,
+            email: None,
+            matriculation_number: number.map(Into::into),
+            study_program: None,
+            semester: None,
+        }
+    }
+
+    #[test]
+    fn columns_and_names() {
+        let rows = parse_csv("Nachname;Vorname;Kennung;E-Mail;Matrikelnr;Gruppe\n\
+                              Mustermann;Erika;uxxxa;erika@example.org;123456;A\n\
+                              ;;uxxxb;;;B\n").unwrap();
+
+        assert_eq!(rows, vec![
+            ImportRow {
+                line: 2,
+                username: "uxxxa".into(),
+                name: Some("Erika Mustermann".into()),
+                email: Some("erika@example.org".into()),
+                matriculation_number: Some("123456".into()),
+            },
+            row(3, "uxxxb"),
+        ]);
+    }
+
+    #[test]
+    fn delimiter_detection() {
+        for &input in &[
+            "username,name\nuxxxa,\"Mustermann; Erika\"\n",
+            "username;name\nuxxxa;\"Mustermann, Erika\"\n",
+            "username\tname\nuxxxa\t\"Mustermann, Erika\"\n",
+        ] {
+            let rows = parse_csv(input).unwrap();
+            assert_eq!(rows.len(), 1, "{:?}", input);
+            assert_eq!(rows[0].username, "uxxxa");
+            assert!(rows[0].name.as_ref().unwrap().starts_with("Mustermann"), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn quoting() {
+        let rows = parse_csv("username;name\n\
+                              \"uxxxa\";\"Mustermann; Erika\"\n\
+                              uxxxb; \"Max \"\"Mad\"\" Mustermann\" \n\
+                              uxxxc;\"\"\n\
+                              uxxxd;\"Multi\nline\"\n\
+                              uxxxe;\"Erika\" \"Mustermann\"\n").unwrap();
+        let names = rows.iter().map(|r| (r.line, r.name.as_ref().map(|n| n.as_str()))).collect::<Vec<_>>();
+
+        assert_eq!(names, vec![
+            (2, Some("Mustermann; Erika")),
+            (3, Some("Max \"Mad\" Mustermann")),
+            (4, None),
+            (5, Some("Multi\nline")),
+            (7, Some("Erika \"Mustermann\"")),
+        ]);
+    }
+
+    #[test]
+    fn quotes_inside_fields_are_literal() {
+        let rows = parse_csv("username;name;email\n\
+                              uxxxa;Max \"Mad\" Mustermann;max@example.org\n\
+                              uxxxb;5\" Zoll;\n").unwrap();
+
+        assert_eq!(rows[0].name, Some("Max \"Mad\" Mustermann".into()));
+        assert_eq!(rows[0].email, Some("max@example.org".into()));
+        assert_eq!(rows[1].name, Some("5\" Zoll".into()));
+    }
+
+    #[test]
+    fn bom_and_crlf() {
+        let rows = parse_csv("\u{feff}Username;Name\r\nuxxxa;Erika\r\n\r\nuxxxb;\"Max\r\nM.\"\r\n").unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!((rows[0].line, &*rows[0].username), (2, "uxxxa"));
+        assert_eq!(rows[0].name, Some("Erika".into()));
+        assert_eq!((rows[1].line, &*rows[1].username), (4, "uxxxb"));
+        assert_eq!(rows[1].name, Some("Max\r\nM.".into()));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_csv(""), Err(CsvError::Empty));
+        assert_eq!(parse_csv("\u{feff}"), Err(CsvError::Empty));
+        assert_eq!(parse_csv("name;email\nErika;erika@example.org\n"), Err(CsvError::MissingUsernameColumn));
+        assert_eq!(parse_csv("username;name\nuxxxa;Erika\n;Max\n"), Err(CsvError::EmptyUsername(3)));
+        assert_eq!(
+            parse_csv("username;name\nuxxxa;Erika\nuxxxb;\"Max\nuxxxc;Moritz\n"),
+            Err(CsvError::UnterminatedQuote(3)),
+        );
+    }
+
+    #[test]
+    fn plan() {
+        let users = vec![
+            user(1, "uxxxa", Role::Student, Some("111111")),
+            user(2, "uxxxb", Role::Tutor, None),
+            user(3, "uxxxc", Role::Student, Some("333333")),
+        ];
+        let rows = vec![
+            // Unchanged, updated and new
+            ImportRow { matriculation_number: Some("111111".into()), ..row(2, "uxxxa") },
+            ImportRow { name: Some("Carla".into()), ..row(3, "uxxxc") },
+            ImportRow { matriculation_number: Some("444444".into()), ..row(4, "uxxxd") },
+
+            // Conflicts
+            ImportRow { name: Some("Erika".into()), ..row(5, "uxxxa") },
+            row(6, "uxxxb"),
+            ImportRow { email: Some("no address".into()), ..row(7, "uxxxe") },
+            ImportRow { matriculation_number: Some("333333".into()), ..row(8, "uxxxf") },
+            ImportRow { matriculation_number: Some("444444".into()), ..row(9, "uxxxg") },
+        ];
+
+        let plan = ImportPlan::compare(rows, Role::Student, users);
+
+        assert_eq!(plan.unchanged.iter().map(|u| u.id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(plan.updates.iter().map(|&(ref u, _)| u.id).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(plan.new_users.iter().map(|r| r.line).collect::<Vec<_>>(), vec![4]);
+
+        let conflicts = plan.conflicts.iter().map(|c| (c.row.line, c.kind.clone())).collect::<Vec<_>>();
+        assert_eq!(conflicts, vec![
+            (5, ConflictKind::DuplicateInFile),
+            (6, ConflictKind::RoleMismatch(Role::Tutor)),
+            (7, ConflictKind::InvalidProfile(ProfileError::InvalidEmail)),
+            (8, ConflictKind::MatriculationNumberTaken),
+            (9, ConflictKind::MatriculationNumberTaken),
+        ]);
+    }
+}
@@ -0,0 +1,275 @@
+// ===========================================================================
+// Settings page
+// ===========================================================================
+unit settings_title {
+    De => "Einstellungen",
+    En => "Settings",
+}
+
+
+// ===========================================================================
+// Profile
+// ===========================================================================
+unit profile_headline {
+    De => "Profil",
+    En => "Profile",
+}
+unit profile_explanation {
+    De => "Diese Angaben werden für Benachrichtigungen und die Meldung an das \
+           Prüfungsamt verwendet. Ausgegraute Felder können nur von einem \
+           Administrator geändert werden.",
+    En => "This information is used for notifications and the report to the \
+           exam office. Greyed out fields can only be changed by an \
+           administrator.",
+}
+unit field_email {
+    De => "E-Mail-Adresse",
+    En => "Email address",
+}
+unit field_matriculation_number {
+    De => "Matrikelnummer",
+    En => "Matriculation number",
+}
+unit field_study_program {
+    De => "Studiengang",
+    En => "Study program",
+}
+unit field_semester {
+    De => "Fachsemester",
+    En => "Semester",
+}
+unit save_profile {
+    De => "Profil speichern",
+    En => "Save profile",
+}
+unit flash_success_profile_saved {
+    De => "Dein Profil wurde gespeichert.",
+    En => "Your profile was saved.",
+}
+unit err_invalid_email {
+    De => "Die E-Mail-Adresse ist ungültig.",
+    En => "The email address is invalid.",
+}
+unit err_invalid_matriculation_number {
+    De => "Die Matrikelnummer darf nur aus Ziffern bestehen.",
+    En => "The matriculation number must consist of digits only.",
+}
+unit err_invalid_semester {
+    De => "Das Fachsemester muss eine positive Zahl sein.",
+    En => "The semester has to be a positive number.",
+}
+unit err_matriculation_number_taken {
+    De => "Diese Matrikelnummer gehört bereits zu einem anderen Nutzer.",
+    En => "This matriculation number already belongs to another user.",
+}
+
+
+// ===========================================================================
+// Password
+// ===========================================================================
+unit password_headline {
+    De => "Passwort ändern",
+    En => "Change password",
+}
+unit old_password {
+    De => "Aktuelles Passwort",
+    En => "Current password",
+}
+unit new_password {
+    De => "Neues Passwort",
+    En => "New password",
+}
+unit new_password_hint(min_len: u64) {
+    De => "Mindestens {min_len} Zeichen, darf deinen Nutzernamen nicht enthalten",
+    En => "At least {min_len} characters, must not contain your username",
+}
+unit new_password_confirm {
+    De => "Neues Passwort wiederholen",
+    En => "Repeat new password",
+}
+unit change_password {
+    De => "Passwort ändern",
+    En => "Change password",
+}
+unit flash_success_password_changed {
+    De => "Dein Passwort wurde geändert. Alle anderen Sitzungen wurden beendet.",
+    En => "Your password was changed. All other sessions were revoked.",
+}
+unit flash_err_old_password_incorrect {
+    De => "Das aktuelle Passwort ist falsch.",
+    En => "The current password is incorrect.",
+}
+
+
+// ===========================================================================
+// Two-factor authentication
+// ===========================================================================
+unit two_factor_headline {
+    De => "Zwei-Faktor-Authentifizierung",
+    En => "Two-factor authentication",
+}
+unit two_factor_explanation {
+    De => "Mit der Zwei-Faktor-Authentifizierung musst du bei der Anmeldung \
+           zusätzlich einen Code aus einer Authenticator-App auf deinem \
+           Smartphone eingeben. So kann sich niemand mit deinem Passwort allein \
+           anmelden.",
+    En => "With two-factor authentication, you additionally have to enter a \
+           code from an authenticator app on your phone when logging in. This \
+           way, nobody can log in with your password alone.",
+}
+unit two_factor_enabled {
+    De => "Aktiv",
+    En => "Enabled",
+}
+unit two_factor_recovery_codes_left(count: u64) {
+    De => "Du hast noch {count} unbenutzte Wiederherstellungscode(s).",
+    En => "You have {count} unused recovery code(s) left.",
+}
+unit two_factor_required {
+    De => "Für deine Rolle ist die Zwei-Faktor-Authentifizierung \
+           verpflichtend. Du wirst bei deiner nächsten Anmeldung aufgefordert, \
+           sie einzurichten.",
+    En => "Two-factor authentication is mandatory for your role. You will be \
+           asked to set it up on your next login.",
+}
+unit set_up_two_factor {
+    De => "Einrichten",
+    En => "Set up",
+}
+unit generate_recovery_codes {
+    De => "Neue Wiederherstellungscodes erzeugen",
+    En => "Generate new recovery codes",
+}
+unit two_factor_code_placeholder {
+    De => "Aktueller Code",
+    En => "Current code",
+}
+unit disable_two_factor {
+    De => "Deaktivieren",
+    En => "Disable",
+}
+unit recovery_codes_headline {
+    De => "Wiederherstellungscodes",
+    En => "Recovery codes",
+}
+unit recovery_codes_explanation {
+    De => "Falls du keinen Zugriff auf deine Authenticator-App hast, kannst du \
+           dich mit einem dieser Codes anmelden. Jeder Code funktioniert nur \
+           einmal. Bewahre sie sicher auf: sie werden nur jetzt angezeigt! Alle \
+           älteren Codes sind ab sofort ungültig.",
+    En => "If you don't have access to your authenticator app, you can log in \
+           with one of these codes. Each code works only once. Store them \
+           safely: they are only shown now! All older codes are invalid from now \
+           on.",
+}
+unit back_to_settings {
+    De => "Zurück zu den Einstellungen",
+    En => "Back to settings",
+}
+
+unit flash_success_two_factor_enabled {
+    De => "Die Zwei-Faktor-Authentifizierung ist jetzt aktiv. Erzeuge jetzt \
+           Wiederherstellungscodes!",
+    En => "Two-factor authentication is now enabled. Now generate recovery \
+           codes!",
+}
+unit flash_success_two_factor_disabled {
+    De => "Die Zwei-Faktor-Authentifizierung wurde deaktiviert.",
+    En => "Two-factor authentication was disabled.",
+}
+unit flash_err_two_factor_code_incorrect {
+    De => "Der eingegebene Code ist falsch.",
+    En => "The given code is incorrect.",
+}
+unit flash_err_two_factor_required {
+    De => "Für deine Rolle ist die Zwei-Faktor-Authentifizierung verpflichtend.",
+    En => "Two-factor authentication is mandatory for your role.",
+}
+unit flash_err_two_factor_not_enabled {
+    De => "Die Zwei-Faktor-Authentifizierung ist nicht aktiv.",
+    En => "Two-factor authentication is not enabled.",
+}
+
+
+// ===========================================================================
+// Calendar
+// ===========================================================================
+unit calendar_headline {
+    De => "Kalender",
+    En => "Calendar",
+}
+unit calendar_explanation {
+    De => "Du kannst deine Testat-Termine in einer Kalender-App (z.B. Thunderbird, \
+           Google Kalender oder auf deinem Handy) abonnieren. Füge dazu den folgenden \
+           Link als Internet-Kalender hinzu. Behalte den Link für dich: Jeder, der ihn \
+           kennt, kann deine Termine sehen.",
+    En => "You can subscribe to your testat schedule with a calendar app (e.g. \
+           Thunderbird, Google Calendar or on your phone). To do so, add the following \
+           link as an internet calendar. Keep the link to yourself: anyone who knows it \
+           can see your schedule.",
+}
+unit create_calendar_link {
+    De => "Kalender-Link erstellen",
+    En => "Create calendar link",
+}
+unit renew_calendar_link {
+    De => "Neuen Link erstellen (der alte wird ungültig)",
+    En => "Create new link (the old one becomes invalid)",
+}
+
+
+// ===========================================================================
+// Sessions
+// ===========================================================================
+unit sessions_headline {
+    De => "Aktive Sitzungen",
+    En => "Active sessions",
+}
+unit sessions_explanation {
+    De => "Hier siehst du alle Geräte und Browser, in denen du gerade \
+           angemeldet bist. Wenn dir eine Sitzung unbekannt vorkommt, beende \
+           sie.",
+    En => "These are all devices and browsers in which you are currently \
+           logged in. If you don't recognize a session, revoke it.",
+}
+unit session_birth {
+    De => "Angemeldet am",
+    En => "Logged in at",
+}
+unit session_last_use {
+    De => "Zuletzt aktiv",
+    En => "Last activity",
+}
+unit session_user_agent {
+    De => "Browser",
+    En => "Browser",
+}
+unit session_ip_address {
+    De => "IP-Adresse",
+    En => "IP address",
+}
+unit current_session {
+    De => "Diese Sitzung",
+    En => "This session",
+}
+unit revoke_session {
+    De => "Beenden",
+    En => "Revoke",
+}
+unit revoke_other_sessions {
+    De => "Alle anderen Sitzungen beenden",
+    En => "Log out everywhere else",
+}
+
+unit flash_success_session_revoked {
+    De => "Die Sitzung wurde beendet.",
+    En => "The session was revoked.",
+}
+unit flash_success_other_sessions_revoked(count: u64) {
+    De => "{count} andere Sitzung(en) wurden beendet.",
+    En => "{count} other session(s) were revoked.",
+}
+unit flash_err_session_not_found {
+    De => "Die angegebene Sitzung existiert nicht (mehr).",
+    En => "The given session does not exist (anymore).",
+}
@@ -4,21 +4,36 @@
 //! few routes, thought:
 //!
 //! - GET `/settings`: user specific settings
+//! - POST `/settings/revoke_session`: ends one login session of the user
+//! - POST `/settings/revoke_other_sessions`: ends all login sessions of the
+//!   user, except the current one
+//! - POST `/settings/profile`: changes the profile fields (email, ...)
+//! - POST `/settings/password`: changes the internal password of the user
+//! - GET and POST `/settings/two_factor`: sets up two-factor authentification
+//! - POST `/settings/two_factor/recovery_codes`: generates new recovery codes
+//! - POST `/settings/two_factor/disable`: disables two-factor authentification
 
 use diesel;
+use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use rocket::{Outcome, State};
 use rocket::http::{Cookies, Status};
 use rocket::request::{self, FromRequest, Request};
 use std::ops::Deref;
 
+use course::{self, Course};
 use db::Db;
-use db::schema::users;
+use db::schema::{course_members, users};
 use errors::*;
-use login::Session;
+use login::{impersonation, Session};
 use prep::StudentPreferences;
+use self::profile::Profile;
 
 
+mod html;
+pub mod import;
+pub mod permission;
+pub mod profile;
 pub mod routes;
 
 
@@ -35,8 +50,16 @@ pub struct User {
     /// The real name of the user.
     pub name: Option<String>,
 
-    /// The status of the user: student, tutor or administrator.
+    /// The global role of the user: admins are admins in every course.
+    /// Students and tutors have a role per course (see `role_in()`); this
+    /// role is only used when they are added to a course automatically.
     pub role: Role,
+
+    /// The optional profile fields, see `profile::Profile`.
+    pub email: Option<String>,
+    pub matriculation_number: Option<String>,
+    pub study_program: Option<String>,
+    pub semester: Option<i16>,
 }
 
 impl User {
@@ -51,12 +74,42 @@ impl User {
             .make_ok()
     }
 
+    /// Loads the user with the given id from the database.
+    pub fn load_by_id(id: i64, db: &Db) -> Result<Option<Self>> {
+        users::table
+            .find(id)
+            .first(&*db.conn()?)
+            .optional()?
+            .make_ok()
+    }
+
+    /// Loads all users (optionally only those with the given role), ordered
+    /// by username.
+    pub fn load_all(role: Option<Role>, db: &Db) -> Result<Vec<Self>> {
+        let query = users::table.order(users::username);
+        match role {
+            Some(role) => query.filter(users::role.eq(role)).load(&*db.conn()?),
+            None => query.load(&*db.conn()?),
+        }.chain_err(|| "failed to load users")
+    }
+
     /// Creates a new user from the given data and stores it in the database.
     pub fn create(
         username: String,
         name: Option<String>,
         role: Role,
         db: &Db,
+    ) -> Result<Self> {
+        Self::create_on(username, name, role, &*db.conn()?)
+    }
+
+    /// Like `create()`, but uses the given connection. This is useful to
+    /// create users within a transaction.
+    pub fn create_on(
+        username: String,
+        name: Option<String>,
+        role: Role,
+        conn: &PgConnection,
     ) -> Result<Self> {
         #[derive(Debug, Clone, Eq, PartialEq, Insertable)]
         #[table_name = "users"]
@@ -70,19 +123,136 @@ impl User {
 
         let inserted = diesel::insert(&new_user)
             .into(users::table)
-            .get_result::<User>(&*db.conn()?)?;
-
-        // Insert default preferences for the user
-        let out = match role {
-            Role::Student => {
-                let student = inserted.into_student().unwrap();
-                StudentPreferences::create_default(&student, db)?;
-                student.into_inner()
+            .get_result::<User>(conn)?;
+
+        // Add the user to all courses new users are added to.
+        if role != Role::Admin {
+            for course in course::load_auto_enroll_on(conn)? {
+                inserted.set_role_in_on(&course, Some(role), conn)?;
+            }
+        }
+
+        Ok(inserted)
+    }
+
+    /// Changes the name of this user and stores it in the database.
+    pub fn set_name(&mut self, name: Option<String>, db: &Db) -> Result<()> {
+        diesel::update(users::table.find(self.id))
+            .set(users::name.eq(&name))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to update name of user")?;
+        self.name = name;
+
+        Ok(())
+    }
+
+    /// Changes the global role of this user and stores it in the database.
+    /// The roles in courses are not changed.
+    pub fn set_role(&mut self, role: Role, db: &Db) -> Result<()> {
+        diesel::update(users::table.find(self.id))
+            .set(users::role.eq(role))
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to update role of user")?;
+        self.role = role;
+
+        Ok(())
+    }
+
+    /// Changes profile, name and global role of this user at once. Either
+    /// everything or nothing is changed; see `set_profile()` for the errors.
+    pub fn update(
+        &mut self,
+        profile: Profile,
+        name: Option<String>,
+        role: Role,
+        db: &Db,
+    ) -> Result<()> {
+        let conn = &*db.conn()?;
+        let mut updated = self.clone();
+        conn.transaction::<_, Error, _>(|| {
+            updated.set_profile_on(profile, conn)?;
+            diesel::update(users::table.find(self.id))
+                .set((users::name.eq(&name), users::role.eq(role)))
+                .execute(conn)
+                .chain_err(|| "failed to update user")?;
+
+            Ok(())
+        })?;
+
+        updated.name = name;
+        updated.role = role;
+        *self = updated;
+
+        Ok(())
+    }
+
+    /// Returns the role of this user in the given course, or `None` if they
+    /// aren't a member. Admins are admins in every course.
+    pub fn role_in(&self, course: &Course, db: &Db) -> Result<Option<Role>> {
+        if self.is_admin() {
+            return Ok(Some(Role::Admin));
+        }
+
+        course_members::table
+            .find((course.id(), self.id))
+            .select(course_members::role)
+            .first(&*db.conn()?)
+            .optional()
+            .chain_err(|| "failed to load role in course")
+    }
+
+    /// Changes the role of this user in the given course. `None` removes the
+    /// user from the course. Admins cannot be members of courses.
+    ///
+    /// If the user becomes a student, default preferences are created (if the
+    /// user doesn't have any yet).
+    pub fn set_role_in(&self, course: &Course, role: Option<Role>, db: &Db) -> Result<()> {
+        let conn = &*db.conn()?;
+        conn.transaction::<_, Error, _>(|| self.set_role_in_on(course, role, conn))
+    }
+
+    /// Like `set_role_in()`, but uses the given connection.
+    fn set_role_in_on(&self, course: &Course, role: Option<Role>, conn: &PgConnection) -> Result<()> {
+        #[derive(Insertable)]
+        #[table_name = "course_members"]
+        struct NewMember {
+            course_id: i16,
+            user_id: i64,
+            role: Role,
+        }
+
+        if role == Some(Role::Admin) {
+            bail!("admins cannot be members of courses");
+        }
+
+        diesel::delete(course_members::table.find((course.id(), self.id)))
+            .execute(conn)?;
+
+        if let Some(role) = role {
+            diesel::insert(&NewMember { course_id: course.id(), user_id: self.id, role })
+                .into(course_members::table)
+                .execute(conn)
+                .chain_err(|| "failed to insert course member")?;
+
+            if role == Role::Student {
+                let student = Student(self.clone());
+                StudentPreferences::create_default_if_missing_on(&student, course, conn)?;
             }
-            _ => inserted,
-        };
+        }
+
+        Ok(())
+    }
+
+    /// Removes this user from the database. All data of the user (sessions,
+    /// passwords, preferences, ratings, ...) is removed with it.
+    ///
+    /// Users who took part in an impersonation cannot be deleted, since those
+    /// records are kept (the database reports a foreign key violation).
+    pub fn delete(self, db: &Db) -> Result<()> {
+        diesel::delete(users::table.find(self.id))
+            .execute(&*db.conn()?)?;
 
-        Ok(out)
+        Ok(())
     }
 
     pub fn id(&self) -> i64 {
@@ -136,16 +306,55 @@ impl User {
             Err(self)
         }
     }
+
+    /// Returns this user as student, if they are a student in the given
+    /// course.
+    pub fn into_student_of(self, course: &Course, db: &Db) -> Result<StdResult<Student, Self>> {
+        match self.role_in(course, db)? {
+            Some(Role::Student) => Ok(Ok(Student(self))),
+            _ => Ok(Err(self)),
+        }
+    }
+
+    /// Returns this user as tutor, if they are a tutor in the given course.
+    pub fn into_tutor_of(self, course: &Course, db: &Db) -> Result<StdResult<Tutor, Self>> {
+        match self.role_in(course, db)? {
+            Some(Role::Tutor) => Ok(Ok(Tutor(self))),
+            _ => Ok(Err(self)),
+        }
+    }
 }
 
 /// The role of the user.
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Role {
     Admin,
     Tutor,
     Student,
 }
 
+impl Role {
+    pub fn all() -> &'static [Role] {
+        &[Role::Admin, Role::Tutor, Role::Student]
+    }
+
+    /// The name of the role as used in the database, forms and the
+    /// configuration file.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Role::Admin => "admin",
+            Role::Tutor => "tutor",
+            Role::Student => "student",
+        }
+    }
+
+    /// The inverse of `as_str()`.
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::all().iter().cloned().find(|r| r.as_str() == s)
+    }
+}
+
 macro_rules! create_user_role_type {
     ($role:ident) => {
         #[derive(Debug, Clone)]
@@ -164,6 +373,10 @@ macro_rules! create_user_role_type {
                 self.0.name.as_ref().map(AsRef::as_ref)
             }
 
+            pub fn as_user(&self) -> &User {
+                &self.0
+            }
+
             pub fn into_inner(self) -> User {
                 self.0
             }
@@ -182,21 +395,52 @@ create_user_role_type!(Admin);
 /// handler. The `from_request()` call fails if there is no user logged in.
 /// This way, handlers can easily ensure that they can only be visited with a
 /// valid login session.
+///
+/// The current course of the user (see `course`) is determined together
+/// with the session.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct AuthUser {
     user: User,
     session: Session,
+    course: Option<(Course, Role)>,
 }
 
 impl AuthUser {
     pub fn new(user: User, session: Session) -> Self {
-        Self { user, session }
+        Self { user, session, course: None }
     }
 
     pub fn destroy_session(self, cookies: Cookies, db: &Db) -> Result<()> {
         self.session.destroy(cookies, db)
     }
 
+    /// Returns the session with which the user was authenticated.
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Returns the current course, or `None` if the user isn't a member of
+    /// any course.
+    pub fn course(&self) -> Option<&Course> {
+        self.course.as_ref().map(|&(ref course, _)| course)
+    }
+
+    /// Returns the role of the user in the current course. Without a current
+    /// course, the global role is returned.
+    pub fn course_role(&self) -> Role {
+        self.course.as_ref().map(|&(_, role)| role).unwrap_or(self.user.role)
+    }
+
+    /// Returns whether the user has the given permission in the current
+    /// course, either by their role in it or by an explicit grant.
+    pub fn has_permission(&self, perm: permission::Permission, db: &Db) -> Result<bool> {
+        if self.course_role().permissions().contains(&perm) {
+            return Ok(true);
+        }
+
+        Ok(self.user.granted_permissions(db)?.contains(&perm))
+    }
+
     pub fn into_user(self) -> User {
         self.user
     }
@@ -216,8 +460,26 @@ impl<'a, 'r> FromRequest<'a, 'r> for AuthUser {
         // Obtain a DB pool.
         let db = req.guard::<State<Db>>().expect("cannot retrieve DB connection from request");
 
-        match Session::from_cookies(req.cookies(), &db) {
+        let auth_user = Session::from_cookies(req.cookies(), &db).and_then(|auth_user| {
+            match auth_user {
+                Some(mut auth_user) => {
+                    auth_user.course = course::current_of(&auth_user.user, &req.cookies(), &db)?;
+                    Ok(Some(auth_user))
+                }
+                None => Ok(None),
+            }
+        });
+
+        match auth_user {
             Err(e) => Outcome::Failure((Status::InternalServerError, Some(e))),
+
+            // Admins impersonating a user can only look around. Since all
+            // routes changing something need an `AuthUser`, checking it here
+            // covers all of them.
+            Ok(Some(ref auth_user)) if auth_user.session().is_impersonation()
+                && !impersonation::is_allowed(req.method(), req.uri().path()) => {
+                Outcome::Failure((Status::Forbidden, None))
+            }
             Ok(Some(auth_user)) => Outcome::Success(auth_user),
             Ok(None) => Outcome::Failure((Status::Forbidden, None)),
         }
@@ -225,28 +487,93 @@ impl<'a, 'r> FromRequest<'a, 'r> for AuthUser {
 }
 
 
-/// An authorized user which has the user role `Admin`.
+macro_rules! auth_role_guard {
+    ($(#[$attr:meta])* $name:ident, $role:ident) => {
+        $(#[$attr])*
+        ///
+        /// This type implements `FromRequest` and can therefore be used as
+        /// request guard. If no user is logged in, the guard fails like
+        /// `AuthUser`. If the user has another role, the request is
+        /// forwarded. This way, there can be multiple handlers (with
+        /// different ranks) for the same route, one for each role.
+        #[derive(Debug, Clone)]
+        pub struct $name {
+            user: $role,
+            session: Session,
+            course: Course,
+        }
+
+        impl $name {
+            /// Returns the session with which the user was authenticated.
+            pub fn session(&self) -> &Session {
+                &self.session
+            }
+
+            /// Returns the current course, in which the user has this role.
+            pub fn course(&self) -> &Course {
+                &self.course
+            }
+
+            pub fn into_inner(self) -> $role {
+                self.user
+            }
+        }
+
+        impl Deref for $name {
+            type Target = $role;
+            fn deref(&self) -> &Self::Target {
+                &self.user
+            }
+        }
+
+        impl<'a, 'r> FromRequest<'a, 'r> for $name {
+            type Error = Option<Error>;
+
+            fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+                let AuthUser { user, session, course } = AuthUser::from_request(req)?;
+                match course {
+                    Some((course, Role::$role)) => {
+                        Outcome::Success($name { user: $role(user), session, course })
+                    }
+                    _ => Outcome::Forward(()),
+                }
+            }
+        }
+    }
+}
+
+auth_role_guard!(
+    /// An authorized user which is a student in the current course.
+    AuthStudent, Student
+);
+auth_role_guard!(
+    /// An authorized user which is a tutor in the current course.
+    AuthTutor, Tutor
+);
+
+/// An authorized user which is a tutor in the current course or an admin.
 ///
-/// This type implements `FromRequest` and can therefore be used as request
-/// guard.
-pub struct AuthAdmin(pub AuthUser);
+/// Like `AuthStudent` and `AuthTutor`, this guard forwards the request if
+/// the user has another role.
+#[derive(Debug, Clone)]
+pub struct AuthStaff(pub AuthUser);
 
-impl Deref for AuthAdmin {
+impl Deref for AuthStaff {
     type Target = AuthUser;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-impl<'a, 'r> FromRequest<'a, 'r> for AuthAdmin {
+impl<'a, 'r> FromRequest<'a, 'r> for AuthStaff {
     type Error = Option<Error>;
 
     fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
         let user = AuthUser::from_request(req)?;
-        if user.role() == Role::Admin {
-            Outcome::Success(AuthAdmin(user))
+        if user.course().is_some() && user.course_role() != Role::Student {
+            Outcome::Success(AuthStaff(user))
         } else {
-            Outcome::Failure((Status::Unauthorized, None))
+            Outcome::Forward(())
         }
     }
 }
@@ -0,0 +1,268 @@
+//! Permissions of users.
+//!
+//! Instead of checking the role of a user, routes check whether the user has
+//! a specific `Permission`. Each role comes with a fixed set of permissions
+//! (see `Role::permissions()`). Additionally, single users can be granted
+//! further permissions without changing their role, e.g. to let a head tutor
+//! manage timeslots. Those grants are stored in the database and managed in
+//! the admin panel.
+//!
+//! The easiest way to require a permission in a route is the request guard
+//! `Require<P>`, e.g. `Require<perm::ManageTimeslots>`.
+
+use std::marker::PhantomData;
+use std::ops::Deref;
+
+use diesel;
+use diesel::prelude::*;
+use rocket::{Outcome, State};
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+
+use db::Db;
+use db::schema::permission_grants;
+use errors::*;
+use super::{AuthUser, Role, User};
+
+
+/// A single thing a user may do.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Permission {
+    /// See the admin panel with its statistics and configuration.
+    ViewAdminPanel,
+
+    /// End sessions of other users and create password reset links.
+    ManageUsers,
+
+    /// View the app as another user.
+    ImpersonateUsers,
+
+    /// Grant and revoke permissions of other users.
+    ManagePermissions,
+
+    /// Change the state of the app (preparation, running, frozen).
+    ChangeAppState,
+
+    /// Add and delete timeslots and edit the semester calendar.
+    ManageTimeslots,
+
+    /// See the timeslot ratings of all students and tutors.
+    ViewAllRatings,
+
+    /// Grade the testats of students.
+    GradeStudents,
+
+    /// Create and delete announcements shown to all users.
+    ManageAnnouncements,
+
+    /// Create courses and change the roles of users in courses.
+    ManageCourses,
+}
+
+impl Permission {
+    /// All permissions, in the order in which they are shown to users.
+    pub fn all() -> &'static [Permission] {
+        use self::Permission::*;
+
+        &[
+            ViewAdminPanel,
+            ManageUsers,
+            ImpersonateUsers,
+            ManagePermissions,
+            ChangeAppState,
+            ManageTimeslots,
+            ViewAllRatings,
+            GradeStudents,
+            ManageAnnouncements,
+            ManageCourses,
+        ]
+    }
+
+    /// The name of the permission as used in forms and in the database.
+    pub fn as_str(&self) -> &'static str {
+        use self::Permission::*;
+
+        match *self {
+            ViewAdminPanel => "view_admin_panel",
+            ManageUsers => "manage_users",
+            ImpersonateUsers => "impersonate_users",
+            ManagePermissions => "manage_permissions",
+            ChangeAppState => "change_app_state",
+            ManageTimeslots => "manage_timeslots",
+            ViewAllRatings => "view_all_ratings",
+            GradeStudents => "grade_students",
+            ManageAnnouncements => "manage_announcements",
+            ManageCourses => "manage_courses",
+        }
+    }
+
+    /// The inverse of `as_str()`.
+    pub fn from_str(s: &str) -> Option<Self> {
+        Self::all().iter().cloned().find(|p| p.as_str() == s)
+    }
+}
+
+impl Role {
+    /// The permissions every user with this role has.
+    pub fn permissions(&self) -> &'static [Permission] {
+        use self::Permission::*;
+
+        match *self {
+            Role::Admin => Permission::all(),
+            Role::Tutor => &[ViewAllRatings, GradeStudents],
+            Role::Student => &[],
+        }
+    }
+}
+
+impl User {
+    /// Returns whether this user has the given permission, either by role or
+    /// by an explicit grant.
+    pub fn has_permission(&self, perm: Permission, db: &Db) -> Result<bool> {
+        if self.role().permissions().contains(&perm) {
+            return Ok(true);
+        }
+
+        Ok(self.granted_permissions(db)?.contains(&perm))
+    }
+
+    /// Returns the permissions which were explicitly granted to this user (in
+    /// addition to those of their role).
+    pub fn granted_permissions(&self, db: &Db) -> Result<Vec<Permission>> {
+        permission_grants::table
+            .filter(permission_grants::user_id.eq(self.id()))
+            .select(permission_grants::permission)
+            .load::<Permission>(&*db.conn()?)
+            .chain_err(|| "failed to load permission grants")
+    }
+
+    /// Grants the given permission to this user. Granting a permission twice
+    /// is not an error.
+    pub fn grant_permission(&self, perm: Permission, db: &Db) -> Result<()> {
+        #[derive(Insertable)]
+        #[table_name = "permission_grants"]
+        struct NewGrant {
+            user_id: i64,
+            permission: Permission,
+        }
+
+        if self.granted_permissions(db)?.contains(&perm) {
+            return Ok(());
+        }
+
+        let new = NewGrant {
+            user_id: self.id(),
+            permission: perm,
+        };
+        diesel::insert(&new)
+            .into(permission_grants::table)
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to grant permission")?;
+
+        Ok(())
+    }
+
+    /// Revokes the given permission from this user. Only explicitly granted
+    /// permissions can be revoked, the permissions of the role stay.
+    pub fn revoke_permission(&self, perm: Permission, db: &Db) -> Result<()> {
+        let grant = permission_grants::table
+            .filter(permission_grants::user_id.eq(self.id()))
+            .filter(permission_grants::permission.eq(perm));
+        diesel::delete(grant)
+            .execute(&*db.conn()?)
+            .chain_err(|| "failed to revoke permission")?;
+
+        Ok(())
+    }
+}
+
+/// Loads all users with explicitly granted permissions together with those
+/// permissions, ordered by username.
+pub fn load_all_grants(db: &Db) -> Result<Vec<(User, Vec<Permission>)>> {
+    use db::schema::users;
+
+    let rows = permission_grants::table
+        .inner_join(users::table)
+        .order((users::username, permission_grants::permission))
+        .select((users::all_columns, permission_grants::permission))
+        .load::<(User, Permission)>(&*db.conn()?)?;
+
+    let mut out: Vec<(User, Vec<Permission>)> = Vec::new();
+    for (user, perm) in rows {
+        let is_new_user = out.last().map(|&(ref u, _)| u.id() != user.id()).unwrap_or(true);
+        if is_new_user {
+            out.push((user, vec![perm]));
+        } else {
+            out.last_mut().unwrap().1.push(perm);
+        }
+    }
+
+    Ok(out)
+}
+
+
+/// A permission on the type level. Used as type parameter of `Require`.
+pub trait PermissionMarker {
+    fn permission() -> Permission;
+}
+
+/// Marker types for all permissions, see `Require`.
+pub mod perm {
+    use super::{Permission, PermissionMarker};
+
+    macro_rules! permission_markers {
+        ($($name:ident,)*) => {
+            $(
+                pub struct $name;
+
+                impl PermissionMarker for $name {
+                    fn permission() -> Permission {
+                        Permission::$name
+                    }
+                }
+            )*
+        }
+    }
+
+    permission_markers! {
+        ViewAdminPanel,
+        ManageUsers,
+        ImpersonateUsers,
+        ManagePermissions,
+        ChangeAppState,
+        ManageTimeslots,
+        ViewAllRatings,
+        GradeStudents,
+        ManageAnnouncements,
+        ManageCourses,
+    }
+}
+
+/// An authorized user which has the permission `P`.
+///
+/// This type implements `FromRequest` and can therefore be used as request
+/// guard. If the user lacks the permission, the request fails with "403
+/// Forbidden".
+pub struct Require<P: PermissionMarker>(pub AuthUser, PhantomData<P>);
+
+impl<P: PermissionMarker> Deref for Require<P> {
+    type Target = AuthUser;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, 'r, P: PermissionMarker> FromRequest<'a, 'r> for Require<P> {
+    type Error = Option<Error>;
+
+    fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        let user = AuthUser::from_request(req)?;
+        let db = req.guard::<State<Db>>().expect("cannot retrieve DB connection from request");
+
+        match user.has_permission(P::permission(), &db) {
+            Ok(true) => Outcome::Success(Require(user, PhantomData)),
+            Ok(false) => Outcome::Failure((Status::Forbidden, None)),
+            Err(e) => Outcome::Failure((Status::InternalServerError, Some(e))),
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! Optional profile fields of users: email address, matriculation number,
+//! study program and semester.
+//!
+//! These are needed for notifications and exports for the exam office. They
+//! are filled by external login providers (if configured, see
+//! `login::sync_user()`), by the CSV import and by admins. Users can change
+//! the fields listed in the `[profile]` section of the configuration file on
+//! their settings page.
+
+use diesel;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+use config;
+use db::Db;
+use db::schema::users;
+use dict::{self, Locale};
+use errors::*;
+use super::User;
+
+
+/// Profile settings from the configuration file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    /// The fields users may change themselves on the settings page. All
+    /// other fields can only be changed by admins (or external login
+    /// providers).
+    #[serde(default = "default_user_editable")]
+    pub user_editable: Vec<ProfileField>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            user_editable: default_user_editable(),
+        }
+    }
+}
+
+fn default_user_editable() -> Vec<ProfileField> {
+    vec![ProfileField::Email, ProfileField::StudyProgram, ProfileField::Semester]
+}
+
+/// Returns whether users may change the given field themselves.
+pub fn is_user_editable(field: ProfileField) -> bool {
+    config::FILE.profile.user_editable.contains(&field)
+}
+
+/// One of the profile fields.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileField {
+    Email,
+    MatriculationNumber,
+    StudyProgram,
+    Semester,
+}
+
+impl ProfileField {
+    pub fn all() -> &'static [ProfileField] {
+        use self::ProfileField::*;
+
+        &[Email, MatriculationNumber, StudyProgram, Semester]
+    }
+
+    /// The name of the field as used in forms and the configuration file.
+    pub fn as_str(&self) -> &'static str {
+        use self::ProfileField::*;
+
+        match *self {
+            Email => "email",
+            MatriculationNumber => "matriculation_number",
+            StudyProgram => "study_program",
+            Semester => "semester",
+        }
+    }
+
+    /// A user facing name of the field.
+    pub fn label(&self, locale: Locale) -> String {
+        let dict = dict::new(locale).user;
+
+        match *self {
+            ProfileField::Email => dict.field_email(),
+            ProfileField::MatriculationNumber => dict.field_matriculation_number(),
+            ProfileField::StudyProgram => dict.field_study_program(),
+            ProfileField::Semester => dict.field_semester(),
+        }
+    }
+}
+
+quick_error! {
+    #[derive(Debug, Clone, Eq, PartialEq)]
+    pub enum ProfileError {
+        /// The given email address is not valid.
+        InvalidEmail {
+            description("invalid email address")
+        }
+
+        /// A matriculation number has to consist of digits only.
+        InvalidMatriculationNumber {
+            description("invalid matriculation number")
+        }
+
+        /// The semester has to be a positive number.
+        InvalidSemester {
+            description("invalid semester")
+        }
+
+        /// Another user already has the given matriculation number.
+        MatriculationNumberTaken {
+            description("the matriculation number belongs to another user")
+        }
+    }
+}
+
+impl ProfileError {
+    pub fn msg(&self, locale: Locale) -> String {
+        let dict = dict::new(locale).user;
+
+        match *self {
+            ProfileError::InvalidEmail => dict.err_invalid_email(),
+            ProfileError::InvalidMatriculationNumber => dict.err_invalid_matriculation_number(),
+            ProfileError::InvalidSemester => dict.err_invalid_semester(),
+            ProfileError::MatriculationNumberTaken => dict.err_matriculation_number_taken(),
+        }
+    }
+}
+
+/// The profile fields of a user. `None` means "unknown".
+#[derive(Debug, Clone, Default, Eq, PartialEq, AsChangeset)]
+#[table_name = "users"]
+#[changeset_options(treat_none_as_null = "true")]
+pub struct Profile {
+    pub email: Option<String>,
+    pub matriculation_number: Option<String>,
+    pub study_program: Option<String>,
+    pub semester: Option<i16>,
+}
+
+impl Profile {
+    /// Sets the given field from user input. Surrounding whitespace is
+    /// removed; empty input clears the field.
+    pub fn set_field(&mut self, field: ProfileField, input: &str) -> StdResult<(), ProfileError> {
+        let input = input.trim();
+        let value = if input.is_empty() {
+            None
+        } else {
+            Some(input.to_owned())
+        };
+
+        match field {
+            ProfileField::Email => {
+                if let Some(ref email) = value {
+                    check_email(email)?;
+                }
+                self.email = value;
+            }
+            ProfileField::MatriculationNumber => {
+                if let Some(ref number) = value {
+                    if !number.chars().all(|c| c.is_digit(10)) {
+                        return Err(ProfileError::InvalidMatriculationNumber);
+                    }
+                }
+                self.matriculation_number = value;
+            }
+            ProfileField::StudyProgram => self.study_program = value,
+            ProfileField::Semester => {
+                self.semester = match value {
+                    Some(s) => match s.parse::<i16>() {
+                        Ok(semester) if semester > 0 => Some(semester),
+                        _ => return Err(ProfileError::InvalidSemester),
+                    },
+                    None => None,
+                };
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the value of the given field as string (as shown in forms).
+    pub fn field(&self, field: ProfileField) -> Option<String> {
+        match field {
+            ProfileField::Email => self.email.clone(),
+            ProfileField::MatriculationNumber => self.matriculation_number.clone(),
+            ProfileField::StudyProgram => self.study_program.clone(),
+            ProfileField::Semester => self.semester.map(|s| s.to_string()),
+        }
+    }
+}
+
+/// A very basic check: we just want to catch typos, not implement RFC 5322.
+pub fn check_email(email: &str) -> StdResult<(), ProfileError> {
+    let mut parts = email.splitn(2, '@');
+    let local = parts.next().unwrap_or("");
+    let domain = parts.next().unwrap_or("");
+
+    let valid = !local.is_empty()
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !domain.contains('@')
+        && !email.chars().any(|c| c.is_whitespace());
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ProfileError::InvalidEmail)
+    }
+}
+
+impl User {
+    pub fn profile(&self) -> Profile {
+        Profile {
+            email: self.email.clone(),
+            matriculation_number: self.matriculation_number.clone(),
+            study_program: self.study_program.clone(),
+            semester: self.semester,
+        }
+    }
+
+    /// Stores the given profile in the database. If the matriculation number
+    /// already belongs to another user, `ProfileError::MatriculationNumberTaken`
+    /// is returned and nothing is changed.
+    pub fn set_profile(&mut self, profile: Profile, db: &Db) -> Result<()> {
+        self.set_profile_on(profile, &*db.conn()?)
+    }
+
+    /// Like `set_profile()`, but uses the given connection. This is useful
+    /// to change the profile within a transaction.
+    pub fn set_profile_on(&mut self, profile: Profile, conn: &PgConnection) -> Result<()> {
+        let res = diesel::update(users::table.find(self.id))
+            .set(&profile)
+            .execute(conn);
+
+        match res {
+            Ok(_) => {}
+            Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                bail!(ProfileError::MatriculationNumberTaken);
+            }
+            Err(e) => return Err(e).chain_err(|| "failed to update profile of user"),
+        }
+
+        self.email = profile.email;
+        self.matriculation_number = profile.matriculation_number;
+        self.study_program = profile.study_program;
+        self.semester = profile.semester;
+
+        Ok(())
+    }
+
+    pub fn email(&self) -> Option<&str> {
+        self.email.as_ref().map(AsRef::as_ref)
+    }
+
+    pub fn matriculation_number(&self) -> Option<&str> {
+        self.matriculation_number.as_ref().map(AsRef::as_ref)
+    }
+}
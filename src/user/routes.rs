@@ -1,11 +1,341 @@
 use rocket::State;
+use rocket::http::{Cookie, Cookies, Status};
+use rocket::request::Form;
+use rocket::response::{Flash, Redirect};
 
-use user::AuthUser;
 use db::Db;
+use dict::{self, Locale};
+use errors::*;
+use hex;
+use login::{self, Session};
+use login::password::{self, Password};
+use login::totp::{self, CodeCheck, TotpSecret};
+use super::html;
+use super::profile::{self, ProfileField};
 use template::Page;
+use testat::ical;
+use user::AuthUser;
 
 
 #[get("/settings")]
-pub fn settings(_auth_user: AuthUser, _db: State<Db>) -> Page {
-    Page::unimplemented()
+pub fn settings(auth_user: AuthUser, locale: Locale, db: State<Db>) -> Result<Page> {
+    let sessions = Session::load_all_of_user(&auth_user, &db)?;
+    let two_factor = html::TwoFactorState {
+        enabled: TotpSecret::load(&auth_user, &db)?.is_some(),
+        required: totp::is_required_for(&auth_user, &db)?,
+        recovery_codes: totp::count_recovery_codes(&auth_user, &db)?,
+    };
+
+    let has_password = Password::load(&auth_user, &db)?.is_some();
+    let calendar_token = ical::token_of(&auth_user, &db)?;
+
+    Page::empty()
+        .with_title(dict::new(locale).user.settings_title())
+        .with_content(html::settings(
+            locale,
+            auth_user.session(),
+            &sessions,
+            &two_factor,
+            has_password,
+            &auth_user.profile(),
+            calendar_token.as_ref().map(|t| t.as_str()),
+        ))
+        .make_ok()
+}
+
+#[derive(FromForm)]
+pub struct ProfileForm {
+    email: Option<String>,
+    matriculation_number: Option<String>,
+    study_program: Option<String>,
+    semester: Option<String>,
+}
+
+/// Changes the profile fields the user may change themselves (see
+/// `profile::is_user_editable()`). All other fields are ignored.
+#[post("/settings/profile", data = "<form>")]
+pub fn change_profile(
+    auth_user: AuthUser,
+    form: Form<ProfileForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let form = form.into_inner();
+    let mut user = auth_user.into_user();
+    let mut new_profile = user.profile();
+
+    let inputs = [
+        (ProfileField::Email, &form.email),
+        (ProfileField::MatriculationNumber, &form.matriculation_number),
+        (ProfileField::StudyProgram, &form.study_program),
+        (ProfileField::Semester, &form.semester),
+    ];
+    for &(field, input) in &inputs {
+        if let Some(ref input) = *input {
+            if !profile::is_user_editable(field) {
+                continue;
+            }
+            if let Err(e) = new_profile.set_field(field, input) {
+                return Ok(Flash::error(Redirect::to("/settings"), e.msg(locale)));
+            }
+        }
+    }
+
+    match user.set_profile(new_profile, &db) {
+        Ok(()) => {}
+        Err(Error(ErrorKind::ProfileError(e), _)) => {
+            return Ok(Flash::error(Redirect::to("/settings"), e.msg(locale)));
+        }
+        Err(e) => return Err(e),
+    }
+
+    Ok(Flash::success(
+        Redirect::to("/settings"),
+        dict::new(locale).user.flash_success_profile_saved(),
+    ))
+}
+
+#[derive(FromForm)]
+pub struct ChangePasswordForm {
+    old_password: String,
+    new_password: String,
+    new_password_confirm: String,
+}
+
+/// Changes the internal password of the logged in user. All other sessions
+/// of the user are ended.
+#[post("/settings/password", data = "<form>")]
+pub fn change_password(
+    auth_user: AuthUser,
+    form: Form<ChangePasswordForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).user;
+    let form = form.into_inner();
+
+    // Only users who have an internal password can change it.
+    let old = match Password::load(&auth_user, &db)? {
+        Some(old) => old,
+        None => return Err(ErrorKind::BadHttp(Status::BadRequest).into()),
+    };
+
+    if !old.verify(&form.old_password) {
+        return Ok(Flash::error(
+            Redirect::to("/settings"),
+            dict.flash_err_old_password_incorrect(),
+        ));
+    }
+    if let Err(rejection) = password::check_new_password(
+        auth_user.username(),
+        &form.new_password,
+        &form.new_password_confirm,
+    ) {
+        return Ok(Flash::error(Redirect::to("/settings"), rejection.msg(locale)));
+    }
+
+    Password::set_for(&auth_user, &form.new_password, &db)?;
+    Session::destroy_all_of_user(&auth_user, Some(auth_user.session()), &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/settings"),
+        dict.flash_success_password_changed(),
+    ))
+}
+
+#[derive(FromForm)]
+pub struct RevokeSessionForm {
+    session: String,
+}
+
+/// Ends one session of the logged in user.
+#[post("/settings/revoke_session", data = "<form>")]
+pub fn revoke_session(
+    auth_user: AuthUser,
+    form: Form<RevokeSessionForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).user;
+    let form = form.into_inner();
+
+    // It's not possible to revoke sessions of other users: we only search
+    // through the sessions of the logged in user. The current session isn't
+    // listed with a button (that's the logout) and is kept.
+    if Session::destroy_by_public_id(&auth_user, &form.session, auth_user.session(), &db)? == 0 {
+        return Ok(Flash::error(
+            Redirect::to("/settings"),
+            dict.flash_err_session_not_found(),
+        ));
+    }
+
+    Ok(Flash::success(
+        Redirect::to("/settings"),
+        dict.flash_success_session_revoked(),
+    ))
+}
+
+/// Ends all sessions of the logged in user except the current one.
+#[post("/settings/revoke_other_sessions")]
+pub fn revoke_other_sessions(
+    auth_user: AuthUser,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let count = Session::destroy_all_of_user(&auth_user, Some(auth_user.session()), &db)?;
+
+    Ok(Flash::success(
+        Redirect::to("/settings"),
+        dict::new(locale).user.flash_success_other_sessions_revoked(count as u64),
+    ))
+}
+
+/// The name of the private cookie storing the secret during the setup of
+/// two-factor authentification.
+const TOTP_SETUP_COOKIE_NAME: &str = "two_factor_setup";
+
+/// Shows a QR code to set up two-factor authentification.
+#[get("/settings/two_factor")]
+pub fn two_factor_setup(
+    auth_user: AuthUser,
+    mut cookies: Cookies,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<StdResult<Page, Redirect>> {
+    if TotpSecret::load(&auth_user, &db)?.is_some() {
+        return Ok(Err(Redirect::to("/settings")));
+    }
+
+    // We keep the secret until the setup is finished, so that reloading the
+    // page doesn't invalidate an already scanned QR code.
+    let stored = cookies.get_private(TOTP_SETUP_COOKIE_NAME)
+        .and_then(|cookie| hex::decode(cookie.value()).ok());
+    let secret = match stored {
+        Some(secret) => secret,
+        None => {
+            let secret = totp::generate_secret()?;
+            let cookie = Cookie::build(TOTP_SETUP_COOKIE_NAME, hex::encode(&secret))
+                .path("/settings")
+                .http_only(true)
+                .finish();
+            cookies.add_private(cookie);
+            secret
+        }
+    };
+
+    let content = login::html::two_factor_setup_page(
+        &secret,
+        &auth_user,
+        "/settings/two_factor",
+        locale,
+    )?;
+
+    Ok(Ok(
+        Page::empty()
+            .with_title(dict::new(locale).user.settings_title())
+            .with_content(content)
+    ))
+}
+
+#[derive(FromForm)]
+pub struct TwoFactorCodeForm {
+    code: String,
+}
+
+/// Finishes the setup of two-factor authentification if the user entered a
+/// correct code.
+#[post("/settings/two_factor", data = "<form>")]
+pub fn two_factor_confirm_setup(
+    auth_user: AuthUser,
+    form: Form<TwoFactorCodeForm>,
+    mut cookies: Cookies,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).user;
+
+    let secret = match cookies.get_private(TOTP_SETUP_COOKIE_NAME) {
+        Some(cookie) => hex::decode(cookie.value()).ok(),
+        None => None,
+    };
+    let code = totp::normalize_code(&form.into_inner().code);
+    let step = secret.as_ref().and_then(|secret| totp::verify_code(secret, &code, 0));
+
+    match (secret, step) {
+        (Some(secret), Some(step)) => {
+            TotpSecret::create(&auth_user, secret, step, &db)?;
+            cookies.remove_private(Cookie::build(TOTP_SETUP_COOKIE_NAME, "").path("/settings").finish());
+
+            Ok(Flash::success(
+                Redirect::to("/settings"),
+                dict.flash_success_two_factor_enabled(),
+            ))
+        }
+        _ => {
+            Ok(Flash::error(
+                Redirect::to("/settings/two_factor"),
+                dict.flash_err_two_factor_code_incorrect(),
+            ))
+        }
+    }
+}
+
+/// Generates new recovery codes and shows them. All old codes become invalid.
+#[post("/settings/two_factor/recovery_codes")]
+pub fn two_factor_recovery_codes(
+    auth_user: AuthUser,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<StdResult<Page, Redirect>> {
+    if TotpSecret::load(&auth_user, &db)?.is_none() {
+        return Ok(Err(Redirect::to("/settings")));
+    }
+
+    let codes = totp::generate_recovery_codes(&auth_user, &db)?;
+
+    Ok(Ok(
+        Page::empty()
+            .with_title(dict::new(locale).user.settings_title())
+            .with_content(html::recovery_codes(locale, &codes))
+    ))
+}
+
+/// Disables two-factor authentification. The user has to enter a valid code
+/// to do that.
+#[post("/settings/two_factor/disable", data = "<form>")]
+pub fn two_factor_disable(
+    auth_user: AuthUser,
+    form: Form<TwoFactorCodeForm>,
+    locale: Locale,
+    db: State<Db>,
+) -> Result<Flash<Redirect>> {
+    let dict = dict::new(locale).user;
+
+    if totp::is_required_for(&auth_user, &db)? {
+        return Ok(Flash::error(
+            Redirect::to("/settings"),
+            dict.flash_err_two_factor_required(),
+        ));
+    }
+
+    let mut secret = match TotpSecret::load(&auth_user, &db)? {
+        Some(secret) => secret,
+        None => return Ok(Flash::error(Redirect::to("/settings"), dict.flash_err_two_factor_not_enabled())),
+    };
+
+    match secret.check(&form.into_inner().code, &db)? {
+        CodeCheck::Valid => {
+            TotpSecret::remove_for(&auth_user, &db)?;
+            Ok(Flash::success(
+                Redirect::to("/settings"),
+                dict.flash_success_two_factor_disabled(),
+            ))
+        }
+        CodeCheck::Invalid | CodeCheck::Locked => {
+            Ok(Flash::error(
+                Redirect::to("/settings"),
+                dict.flash_err_two_factor_code_incorrect(),
+            ))
+        }
+    }
 }